@@ -16,3 +16,14 @@ pub const ERROR_NO_TOKEN_TO_MERGE: &[u8] = b"No token to merge";
 pub const ERROR_PAYMENT_FAILED: &[u8] = b"Payment failed";
 pub const ERROR_PERMISSIONS: &[u8] = b"Permission denied";
 pub const ERROR_PARAMETERS: &[u8] = b"Bad parameters";
+pub const ERROR_FUNDED_REWARDS_DISABLED: &[u8] = b"Funded rewards mode not enabled";
+pub const ERROR_PRODUCING_REWARDS: &[u8] = b"Rewards are being produced";
+pub const ERROR_UNKNOWN_LOCK_OPTION: &[u8] = b"Unknown lock option";
+pub const ERROR_BAD_REWARD_MULTIPLIER: &[u8] = b"Bad reward multiplier";
+pub const ERROR_BAD_UNLOCK_MILESTONES: &[u8] = b"Bad unlock milestones";
+pub const ERROR_NO_MIGRATION_TARGET: &[u8] = b"No migration target";
+pub const ERROR_FARM_MIGRATING: &[u8] = b"Farm is migrating";
+pub const ERROR_UNKNOWN_MIGRATION_SOURCE: &[u8] = b"Unknown migration source";
+pub const ERROR_BAD_BLOCK_TIME: &[u8] = b"Bad block time";
+pub const ERROR_FUNDED_REWARDS_REMAINING: &[u8] = b"Funded rewards remaining";
+pub const ERROR_NOT_ENOUGH_FUNDED_REWARDS: &[u8] = b"Not enough funded rewards";
@@ -61,6 +61,14 @@ pub struct WrappedLpTokenAttributes<M: ManagedTypeApi> {
     pub locked_assets_nonce: Nonce,
 }
 
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct WrappedSwapTokenAttributes<M: ManagedTypeApi> {
+    pub token_id: TokenIdentifier<M>,
+    pub token_total_amount: BigUint<M>,
+    pub locked_assets_invested: BigUint<M>,
+    pub locked_assets_nonce: Nonce,
+}
+
 #[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
 pub struct WrappedFarmTokenAttributes<M: ManagedTypeApi> {
     pub farm_token_id: TokenIdentifier<M>,
@@ -144,3 +152,40 @@ pub struct LockedAssetTokenAttributesEx<M: ManagedTypeApi> {
     pub unlock_schedule: UnlockScheduleEx<M>,
     pub is_merged: bool,
 }
+
+/// Unlocks continuously between `start_epoch` and `end_epoch`.
+/// Nothing is unlockable before `cliff_epoch`, which is `start_epoch` when there is no cliff.
+#[derive(
+    ManagedVecItem,
+    TopEncode,
+    TopDecode,
+    NestedEncode,
+    NestedDecode,
+    TypeAbi,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+)]
+pub struct LinearVestingSchedule {
+    pub start_epoch: u64,
+    pub cliff_epoch: u64,
+    pub end_epoch: u64,
+}
+
+#[derive(
+    ManagedVecItem,
+    TopEncode,
+    TopDecode,
+    NestedEncode,
+    NestedDecode,
+    TypeAbi,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+)]
+pub struct LinearVestingTokenAttributes {
+    pub vesting_schedule: LinearVestingSchedule,
+    pub is_merged: bool,
+}
@@ -0,0 +1,207 @@
+#![no_std]
+
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_errors::*;
+
+use common_structs::Nonce;
+use config::DEFAULT_NFT_DEPOSIT_MAX_LEN;
+
+#[elrond_wasm::module]
+pub trait ClaimDelegationModule:
+    config::ConfigModule + token_send::TokenSendModule + farm_token::FarmTokenModule
+{
+    /// Sends the rewards of the caller's claims to the given recipient.
+    /// Exits and migrations still pay the rewards to the caller.
+    #[endpoint(setRewardRecipient)]
+    fn set_reward_recipient(&self, recipient: ManagedAddress) {
+        require!(!recipient.is_zero(), ERROR_PARAMETERS);
+
+        let caller = self.blockchain().get_caller();
+        self.reward_recipient(&caller).set(&recipient);
+    }
+
+    #[endpoint(clearRewardRecipient)]
+    fn clear_reward_recipient(&self) {
+        let caller = self.blockchain().get_caller();
+        self.reward_recipient(&caller).clear();
+    }
+
+    #[endpoint(authorizeClaimer)]
+    fn authorize_claimer(&self, claimer: ManagedAddress) {
+        let caller = self.blockchain().get_caller();
+        let is_new = self.authorized_claimers(&caller).insert(claimer);
+        require!(is_new, "Claimer already authorized");
+    }
+
+    #[endpoint(revokeClaimer)]
+    fn revoke_claimer(&self, claimer: ManagedAddress) {
+        let caller = self.blockchain().get_caller();
+        let is_removed = self.authorized_claimers(&caller).remove(&claimer);
+        require!(is_removed, "Claimer not authorized");
+    }
+
+    /// Positions in custody still belong to the caller, so they are kept in the position index.
+    #[payable("*")]
+    #[endpoint(depositInCustody)]
+    fn deposit_in_custody(&self, #[var_args] opt_reward_recipient: OptionalArg<ManagedAddress>) {
+        let caller = self.blockchain().get_caller();
+        let payments = self.call_value().all_esdt_transfers();
+        require!(!payments.is_empty(), ERROR_EMPTY_PAYMENTS);
+
+        let farm_token_id = self.farm_token_id().get();
+        let opt_reward_recipient = opt_reward_recipient.into_option();
+        for payment in payments.iter() {
+            require!(
+                payment.token_identifier == farm_token_id && payment.token_nonce != 0,
+                ERROR_NOT_A_FARM_TOKEN
+            );
+            require!(payment.amount != 0u64, ERROR_ZERO_AMOUNT);
+
+            let mut custody_positions = self.custody_positions(&caller);
+            let new_amount = match custody_positions.get(&payment.token_nonce) {
+                Some(amount) => amount + &payment.amount,
+                None => payment.amount.clone(),
+            };
+            custody_positions.insert(payment.token_nonce, new_amount);
+
+            if let Some(recipient) = &opt_reward_recipient {
+                self.position_reward_recipient(&caller, payment.token_nonce)
+                    .set(recipient);
+            }
+        }
+
+        require!(
+            self.custody_positions(&caller).len() <= DEFAULT_NFT_DEPOSIT_MAX_LEN,
+            "Too many positions in custody"
+        );
+    }
+
+    #[endpoint(withdrawFromCustody)]
+    fn withdraw_from_custody(&self) -> ManagedMultiResultVec<EsdtTokenPayment<Self::Api>> {
+        let caller = self.blockchain().get_caller();
+        let positions = self.get_custody_positions_vec(&caller);
+        require!(!positions.is_empty(), "No positions in custody");
+
+        for position in positions.iter() {
+            self.remove_custody_position(&caller, position.token_nonce);
+        }
+        self.send_multiple_tokens(&caller, &positions, &OptionalArg::None);
+
+        positions.into()
+    }
+
+    fn get_custody_positions_vec(
+        &self,
+        owner: &ManagedAddress,
+    ) -> ManagedVec<EsdtTokenPayment<Self::Api>> {
+        let farm_token_id = self.farm_token_id().get();
+        let mut result = ManagedVec::new();
+        for (nonce, amount) in self.custody_positions(owner).iter() {
+            result.push(self.create_payment(&farm_token_id, nonce, &amount));
+        }
+        result
+    }
+
+    fn remove_custody_position(&self, owner: &ManagedAddress, nonce: Nonce) {
+        self.custody_positions(owner).remove(&nonce);
+        self.position_reward_recipient(owner, nonce).clear();
+    }
+
+    /// Replaces a position held in custody with the one resulted after claiming,
+    /// keeping the reward recipient configured for the old position.
+    fn replace_custody_position(
+        &self,
+        owner: &ManagedAddress,
+        old_nonce: Nonce,
+        new_position: &EsdtTokenPayment<Self::Api>,
+    ) {
+        let position_recipient_mapper = self.position_reward_recipient(owner, old_nonce);
+        let opt_position_recipient = if position_recipient_mapper.is_empty() {
+            None
+        } else {
+            Some(position_recipient_mapper.get())
+        };
+        self.remove_custody_position(owner, old_nonce);
+
+        let mut custody_positions = self.custody_positions(owner);
+        let new_amount = match custody_positions.get(&new_position.token_nonce) {
+            Some(amount) => amount + &new_position.amount,
+            None => new_position.amount.clone(),
+        };
+        custody_positions.insert(new_position.token_nonce, new_amount);
+
+        if let Some(recipient) = opt_position_recipient {
+            self.position_reward_recipient(owner, new_position.token_nonce)
+                .set(&recipient);
+        }
+    }
+
+    fn require_can_claim_on_behalf(&self, owner: &ManagedAddress, claimer: &ManagedAddress) {
+        require!(
+            owner == claimer || self.authorized_claimers(owner).contains(claimer),
+            ERROR_PERMISSIONS
+        );
+    }
+
+    fn get_reward_destination(&self, owner: &ManagedAddress) -> ManagedAddress {
+        let recipient_mapper = self.reward_recipient(owner);
+        if recipient_mapper.is_empty() {
+            owner.clone()
+        } else {
+            recipient_mapper.get()
+        }
+    }
+
+    fn get_custody_reward_destination(
+        &self,
+        owner: &ManagedAddress,
+        nonce: Nonce,
+    ) -> ManagedAddress {
+        let position_recipient_mapper = self.position_reward_recipient(owner, nonce);
+        if position_recipient_mapper.is_empty() {
+            self.get_reward_destination(owner)
+        } else {
+            position_recipient_mapper.get()
+        }
+    }
+
+    #[view(getCustodyPositions)]
+    fn get_custody_positions(
+        &self,
+        owner: ManagedAddress,
+    ) -> ManagedMultiResultVec<EsdtTokenPayment<Self::Api>> {
+        self.get_custody_positions_vec(&owner).into()
+    }
+
+    #[view(getAuthorizedClaimers)]
+    fn get_authorized_claimers(
+        &self,
+        owner: ManagedAddress,
+    ) -> ManagedMultiResultVec<ManagedAddress> {
+        let mut result = ManagedMultiResultVec::new();
+        for claimer in self.authorized_claimers(&owner).iter() {
+            result.push(claimer);
+        }
+        result
+    }
+
+    #[view(getRewardRecipient)]
+    #[storage_mapper("reward_recipient")]
+    fn reward_recipient(&self, owner: &ManagedAddress) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getPositionRewardRecipient)]
+    #[storage_mapper("position_reward_recipient")]
+    fn position_reward_recipient(
+        &self,
+        owner: &ManagedAddress,
+        nonce: Nonce,
+    ) -> SingleValueMapper<ManagedAddress>;
+
+    #[storage_mapper("authorized_claimers")]
+    fn authorized_claimers(&self, owner: &ManagedAddress) -> SetMapper<ManagedAddress>;
+
+    #[storage_mapper("custody_positions")]
+    fn custody_positions(&self, owner: &ManagedAddress) -> MapMapper<Nonce, BigUint>;
+}
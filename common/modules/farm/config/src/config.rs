@@ -13,6 +13,9 @@ pub const DEFAULT_MINUMUM_FARMING_EPOCHS: u8 = 3;
 pub const DEFAULT_TRANSFER_EXEC_GAS_LIMIT: u64 = 35_000_000;
 pub const DEFAULT_BURN_GAS_LIMIT: u64 = 50_000_000;
 pub const DEFAULT_NFT_DEPOSIT_MAX_LEN: usize = 10;
+pub const SECONDS_IN_DAY: u64 = 86_400;
+pub const SECONDS_IN_YEAR: u64 = 31_536_000;
+pub const DEFAULT_BLOCK_TIME_SECONDS: u64 = 6;
 
 #[derive(TopEncode, TopDecode, PartialEq, TypeAbi)]
 pub enum State {
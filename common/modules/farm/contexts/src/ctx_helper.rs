@@ -16,8 +16,17 @@ pub trait CtxHelper:
         opt_accept_funds_func: OptionalArg<ManagedBuffer>,
     ) -> GenericContext<Self::Api> {
         let caller = self.blockchain().get_caller();
-
         let payments = self.call_value().all_esdt_transfers();
+
+        self.new_farm_context_from_payments(caller, payments, opt_accept_funds_func)
+    }
+
+    fn new_farm_context_from_payments(
+        &self,
+        caller: ManagedAddress,
+        payments: ManagedVec<EsdtTokenPayment<Self::Api>>,
+        opt_accept_funds_func: OptionalArg<ManagedBuffer>,
+    ) -> GenericContext<Self::Api> {
         let mut payments_iter = payments.iter();
 
         let first_payment = payments_iter.next().unwrap();
@@ -0,0 +1,311 @@
+#![no_std]
+
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_errors::*;
+
+use common_structs::{Epoch, FarmTokenAttributes};
+use config::State;
+use contexts::generic::GenericContext;
+use farm_token::FarmToken;
+use migration_from_v1_2::FarmTokenAttributesV1_2;
+
+pub mod farm_migration_target_proxy {
+    elrond_wasm::imports!();
+
+    #[elrond_wasm::proxy]
+    pub trait FarmMigrationTarget {
+        #[payable("*")]
+        #[endpoint(acceptMigratedPosition)]
+        fn accept_migrated_position(
+            &self,
+            source_attributes: ManagedBuffer,
+            orig_caller: ManagedAddress,
+        ) -> EsdtTokenPayment<Self::Api>;
+    }
+}
+
+/// Layouts of farm token attributes that positions can be migrated from.
+/// A new variant, and its translator, is added whenever the layout changes.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum FarmAttributesVersion {
+    V1_2,
+    V1_3,
+}
+
+/// What a target farm keeps from a migrated position, whatever its source layout.
+/// Rewards are settled by the source farm, so the reward per share is not carried over.
+pub struct MigratedPositionEpochs {
+    pub entering_epoch: Epoch,
+    pub original_entering_epoch: Epoch,
+}
+
+#[elrond_wasm::module]
+pub trait FarmMigrationModule:
+    config::ConfigModule
+    + token_send::TokenSendModule
+    + rewards::RewardsModule
+    + farm_token::FarmTokenModule
+    + token_merge::TokenMergeModule
+    + contexts::ctx_helper::CtxHelper
+    + position_index::PositionIndexModule
+{
+    /// Registers the farm that the positions of this farm are moved to.
+    /// From now on, entering this farm is disabled and users can move their positions
+    /// with `migratePosition`, while the owner can move the ones held in custody.
+    #[only_owner]
+    #[endpoint(setMigrationTarget)]
+    fn set_migration_target(&self, target_address: ManagedAddress) {
+        require!(
+            target_address != self.blockchain().get_sc_address(),
+            ERROR_PARAMETERS
+        );
+        require!(
+            self.blockchain().is_smart_contract(&target_address),
+            ERROR_PARAMETERS
+        );
+
+        self.migration_target().set(&target_address);
+    }
+
+    /// Allows a farm to move its positions into this one.
+    /// Their attributes are translated according to the given layout version.
+    #[only_owner]
+    #[endpoint(addMigrationSource)]
+    fn add_migration_source(
+        &self,
+        source_address: ManagedAddress,
+        attributes_version: FarmAttributesVersion,
+    ) {
+        require!(
+            source_address != self.blockchain().get_sc_address(),
+            ERROR_PARAMETERS
+        );
+
+        self.migration_sources()
+            .insert(source_address, attributes_version);
+    }
+
+    #[only_owner]
+    #[endpoint(removeMigrationSource)]
+    fn remove_migration_source(&self, source_address: ManagedAddress) {
+        let removed = self.migration_sources().remove(&source_address);
+        require!(removed.is_some(), ERROR_UNKNOWN_MIGRATION_SOURCE);
+    }
+
+    /// Returns the farm token amount moved to the target and the total,
+    /// which also includes the positions still left in this farm.
+    #[view(getMigrationProgress)]
+    fn get_migration_progress(&self) -> MultiResult2<BigUint, BigUint> {
+        let migrated_supply = self.migrated_farm_token_supply().get();
+        let total_supply = &migrated_supply + &self.farm_token_supply().get();
+
+        (migrated_supply, total_supply).into()
+    }
+
+    #[view(getMigrationSourceVersion)]
+    fn get_migration_source_version(
+        &self,
+        source_address: ManagedAddress,
+    ) -> OptionalResult<FarmAttributesVersion> {
+        match self.migration_sources().get(&source_address) {
+            Some(attributes_version) => OptionalResult::Some(attributes_version),
+            None => OptionalResult::None,
+        }
+    }
+
+    fn require_not_migrating(&self) {
+        require!(self.migration_target().is_empty(), ERROR_FARM_MIGRATING);
+    }
+
+    /// Returns the attributes of a farm token as stored on chain,
+    /// so that the target farm decodes them with the layout of this farm.
+    fn get_raw_position_attributes(&self, position: &EsdtTokenPayment<Self::Api>) -> ManagedBuffer {
+        self.blockchain()
+            .get_esdt_token_data(
+                &self.blockchain().get_sc_address(),
+                &position.token_identifier,
+                position.token_nonce,
+            )
+            .attributes
+    }
+
+    /// Sends the farming tokens of a position to the target farm, which mints
+    /// the new position to `orig_caller`. `farm_amount` is the amount of farm tokens
+    /// that were burned for it and is accounted as migrated.
+    fn forward_position_to_migration_target(
+        &self,
+        farming_tokens: EsdtTokenPayment<Self::Api>,
+        source_attributes: ManagedBuffer,
+        farm_amount: &BigUint,
+        orig_caller: ManagedAddress,
+    ) -> EsdtTokenPayment<Self::Api> {
+        require!(
+            !self.migration_target().is_empty(),
+            ERROR_NO_MIGRATION_TARGET
+        );
+        let target_address = self.migration_target().get();
+
+        self.migrated_farm_token_supply()
+            .update(|x| *x += farm_amount);
+
+        self.farm_migration_target_proxy(target_address)
+            .accept_migrated_position(source_attributes, orig_caller)
+            .add_token_transfer(
+                farming_tokens.token_identifier,
+                farming_tokens.token_nonce,
+                farming_tokens.amount,
+            )
+            .execute_on_dest_context_custom_range(|_, after| (after - 1, after))
+    }
+
+    /// Decodes the attributes sent by a registered source farm using the layout
+    /// it was registered with. The source farm is the caller of `acceptMigratedPosition`.
+    fn translate_migrated_attributes(
+        &self,
+        source_address: &ManagedAddress,
+        source_attributes: &ManagedBuffer,
+    ) -> MigratedPositionEpochs {
+        let opt_version = self.migration_sources().get(source_address);
+        require!(opt_version.is_some(), ERROR_UNKNOWN_MIGRATION_SOURCE);
+
+        match opt_version.unwrap() {
+            FarmAttributesVersion::V1_2 => {
+                let attributes = self
+                    .serializer()
+                    .top_decode_from_managed_buffer::<FarmTokenAttributesV1_2<Self::Api>>(
+                        source_attributes,
+                    );
+                MigratedPositionEpochs {
+                    entering_epoch: attributes.entering_epoch,
+                    original_entering_epoch: attributes.original_entering_epoch,
+                }
+            }
+            FarmAttributesVersion::V1_3 => {
+                let attributes = self
+                    .serializer()
+                    .top_decode_from_managed_buffer::<FarmTokenAttributes<Self::Api>>(
+                        source_attributes,
+                    );
+                MigratedPositionEpochs {
+                    entering_epoch: attributes.entering_epoch,
+                    original_entering_epoch: attributes.original_entering_epoch,
+                }
+            }
+        }
+    }
+
+    /// Builds the context of `acceptMigratedPosition` for the single payment of farming tokens.
+    /// The rewards are aggregated by the farm afterwards, as each farm generates them its own way.
+    fn new_migrated_position_context(
+        &self,
+        orig_caller: ManagedAddress,
+    ) -> GenericContext<Self::Api> {
+        self.require_not_migrating();
+
+        let payments = self.call_value().all_esdt_transfers();
+        require!(payments.len() == 1, ERROR_BAD_PAYMENTS_LEN);
+        let mut context =
+            self.new_farm_context_from_payments(orig_caller, payments, OptionalArg::None);
+
+        self.load_state(&mut context);
+        require!(
+            context.get_contract_state().unwrap() == &State::Active,
+            ERROR_NOT_ACTIVE
+        );
+
+        self.load_farm_token_id(&mut context);
+        require!(
+            !context.get_farm_token_id().unwrap().is_empty(),
+            ERROR_NO_FARM_TOKEN
+        );
+
+        self.load_farming_token_id(&mut context);
+        require!(context.is_accepted_payment_enter(), ERROR_BAD_PAYMENTS);
+
+        self.load_reward_token_id(&mut context);
+        self.load_reward_reserve(&mut context);
+        self.load_block_nonce(&mut context);
+        self.load_block_epoch(&mut context);
+        self.load_reward_per_share(&mut context);
+        self.load_farm_token_supply(&mut context);
+        self.load_division_safety_constant(&mut context);
+
+        context
+    }
+
+    /// Mints the position moved from the calling source farm, with its entering epochs
+    /// and the current reward per share, and sends it to the caller of the context.
+    fn create_migrated_position(
+        &self,
+        context: &mut GenericContext<Self::Api>,
+        source_attributes: &ManagedBuffer,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let source_address = self.blockchain().get_caller();
+        let position_epochs =
+            self.translate_migrated_attributes(&source_address, source_attributes);
+
+        let farming_amount = context
+            .get_tx_input()
+            .get_payments()
+            .get_first()
+            .amount
+            .clone();
+        let attributes = FarmTokenAttributes {
+            reward_per_share: context.get_reward_per_share().unwrap().clone(),
+            entering_epoch: position_epochs.entering_epoch,
+            original_entering_epoch: position_epochs.original_entering_epoch,
+            initial_farming_amount: farming_amount.clone(),
+            compounded_reward: BigUint::zero(),
+            current_farm_amount: farming_amount.clone(),
+        };
+
+        let farm_token_id = context.get_farm_token_id().unwrap().clone();
+        let new_nonce = self.mint_farm_tokens(&farm_token_id, &farming_amount, &attributes);
+        let new_position = self.create_payment(&farm_token_id, new_nonce, &farming_amount);
+        context.set_output_position(
+            FarmToken {
+                token_amount: new_position.clone(),
+                attributes,
+            },
+            false,
+        );
+
+        self.commit_changes(context);
+        self.record_migrated_position(&source_address, &farming_amount);
+        self.execute_output_payments(context);
+        self.update_position_index(context);
+
+        new_position
+    }
+
+    fn record_migrated_position(&self, source_address: &ManagedAddress, farming_amount: &BigUint) {
+        self.migrated_farming_amount(source_address)
+            .update(|x| *x += farming_amount);
+    }
+
+    #[proxy]
+    fn farm_migration_target_proxy(
+        &self,
+        to: ManagedAddress,
+    ) -> farm_migration_target_proxy::Proxy<Self::Api>;
+
+    #[view(getMigrationTarget)]
+    #[storage_mapper("migration_target")]
+    fn migration_target(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getMigratedFarmTokenSupply)]
+    #[storage_mapper("migrated_farm_token_supply")]
+    fn migrated_farm_token_supply(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("migration_sources")]
+    fn migration_sources(&self) -> MapMapper<ManagedAddress, FarmAttributesVersion>;
+
+    #[view(getMigratedFarmingAmount)]
+    #[storage_mapper("migrated_farming_amount")]
+    fn migrated_farming_amount(
+        &self,
+        source_address: &ManagedAddress,
+    ) -> SingleValueMapper<BigUint>;
+}
@@ -6,6 +6,7 @@ elrond_wasm::derive_imports!();
 
 use common_structs::{FarmTokenAttributes, Nonce};
 use elrond_wasm::elrond_codec::TopEncode;
+use token_metadata::{PositionDisplayAttributes, PositionEnteringEpoch};
 
 #[derive(ManagedVecItem, Clone)]
 pub struct FarmToken<M: ManagedTypeApi> {
@@ -14,7 +15,9 @@ pub struct FarmToken<M: ManagedTypeApi> {
 }
 
 #[elrond_wasm::module]
-pub trait FarmTokenModule: config::ConfigModule + token_send::TokenSendModule {
+pub trait FarmTokenModule:
+    config::ConfigModule + token_send::TokenSendModule + token_metadata::TokenMetadataModule
+{
     #[only_owner]
     #[payable("EGLD")]
     #[endpoint(registerFarmToken)]
@@ -153,13 +156,20 @@ pub trait FarmTokenModule: config::ConfigModule + token_send::TokenSendModule {
         self.farm_token_supply().update(|x| *x -= total_amount);
     }
 
-    fn mint_farm_tokens<T: TopEncode>(
+    fn mint_farm_tokens<
+        T: TopEncode + PositionEnteringEpoch + PositionDisplayAttributes<Self::Api>,
+    >(
         &self,
         token_id: &TokenIdentifier,
         amount: &BigUint,
         attributes: &T,
     ) -> u64 {
-        let new_nonce = self.nft_create_tokens(token_id, amount, attributes);
+        let new_nonce = self.create_position_tokens(
+            token_id,
+            amount,
+            attributes,
+            attributes.get_entering_epoch(),
+        );
         self.farm_token_supply().update(|x| *x += amount);
         new_nonce
     }
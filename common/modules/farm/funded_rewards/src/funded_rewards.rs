@@ -0,0 +1,182 @@
+#![no_std]
+
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_errors::*;
+
+use common_structs::Nonce;
+
+#[elrond_wasm::module]
+pub trait FundedRewardsModule:
+    config::ConfigModule
+    + token_send::TokenSendModule
+    + farm_token::FarmTokenModule
+    + rewards::RewardsModule
+{
+    fn allocate_funded_rewards(&self, amount: BigUint) -> BigUint {
+        let remaining_before = self.get_remaining_funded_rewards();
+        let allocated = if amount > remaining_before {
+            remaining_before.clone()
+        } else {
+            amount
+        };
+        if allocated == 0u64 {
+            return allocated;
+        }
+
+        self.funded_rewards_distributed()
+            .update(|distributed| *distributed += &allocated);
+
+        let remaining_after = &remaining_before - &allocated;
+        let threshold = self.low_reward_reserve_threshold().get();
+        if remaining_before >= threshold && remaining_after < threshold {
+            self.low_reward_reserve_event(
+                &self.reward_token_id().get(),
+                &remaining_after,
+                &threshold,
+            );
+        }
+
+        allocated
+    }
+
+    /// Rewards that would be emitted between the two block nonces, without allocating them.
+    fn calculate_emitted_rewards(
+        &self,
+        current_block_nonce: Nonce,
+        last_reward_block_nonce: Nonce,
+    ) -> BigUint {
+        let rewards =
+            self.calculate_per_block_rewards(current_block_nonce, last_reward_block_nonce);
+        if !self.funded_rewards_enabled().get() {
+            return rewards;
+        }
+
+        let remaining = self.get_remaining_funded_rewards();
+        if rewards > remaining {
+            remaining
+        } else {
+            rewards
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setFundedRewardsMode)]
+    fn set_funded_rewards_mode(&self, enabled: bool) {
+        require!(!self.produces_per_block_rewards(), ERROR_PRODUCING_REWARDS);
+        if !enabled {
+            require!(
+                self.get_remaining_funded_rewards() == 0u64,
+                ERROR_FUNDED_REWARDS_REMAINING
+            );
+        }
+
+        self.funded_rewards_enabled().set(&enabled);
+    }
+
+    #[only_owner]
+    #[endpoint(setLowRewardReserveThreshold)]
+    fn set_low_reward_reserve_threshold(&self, threshold: BigUint) {
+        self.low_reward_reserve_threshold().set(&threshold);
+    }
+
+    /// Deposits reward tokens in the funded reserve.
+    /// Rewards are allocated from it while the funded mode is enabled.
+    #[payable("*")]
+    #[endpoint(topUpRewards)]
+    fn top_up_rewards(&self) {
+        require!(
+            self.funded_rewards_enabled().get(),
+            ERROR_FUNDED_REWARDS_DISABLED
+        );
+        let (payment_amount, payment_token) = self.call_value().payment_token_pair();
+        require!(
+            payment_token == self.reward_token_id().get(),
+            ERROR_BAD_INPUT_TOKEN
+        );
+        require!(payment_amount != 0u64, ERROR_ZERO_AMOUNT);
+
+        self.funded_reward_capacity()
+            .update(|capacity| *capacity += &payment_amount);
+    }
+
+    /// Sends back to the owner the part of the funded reserve not allocated as rewards yet.
+    /// The rewards emitted since the last aggregation are kept for the farmers.
+    #[only_owner]
+    #[endpoint(withdrawFundedRewards)]
+    fn withdraw_funded_rewards(&self, amount: BigUint) {
+        require!(amount != 0u64, ERROR_ZERO_AMOUNT);
+        let pending = self.calculate_emitted_rewards(
+            self.blockchain().get_block_nonce(),
+            self.last_reward_block_nonce().get(),
+        );
+        require!(
+            &amount + &pending <= self.get_remaining_funded_rewards(),
+            ERROR_NOT_ENOUGH_FUNDED_REWARDS
+        );
+
+        self.funded_reward_capacity()
+            .update(|capacity| *capacity -= &amount);
+
+        let caller = self.blockchain().get_caller();
+        self.send()
+            .direct(&caller, &self.reward_token_id().get(), 0, &amount, &[]);
+    }
+
+    #[view(getRemainingFundedRewards)]
+    fn get_remaining_funded_rewards(&self) -> BigUint {
+        self.funded_reward_capacity().get() - self.funded_rewards_distributed().get()
+    }
+
+    /// Number of blocks the funded reserve can still cover at the current per block rate,
+    /// taking into account the rewards not yet aggregated.
+    #[view(getRemainingRewardBlocks)]
+    fn get_remaining_reward_blocks(&self) -> u64 {
+        require!(
+            self.funded_rewards_enabled().get(),
+            ERROR_FUNDED_REWARDS_DISABLED
+        );
+
+        let per_block_reward_amount = self.per_block_reward_amount().get();
+        if per_block_reward_amount == 0u64 {
+            return 0;
+        }
+
+        let remaining = self.get_remaining_funded_rewards();
+        let current_block_nonce = self.blockchain().get_block_nonce();
+        let last_reward_nonce = self.last_reward_block_nonce().get();
+        let pending = self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
+        if pending >= remaining {
+            return 0;
+        }
+
+        ((remaining - pending) / per_block_reward_amount)
+            .to_u64()
+            .unwrap_or(u64::MAX)
+    }
+
+    #[event("low_reward_reserve")]
+    fn low_reward_reserve_event(
+        &self,
+        #[indexed] reward_token: &TokenIdentifier,
+        #[indexed] remaining_rewards: &BigUint,
+        threshold: &BigUint,
+    );
+
+    #[view(isFundedRewardsModeEnabled)]
+    #[storage_mapper("funded_rewards_enabled")]
+    fn funded_rewards_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[view(getFundedRewardCapacity)]
+    #[storage_mapper("funded_reward_capacity")]
+    fn funded_reward_capacity(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getFundedRewardsDistributed)]
+    #[storage_mapper("funded_rewards_distributed")]
+    fn funded_rewards_distributed(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getLowRewardReserveThreshold)]
+    #[storage_mapper("low_reward_reserve_threshold")]
+    fn low_reward_reserve_threshold(&self) -> SingleValueMapper<BigUint>;
+}
@@ -25,6 +25,7 @@ pub trait MigrationModule:
     + token_send::TokenSendModule
     + farm_token::FarmTokenModule
     + rewards::RewardsModule
+    + token_metadata::TokenMetadataModule
 {
     #[payable("*")]
     #[endpoint(migrateFromV1_2Farm)]
@@ -63,7 +64,7 @@ pub trait MigrationModule:
         let new_pos_amount = farming_tokens.amount.clone();
 
         //Note that this function does not modify the farm supply
-        let new_pos_nonce = self.nft_create_tokens(
+        let new_pos_nonce = self.create_position_tokens(
             &new_pos_token_id,
             &new_pos_amount,
             &FarmTokenAttributes {
@@ -74,6 +75,7 @@ pub trait MigrationModule:
                 compounded_reward: BigUint::zero(),
                 current_farm_amount: new_pos_amount.clone(),
             },
+            Some(old_attrs.entering_epoch),
         );
 
         // Use this function since it works regardless of wasm ocasional unalignment.
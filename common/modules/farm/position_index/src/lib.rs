@@ -0,0 +1,116 @@
+#![no_std]
+#![feature(generic_associated_types)]
+
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_structs::Nonce;
+use contexts::generic::GenericContext;
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct IndexedPosition<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub reward_per_share: BigUint<M>,
+}
+
+#[derive(ManagedVecItem, TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct UserPosition<M: ManagedTypeApi> {
+    pub token_nonce: Nonce,
+    pub amount: BigUint<M>,
+    pub pending_rewards: BigUint<M>,
+}
+
+/// Keeps track of the farm tokens sent to each user, so that their positions
+/// can be listed without scanning their wallets. Farm tokens are transferable,
+/// so the index only reflects the positions received from and returned to the farm.
+#[elrond_wasm::module]
+pub trait PositionIndexModule: config::ConfigModule + token_send::TokenSendModule {
+    #[only_owner]
+    #[endpoint(setPositionIndexEnabled)]
+    fn set_position_index_enabled(&self, enabled: bool) {
+        self.position_index_enabled().set(&enabled);
+    }
+
+    /// Removes the farm tokens paid by the caller and adds the new position sent back, if any.
+    fn update_position_index(&self, context: &GenericContext<Self::Api>) {
+        if !self.position_index_enabled().get() {
+            return;
+        }
+
+        let user = context.get_caller();
+        let payments = context.get_tx_input().get_payments();
+        self.unindex_position(user, payments.get_first());
+        if let Some(additional_payments) = payments.get_additional() {
+            for payment in additional_payments.iter() {
+                self.unindex_position(user, &payment);
+            }
+        }
+
+        if let Some(attributes) = context.get_output_attributes() {
+            let new_position = context.get_output_payments().get(0);
+            self.index_position(user, &new_position, &attributes.reward_per_share);
+        }
+    }
+
+    fn unindex_positions(
+        &self,
+        user: &ManagedAddress,
+        payments: &ManagedVec<EsdtTokenPayment<Self::Api>>,
+    ) {
+        if !self.position_index_enabled().get() {
+            return;
+        }
+
+        for payment in payments.iter() {
+            self.unindex_position(user, &payment);
+        }
+    }
+
+    fn unindex_position(&self, user: &ManagedAddress, payment: &EsdtTokenPayment<Self::Api>) {
+        if payment.token_identifier != self.farm_token_id().get() {
+            return;
+        }
+
+        let mut user_positions = self.user_positions(user);
+        if let Some(mut position) = user_positions.get(&payment.token_nonce) {
+            if position.amount > payment.amount {
+                position.amount -= &payment.amount;
+                user_positions.insert(payment.token_nonce, position);
+            } else {
+                user_positions.remove(&payment.token_nonce);
+            }
+        }
+    }
+
+    fn index_position(
+        &self,
+        user: &ManagedAddress,
+        position: &EsdtTokenPayment<Self::Api>,
+        reward_per_share: &BigUint,
+    ) {
+        if !self.position_index_enabled().get() {
+            return;
+        }
+
+        let mut user_positions = self.user_positions(user);
+        let amount = match user_positions.get(&position.token_nonce) {
+            Some(indexed_position) => indexed_position.amount + &position.amount,
+            None => position.amount.clone(),
+        };
+        user_positions.insert(
+            position.token_nonce,
+            IndexedPosition {
+                amount,
+                reward_per_share: reward_per_share.clone(),
+            },
+        );
+    }
+
+    #[view(isPositionIndexEnabled)]
+    #[storage_mapper("position_index_enabled")]
+    fn position_index_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[storage_mapper("user_positions")]
+    fn user_positions(&self, user: &ManagedAddress)
+        -> MapMapper<Nonce, IndexedPosition<Self::Api>>;
+}
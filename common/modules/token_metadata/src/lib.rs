@@ -0,0 +1,308 @@
+#![no_std]
+
+elrond_wasm::imports!();
+
+use common_structs::{
+    Epoch, FarmTokenAttributes, LinearVestingTokenAttributes, WrappedFarmTokenAttributes,
+    WrappedLpTokenAttributes, WrappedSwapTokenAttributes,
+};
+use elrond_wasm::elrond_codec::TopEncode;
+
+const NAME_EPOCH_SEPARATOR: &[u8] = b" - epoch ";
+const URI_EPOCH_SEPARATOR: &[u8] = b"/";
+const ATTRIBUTES_URI_PREFIX: &[u8] = b"data:application/json,";
+const DECIMAL_CHUNK: u64 = 1_000_000_000_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 18;
+const PERCENT_ENCODE_CHUNK_LEN: usize = 32;
+const HEX_DIGITS: &[u8] = b"0123456789ABCDEF";
+
+/// Implemented by position attributes that keep track of the epoch the position was entered in.
+/// Positions that don't are named after the epoch they are minted in.
+pub trait PositionEnteringEpoch {
+    fn get_entering_epoch(&self) -> Option<Epoch>;
+}
+
+impl<M: ManagedTypeApi> PositionEnteringEpoch for FarmTokenAttributes<M> {
+    fn get_entering_epoch(&self) -> Option<Epoch> {
+        Some(self.entering_epoch)
+    }
+}
+
+/// Implemented by position attributes, so that wallets and explorers can show their fields
+/// without decoding the raw attributes.
+pub trait PositionDisplayAttributes<M: ManagedTypeApi> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>);
+}
+
+/// Writes the display attributes as a flat JSON object.
+/// Amounts are written as strings, since they can exceed the JSON number precision.
+pub struct DisplayAttributesWriter<M: ManagedTypeApi> {
+    buffer: ManagedBuffer<M>,
+    is_empty: bool,
+}
+
+impl<M: ManagedTypeApi> DisplayAttributesWriter<M> {
+    pub fn new() -> Self {
+        DisplayAttributesWriter {
+            buffer: ManagedBuffer::new_from_bytes(b"{"),
+            is_empty: true,
+        }
+    }
+
+    pub fn add_u64(&mut self, key: &[u8], value: u64) {
+        self.add_key(key);
+        append_decimal_u64(&mut self.buffer, value, 1);
+    }
+
+    pub fn add_biguint(&mut self, key: &[u8], value: &BigUint<M>) {
+        self.add_key(key);
+        self.buffer.append_bytes(b"\"");
+        append_decimal_biguint(&mut self.buffer, value);
+        self.buffer.append_bytes(b"\"");
+    }
+
+    pub fn add_token_id(&mut self, key: &[u8], value: &TokenIdentifier<M>) {
+        self.add_key(key);
+        self.buffer.append_bytes(b"\"");
+        self.buffer.append(value.as_managed_buffer());
+        self.buffer.append_bytes(b"\"");
+    }
+
+    pub fn add_bool(&mut self, key: &[u8], value: bool) {
+        self.add_key(key);
+        let value: &[u8] = if value { b"true" } else { b"false" };
+        self.buffer.append_bytes(value);
+    }
+
+    pub fn finish(mut self) -> ManagedBuffer<M> {
+        self.buffer.append_bytes(b"}");
+        self.buffer
+    }
+
+    fn add_key(&mut self, key: &[u8]) {
+        if !self.is_empty {
+            self.buffer.append_bytes(b",");
+        }
+        self.is_empty = false;
+
+        self.buffer.append_bytes(b"\"");
+        self.buffer.append_bytes(key);
+        self.buffer.append_bytes(b"\":");
+    }
+}
+
+impl<M: ManagedTypeApi> Default for DisplayAttributesWriter<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Appends the decimal digits of the value, left padded with zeros to `min_digits`.
+fn append_decimal_u64<M: ManagedTypeApi>(
+    buffer: &mut ManagedBuffer<M>,
+    value: u64,
+    min_digits: usize,
+) {
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    let mut remaining = value;
+    while remaining > 0 || digits.len() - start < min_digits {
+        start -= 1;
+        digits[start] = b'0' + (remaining % 10) as u8;
+        remaining /= 10;
+    }
+
+    buffer.append_bytes(&digits[start..]);
+}
+
+/// Percent-encodes everything but the unreserved URI characters, so the data fits in a URI.
+fn append_percent_encoded<M: ManagedTypeApi>(
+    buffer: &mut ManagedBuffer<M>,
+    data: &ManagedBuffer<M>,
+) {
+    let mut chunk = [0u8; PERCENT_ENCODE_CHUNK_LEN];
+    let mut encoded = [0u8; 3 * PERCENT_ENCODE_CHUNK_LEN];
+    let data_len = data.len();
+    let mut start = 0;
+    while start < data_len {
+        let chunk_len = core::cmp::min(PERCENT_ENCODE_CHUNK_LEN, data_len - start);
+        let _ = data.load_slice(start, &mut chunk[..chunk_len]);
+
+        let mut encoded_len = 0;
+        for &byte in chunk[..chunk_len].iter() {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                encoded[encoded_len] = byte;
+                encoded_len += 1;
+            } else {
+                encoded[encoded_len] = b'%';
+                encoded[encoded_len + 1] = HEX_DIGITS[(byte >> 4) as usize];
+                encoded[encoded_len + 2] = HEX_DIGITS[(byte & 0x0f) as usize];
+                encoded_len += 3;
+            }
+        }
+
+        buffer.append_bytes(&encoded[..encoded_len]);
+        start += chunk_len;
+    }
+}
+
+/// Big numbers are split in chunks fitting in an u64, most significant chunk first.
+fn append_decimal_biguint<M: ManagedTypeApi>(buffer: &mut ManagedBuffer<M>, value: &BigUint<M>) {
+    let chunk_divisor = BigUint::from(DECIMAL_CHUNK);
+    let mut chunks = ManagedVec::<M, u64>::new();
+    let mut remaining = value.clone();
+    while remaining >= chunk_divisor {
+        chunks.push((&remaining % &chunk_divisor).to_u64().unwrap());
+        remaining = &remaining / &chunk_divisor;
+    }
+
+    append_decimal_u64(buffer, remaining.to_u64().unwrap(), 1);
+    for i in (0..chunks.len()).rev() {
+        append_decimal_u64(buffer, chunks.get(i), DECIMAL_CHUNK_DIGITS);
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for FarmTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_biguint(b"rewardPerShare", &self.reward_per_share);
+        writer.add_u64(b"originalEnteringEpoch", self.original_entering_epoch);
+        writer.add_u64(b"enteringEpoch", self.entering_epoch);
+        writer.add_biguint(b"initialFarmingAmount", &self.initial_farming_amount);
+        writer.add_biguint(b"compoundedReward", &self.compounded_reward);
+        writer.add_biguint(b"currentFarmAmount", &self.current_farm_amount);
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for WrappedLpTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_token_id(b"lpTokenId", &self.lp_token_id);
+        writer.add_biguint(b"lpTokenTotalAmount", &self.lp_token_total_amount);
+        writer.add_biguint(b"lockedAssetsInvested", &self.locked_assets_invested);
+        writer.add_u64(b"lockedAssetsNonce", self.locked_assets_nonce);
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for WrappedFarmTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_token_id(b"farmTokenId", &self.farm_token_id);
+        writer.add_u64(b"farmTokenNonce", self.farm_token_nonce);
+        writer.add_biguint(b"farmTokenAmount", &self.farm_token_amount);
+        writer.add_token_id(b"farmingTokenId", &self.farming_token_id);
+        writer.add_u64(b"farmingTokenNonce", self.farming_token_nonce);
+        writer.add_biguint(b"farmingTokenAmount", &self.farming_token_amount);
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for WrappedSwapTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_token_id(b"tokenId", &self.token_id);
+        writer.add_biguint(b"tokenTotalAmount", &self.token_total_amount);
+        writer.add_biguint(b"lockedAssetsInvested", &self.locked_assets_invested);
+        writer.add_u64(b"lockedAssetsNonce", self.locked_assets_nonce);
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for LinearVestingTokenAttributes {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_u64(b"startEpoch", self.vesting_schedule.start_epoch);
+        writer.add_u64(b"cliffEpoch", self.vesting_schedule.cliff_epoch);
+        writer.add_u64(b"endEpoch", self.vesting_schedule.end_epoch);
+        writer.add_bool(b"isMerged", self.is_merged);
+    }
+}
+
+#[elrond_wasm::module]
+pub trait TokenMetadataModule {
+    /// Sets the metadata of the position tokens minted from now on.
+    /// Minted tokens are named `<display name> - epoch <entering epoch>`, have no royalties
+    /// and have the URI `<base uri><token identifier>/<entering epoch>`.
+    /// The attributes are unchanged, as they are decoded by the contracts. Once a display name
+    /// is set, their fields are also given as a percent-encoded JSON object,
+    /// in a `data:application/json,` URI.
+    #[only_owner]
+    #[endpoint(setPositionTokenMetadata)]
+    fn set_position_token_metadata(&self, display_name: ManagedBuffer, base_uri: ManagedBuffer) {
+        self.position_token_display_name().set(&display_name);
+        self.position_token_base_uri().set(&base_uri);
+    }
+
+    fn create_position_tokens<T: TopEncode + PositionDisplayAttributes<Self::Api>>(
+        &self,
+        token_id: &TokenIdentifier,
+        amount: &BigUint,
+        attributes: &T,
+        opt_entering_epoch: Option<Epoch>,
+    ) -> u64 {
+        let entering_epoch =
+            opt_entering_epoch.unwrap_or_else(|| self.blockchain().get_block_epoch());
+
+        self.send().esdt_nft_create(
+            token_id,
+            amount,
+            &self.build_position_token_name(entering_epoch),
+            &BigUint::zero(),
+            &ManagedBuffer::new(),
+            attributes,
+            &self.build_position_token_uris(token_id, entering_epoch, attributes),
+        )
+    }
+
+    #[view(getPositionTokenName)]
+    fn build_position_token_name(&self, entering_epoch: Epoch) -> ManagedBuffer {
+        let mut name = self.position_token_display_name().get();
+        if name.is_empty() {
+            return name;
+        }
+
+        name.append_bytes(NAME_EPOCH_SEPARATOR);
+        name.append(&self.epoch_to_decimal_buffer(entering_epoch));
+        name
+    }
+
+    fn build_position_token_uris<T: PositionDisplayAttributes<Self::Api>>(
+        &self,
+        token_id: &TokenIdentifier,
+        entering_epoch: Epoch,
+        attributes: &T,
+    ) -> ManagedVec<ManagedBuffer> {
+        let mut uris = ManagedVec::new();
+        let mut uri = self.position_token_base_uri().get();
+        if !uri.is_empty() {
+            uri.append(token_id.as_managed_buffer());
+            uri.append_bytes(URI_EPOCH_SEPARATOR);
+            uri.append(&self.epoch_to_decimal_buffer(entering_epoch));
+            uris.push(uri);
+        }
+
+        if !self.position_token_display_name().is_empty() {
+            uris.push(self.build_position_attributes_uri(attributes));
+        }
+        uris
+    }
+
+    fn build_position_attributes_uri<T: PositionDisplayAttributes<Self::Api>>(
+        &self,
+        attributes: &T,
+    ) -> ManagedBuffer {
+        let mut writer = DisplayAttributesWriter::new();
+        attributes.write_display_attributes(&mut writer);
+
+        let mut uri = ManagedBuffer::new_from_bytes(ATTRIBUTES_URI_PREFIX);
+        append_percent_encoded(&mut uri, &writer.finish());
+        uri
+    }
+
+    fn epoch_to_decimal_buffer(&self, epoch: Epoch) -> ManagedBuffer {
+        let mut buffer = ManagedBuffer::new();
+        append_decimal_u64(&mut buffer, epoch, 1);
+        buffer
+    }
+
+    #[view(getPositionTokenDisplayName)]
+    #[storage_mapper("positionTokenDisplayName")]
+    fn position_token_display_name(&self) -> SingleValueMapper<ManagedBuffer>;
+
+    #[view(getPositionTokenBaseUri)]
+    #[storage_mapper("positionTokenBaseUri")]
+    fn position_token_base_uri(&self) -> SingleValueMapper<ManagedBuffer>;
+}
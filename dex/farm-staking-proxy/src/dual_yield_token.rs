@@ -3,6 +3,9 @@ elrond_wasm::derive_imports!();
 
 use hex_literal::hex;
 
+use crate::farm_registry::DEFAULT_FARM_TRIPLE_ID;
+use token_metadata::{DisplayAttributesWriter, PositionDisplayAttributes};
+
 const META_SFT_TOKEN_TYPE_NAME: &[u8] = b"META";
 const ESDT_SYSTEM_SC_ADDRESS_ARRAY: [u8; 32] =
     hex!("000000000000000000010000000000000000000000000000000000000002ffff");
@@ -32,6 +35,27 @@ pub struct DualYieldTokenAttributes<M: ManagedTypeApi> {
     pub lp_farm_token_amount: BigUint<M>,
     pub staking_farm_token_nonce: u64,
     pub staking_farm_token_amount: BigUint<M>,
+    pub farm_triple_id: u32,
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for DualYieldTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_u64(b"lpFarmTokenNonce", self.lp_farm_token_nonce);
+        writer.add_biguint(b"lpFarmTokenAmount", &self.lp_farm_token_amount);
+        writer.add_u64(b"stakingFarmTokenNonce", self.staking_farm_token_nonce);
+        writer.add_biguint(b"stakingFarmTokenAmount", &self.staking_farm_token_amount);
+        writer.add_u64(b"farmTripleId", self.farm_triple_id as u64);
+    }
+}
+
+/// Attributes of the dual yield tokens created before the farm registry was introduced.
+/// All of them belong to the default farm triple.
+#[derive(TopDecode)]
+struct DualYieldTokenAttributesV1<M: ManagedTypeApi> {
+    lp_farm_token_nonce: u64,
+    lp_farm_token_amount: BigUint<M>,
+    staking_farm_token_nonce: u64,
+    staking_farm_token_amount: BigUint<M>,
 }
 
 impl<M: ManagedTypeApi> DualYieldTokenAttributes<M> {
@@ -44,7 +68,9 @@ impl<M: ManagedTypeApi> DualYieldTokenAttributes<M> {
 }
 
 #[elrond_wasm::module]
-pub trait DualYieldTokenModule: token_merge::TokenMergeModule {
+pub trait DualYieldTokenModule:
+    token_merge::TokenMergeModule + token_metadata::TokenMetadataModule
+{
     #[only_owner]
     #[payable("EGLD")]
     #[endpoint(issueDualYieldToken)]
@@ -122,6 +148,17 @@ pub trait DualYieldTokenModule: token_merge::TokenMergeModule {
         }
     }
 
+    fn require_same_farm_triple(
+        &self,
+        attributes: &DualYieldTokenAttributes<Self::Api>,
+        farm_triple_id: u32,
+    ) {
+        require!(
+            attributes.farm_triple_id == farm_triple_id,
+            "Positions belong to different farms"
+        );
+    }
+
     fn create_and_send_dual_yield_tokens(
         &self,
         to: &ManagedAddress,
@@ -129,12 +166,14 @@ pub trait DualYieldTokenModule: token_merge::TokenMergeModule {
         lp_farm_token_amount: BigUint,
         staking_farm_token_nonce: u64,
         staking_farm_token_amount: BigUint,
+        farm_triple_id: u32,
     ) -> EsdtTokenPayment<Self::Api> {
         let payment = self.create_dual_yield_tokens(
             lp_farm_token_nonce,
             lp_farm_token_amount,
             staking_farm_token_nonce,
             staking_farm_token_amount,
+            farm_triple_id,
         );
         self.send().direct(
             to,
@@ -153,25 +192,19 @@ pub trait DualYieldTokenModule: token_merge::TokenMergeModule {
         lp_farm_token_amount: BigUint,
         staking_farm_token_nonce: u64,
         staking_farm_token_amount: BigUint,
+        farm_triple_id: u32,
     ) -> EsdtTokenPayment<Self::Api> {
         let dual_yield_token_id = self.dual_yield_token_id().get();
-        let empty_buffer = ManagedBuffer::new();
         let attributes = DualYieldTokenAttributes {
             lp_farm_token_nonce,
             lp_farm_token_amount,
             staking_farm_token_nonce,
             staking_farm_token_amount,
+            farm_triple_id,
         };
         let amount = attributes.get_total_dual_yield_tokens_for_position();
-        let new_token_nonce = self.send().esdt_nft_create(
-            &dual_yield_token_id,
-            amount,
-            &empty_buffer,
-            &BigUint::zero(),
-            &empty_buffer,
-            &attributes,
-            &ManagedVec::new(),
-        );
+        let new_token_nonce =
+            self.create_position_tokens(&dual_yield_token_id, amount, &attributes, None);
 
         EsdtTokenPayment::new(dual_yield_token_id, new_token_nonce, amount.clone())
     }
@@ -194,7 +227,21 @@ pub trait DualYieldTokenModule: token_merge::TokenMergeModule {
             dual_yield_token_nonce,
         );
 
-        token_info.decode_attributes_or_exit()
+        match token_info.decode_attributes::<DualYieldTokenAttributes<Self::Api>>() {
+            Ok(attributes) => attributes,
+            Err(_) => {
+                let attributes_v1 =
+                    token_info.decode_attributes_or_exit::<DualYieldTokenAttributesV1<Self::Api>>();
+
+                DualYieldTokenAttributes {
+                    lp_farm_token_nonce: attributes_v1.lp_farm_token_nonce,
+                    lp_farm_token_amount: attributes_v1.lp_farm_token_amount,
+                    staking_farm_token_nonce: attributes_v1.staking_farm_token_nonce,
+                    staking_farm_token_amount: attributes_v1.staking_farm_token_amount,
+                    farm_triple_id: DEFAULT_FARM_TRIPLE_ID,
+                }
+            }
+        }
     }
 
     fn get_lp_farm_token_amount_equivalent(
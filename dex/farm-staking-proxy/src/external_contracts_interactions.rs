@@ -1,11 +1,15 @@
 elrond_wasm::imports!();
 
 use farm::farm_token_merge::ProxyTrait as _;
+use pair::config::ProxyTrait as _;
 use pair::safe_price::ProxyTrait as _;
 
+use crate::farm_registry::FarmTriple;
 use crate::result_types::*;
-use farm_staking::{ClaimRewardsResultType, EnterFarmResultType, ExitFarmResultType};
-use pair::RemoveLiquidityResultType;
+use farm_staking::{
+    ClaimRewardsResultType, CompoundRewardsResultType, EnterFarmResultType, ExitFarmResultType,
+};
+use pair::{AddLiquidityResultType, RemoveLiquidityResultType, SwapTokensFixedInputResultType};
 
 pub type SafePriceResult<Api> = MultiResult2<EsdtTokenPayment<Api>, EsdtTokenPayment<Api>>;
 
@@ -17,11 +21,11 @@ pub trait ExternalContractsInteractionsModule:
 
     fn lp_farm_claim_rewards(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         lp_farm_tokens: PaymentsVec<Self::Api>,
     ) -> LpFarmClaimRewardsResult<Self::Api> {
-        let lp_farm_address = self.lp_farm_address().get();
         let lp_farm_result: ClaimRewardsResultType<Self::Api> = self
-            .lp_farm_proxy_obj(lp_farm_address)
+            .lp_farm_proxy_obj(farm_triple.lp_farm_address.clone())
             .claim_rewards(OptionalArg::None)
             .with_multi_token_transfer(lp_farm_tokens)
             .execute_on_dest_context_custom_range(|_, after| (after - 2, after));
@@ -33,17 +37,40 @@ pub trait ExternalContractsInteractionsModule:
         }
     }
 
+    fn lp_farm_enter(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        lp_tokens: EsdtTokenPayment<Self::Api>,
+        additional_lp_farm_tokens: PaymentsVec<Self::Api>,
+    ) -> LpFarmEnterResult<Self::Api> {
+        let mut payments = ManagedVec::from_single_item(lp_tokens);
+        payments.append_vec(additional_lp_farm_tokens);
+
+        let received_lp_farm_token: EnterFarmResultType<Self::Api> = self
+            .lp_farm_proxy_obj(farm_triple.lp_farm_address.clone())
+            .enter_farm(OptionalArg::None)
+            .with_multi_token_transfer(payments)
+            .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
+
+        LpFarmEnterResult {
+            received_lp_farm_token,
+        }
+    }
+
     fn lp_farm_exit(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         lp_farm_token_nonce: u64,
         lp_farm_token_amount: BigUint,
     ) -> LpFarmExitResult<Self::Api> {
-        let lp_farm_token_id = self.lp_farm_token_id().get();
-        let lp_farm_address = self.lp_farm_address().get();
         let exit_farm_result: ExitFarmResultType<Self::Api> = self
-            .lp_farm_proxy_obj(lp_farm_address)
+            .lp_farm_proxy_obj(farm_triple.lp_farm_address.clone())
             .exit_farm(OptionalArg::None)
-            .add_token_transfer(lp_farm_token_id, lp_farm_token_nonce, lp_farm_token_amount)
+            .add_token_transfer(
+                farm_triple.lp_farm_token_id.clone(),
+                lp_farm_token_nonce,
+                lp_farm_token_amount,
+            )
             .execute_on_dest_context();
         let (lp_tokens, lp_farm_rewards) = exit_farm_result.into_tuple();
 
@@ -55,6 +82,7 @@ pub trait ExternalContractsInteractionsModule:
 
     fn merge_lp_farm_tokens(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         base_lp_token: EsdtTokenPayment<Self::Api>,
         mut additional_lp_tokens: ManagedVec<EsdtTokenPayment<Self::Api>>,
     ) -> EsdtTokenPayment<Self::Api> {
@@ -64,8 +92,7 @@ pub trait ExternalContractsInteractionsModule:
 
         additional_lp_tokens.push(base_lp_token);
 
-        let lp_farm_address = self.lp_farm_address().get();
-        self.lp_farm_proxy_obj(lp_farm_address)
+        self.lp_farm_proxy_obj(farm_triple.lp_farm_address.clone())
             .merge_farm_tokens(OptionalArg::None)
             .with_multi_token_transfer(additional_lp_tokens)
             .execute_on_dest_context_custom_range(|_, after| (after - 1, after))
@@ -75,12 +102,12 @@ pub trait ExternalContractsInteractionsModule:
 
     fn staking_farm_enter(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         staking_token_amount: BigUint,
         staking_farm_tokens: PaymentsVec<Self::Api>,
     ) -> StakingFarmEnterResult<Self::Api> {
-        let staking_farm_address = self.staking_farm_address().get();
         let received_staking_farm_token: EnterFarmResultType<Self::Api> = self
-            .staking_farm_proxy_obj(staking_farm_address)
+            .staking_farm_proxy_obj(farm_triple.staking_farm_address.clone())
             .stake_farm_through_proxy(staking_token_amount)
             .with_multi_token_transfer(staking_farm_tokens)
             .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
@@ -92,12 +119,12 @@ pub trait ExternalContractsInteractionsModule:
 
     fn staking_farm_claim_rewards(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         new_staking_farm_values: ManagedVec<BigUint>,
         staking_farm_tokens: PaymentsVec<Self::Api>,
     ) -> StakingFarmClaimRewardsResult<Self::Api> {
-        let staking_farm_address = self.staking_farm_address().get();
         let staking_farm_result: ClaimRewardsResultType<Self::Api> = self
-            .staking_farm_proxy_obj(staking_farm_address)
+            .staking_farm_proxy_obj(farm_triple.staking_farm_address.clone())
             .claim_rewards_with_new_value(new_staking_farm_values)
             .with_multi_token_transfer(staking_farm_tokens)
             .execute_on_dest_context_custom_range(|_, after| (after - 2, after));
@@ -109,24 +136,39 @@ pub trait ExternalContractsInteractionsModule:
         }
     }
 
+    fn staking_farm_compound_rewards(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        staking_farm_tokens: PaymentsVec<Self::Api>,
+    ) -> StakingFarmCompoundRewardsResult<Self::Api> {
+        let new_staking_farm_tokens: CompoundRewardsResultType<Self::Api> = self
+            .staking_farm_proxy_obj(farm_triple.staking_farm_address.clone())
+            .compound_rewards()
+            .with_multi_token_transfer(staking_farm_tokens)
+            .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
+
+        StakingFarmCompoundRewardsResult {
+            new_staking_farm_tokens,
+        }
+    }
+
     fn staking_farm_unstake(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         staking_tokens: EsdtTokenPayment<Self::Api>,
         farm_token_nonce: u64,
         farm_token_amount: BigUint,
     ) -> StakingFarmExitResult<Self::Api> {
-        let staking_farm_token_id = self.staking_farm_token_id().get();
         let mut payments = ManagedVec::from_single_item(staking_tokens);
         payments.push(EsdtTokenPayment::new(
-            staking_farm_token_id,
+            farm_triple.staking_farm_token_id.clone(),
             farm_token_nonce,
             farm_token_amount,
         ));
 
-        let staking_farm_address = self.staking_farm_address().get();
         let unstake_result: ExitFarmResultType<Self::Api> = self
-            .staking_farm_proxy_obj(staking_farm_address)
-            .unstake_farm_through_proxy()
+            .staking_farm_proxy_obj(farm_triple.staking_farm_address.clone())
+            .unstake_farm_through_proxy(OptionalArg::Some(true))
             .with_multi_token_transfer(payments)
             .execute_on_dest_context_custom_range(|_, after| (after - 2, after));
         let (unbond_staking_farm_token, staking_rewards) = unstake_result.into_tuple();
@@ -137,17 +179,42 @@ pub trait ExternalContractsInteractionsModule:
         }
     }
 
+    /// Exits the staking farm position without sending the staking tokens from the liquidity pool,
+    /// so only the compounded rewards are unbonded.
+    fn staking_farm_unstake_keep_liquidity(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        farm_token_nonce: u64,
+        farm_token_amount: BigUint,
+    ) -> StakingFarmExitResult<Self::Api> {
+        let unstake_result: ExitFarmResultType<Self::Api> = self
+            .staking_farm_proxy_obj(farm_triple.staking_farm_address.clone())
+            .unstake_farm_through_proxy(OptionalArg::Some(true))
+            .add_token_transfer(
+                farm_triple.staking_farm_token_id.clone(),
+                farm_token_nonce,
+                farm_token_amount,
+            )
+            .execute_on_dest_context_custom_range(|_, after| (after - 2, after));
+        let (unbond_staking_farm_token, staking_rewards) = unstake_result.into_tuple();
+
+        StakingFarmExitResult {
+            unbond_staking_farm_token,
+            staking_rewards,
+        }
+    }
+
     // pair
 
     fn pair_remove_liquidity(
         &self,
+        farm_triple: &FarmTriple<Self::Api>,
         lp_tokens: EsdtTokenPayment<Self::Api>,
         pair_first_token_min_amount: BigUint,
         pair_second_token_min_amount: BigUint,
     ) -> PairRemoveLiquidityResult<Self::Api> {
-        let pair_address = self.pair_address().get();
         let pair_withdraw_result: RemoveLiquidityResultType<Self::Api> = self
-            .pair_proxy_obj(pair_address)
+            .pair_proxy_obj(farm_triple.pair_address.clone())
             .remove_liquidity(
                 lp_tokens.token_identifier,
                 lp_tokens.token_nonce,
@@ -160,11 +227,11 @@ pub trait ExternalContractsInteractionsModule:
         let (pair_first_token_payment, pair_second_token_payment) =
             pair_withdraw_result.into_tuple();
 
-        let staking_token_id = self.staking_token_id().get();
+        let staking_token_id = &farm_triple.staking_token_id;
         let (staking_token_payment, other_token_payment) =
-            if pair_first_token_payment.token_identifier == staking_token_id {
+            if &pair_first_token_payment.token_identifier == staking_token_id {
                 (pair_first_token_payment, pair_second_token_payment)
-            } else if pair_second_token_payment.token_identifier == staking_token_id {
+            } else if &pair_second_token_payment.token_identifier == staking_token_id {
                 (pair_second_token_payment, pair_first_token_payment)
             } else {
                 sc_panic!("Invalid payments received from Pair");
@@ -176,18 +243,136 @@ pub trait ExternalContractsInteractionsModule:
         }
     }
 
-    fn get_lp_tokens_safe_price(&self, lp_tokens_amount: BigUint) -> BigUint {
-        let pair_address = self.pair_address().get();
+    fn pair_swap_fixed_input(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        payment: EsdtTokenPayment<Self::Api>,
+        token_out: TokenIdentifier,
+        amount_out_min: BigUint,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let swap_result: SwapTokensFixedInputResultType<Self::Api> = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .swap_tokens_fixed_input(
+                payment.token_identifier,
+                payment.token_nonce,
+                payment.amount,
+                token_out,
+                amount_out_min,
+                OptionalArg::None,
+            )
+            .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
+
+        swap_result
+    }
+
+    fn pair_add_liquidity(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        first_token_payment: EsdtTokenPayment<Self::Api>,
+        second_token_payment: EsdtTokenPayment<Self::Api>,
+        first_token_amount_min: BigUint,
+        second_token_amount_min: BigUint,
+    ) -> PairAddLiquidityResult<Self::Api> {
+        let first_token_amount = first_token_payment.amount.clone();
+        let second_token_amount = second_token_payment.amount.clone();
+        let mut payments = ManagedVec::from_single_item(first_token_payment);
+        payments.push(second_token_payment);
+
+        let add_liq_result: AddLiquidityResultType<Self::Api> = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .add_liquidity(
+                first_token_amount_min,
+                second_token_amount_min,
+                OptionalArg::None,
+            )
+            .with_multi_token_transfer(payments)
+            .execute_on_dest_context_custom_range(|_, after| (after - 3, after));
+        let (lp_tokens, first_token_used, second_token_used) = add_liq_result.into_tuple();
+
+        PairAddLiquidityResult {
+            lp_tokens,
+            first_token_leftover: EsdtTokenPayment::new(
+                first_token_used.token_identifier,
+                0,
+                first_token_amount - first_token_used.amount,
+            ),
+            second_token_leftover: EsdtTokenPayment::new(
+                second_token_used.token_identifier,
+                0,
+                second_token_amount - second_token_used.amount,
+            ),
+        }
+    }
+
+    fn get_pair_tokens(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+    ) -> (TokenIdentifier, TokenIdentifier) {
+        let first_token_id: TokenIdentifier = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .first_token_id()
+            .execute_on_dest_context();
+        let second_token_id: TokenIdentifier = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .second_token_id()
+            .execute_on_dest_context();
+
+        (first_token_id, second_token_id)
+    }
+
+    fn get_lp_tokens_safe_price(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        lp_tokens_amount: BigUint,
+    ) -> BigUint {
         let result: SafePriceResult<Self::Api> = self
-            .pair_proxy_obj(pair_address)
+            .pair_proxy_obj(farm_triple.pair_address.clone())
             .update_and_get_tokens_for_given_position_with_safe_price(lp_tokens_amount)
             .execute_on_dest_context();
         let (first_token_info, second_token_info) = result.into_tuple();
-        let staking_token_id = self.staking_token_id().get();
 
-        if first_token_info.token_identifier == staking_token_id {
+        self.get_staking_token_amount(farm_triple, first_token_info, second_token_info)
+    }
+
+    /// Safe price as of the last update of the pair, without updating it.
+    fn get_lp_tokens_last_safe_price(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        lp_tokens_amount: BigUint,
+    ) -> BigUint {
+        let result: SafePriceResult<Self::Api> = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .get_tokens_for_given_position_with_safe_price(lp_tokens_amount)
+            .execute_on_dest_context();
+        let (first_token_info, second_token_info) = result.into_tuple();
+
+        self.get_staking_token_amount(farm_triple, first_token_info, second_token_info)
+    }
+
+    fn get_lp_tokens_spot_price(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        lp_tokens_amount: BigUint,
+    ) -> BigUint {
+        let result: SafePriceResult<Self::Api> = self
+            .pair_proxy_obj(farm_triple.pair_address.clone())
+            .get_tokens_for_given_position(lp_tokens_amount)
+            .execute_on_dest_context();
+        let (first_token_info, second_token_info) = result.into_tuple();
+
+        self.get_staking_token_amount(farm_triple, first_token_info, second_token_info)
+    }
+
+    fn get_staking_token_amount(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        first_token_info: EsdtTokenPayment<Self::Api>,
+        second_token_info: EsdtTokenPayment<Self::Api>,
+    ) -> BigUint {
+        let staking_token_id = &farm_triple.staking_token_id;
+        if &first_token_info.token_identifier == staking_token_id {
             first_token_info.amount
-        } else if second_token_info.token_identifier == staking_token_id {
+        } else if &second_token_info.token_identifier == staking_token_id {
             second_token_info.amount
         } else {
             sc_panic!("Invalid Pair contract called");
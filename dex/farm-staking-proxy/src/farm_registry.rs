@@ -0,0 +1,112 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+/// Id of the triple configured at init. Its data is kept in the original storage.
+pub const DEFAULT_FARM_TRIPLE_ID: u32 = 0;
+
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, TypeAbi)]
+pub struct FarmTriple<M: ManagedTypeApi> {
+    pub pair_address: ManagedAddress<M>,
+    pub lp_farm_address: ManagedAddress<M>,
+    pub staking_farm_address: ManagedAddress<M>,
+    pub staking_token_id: TokenIdentifier<M>,
+    pub lp_farm_token_id: TokenIdentifier<M>,
+    pub staking_farm_token_id: TokenIdentifier<M>,
+}
+
+#[elrond_wasm::module]
+pub trait FarmRegistryModule:
+    crate::external_contracts_interactions::ExternalContractsInteractionsModule
+    + crate::lp_farm_token::LpFarmTokenModule
+    + token_merge::TokenMergeModule
+{
+    /// Registers a new (pair, lp farm, staking farm) triple and returns its id.
+    /// The staking farm has to whitelist this contract separately.
+    #[only_owner]
+    #[endpoint(addFarmTriple)]
+    fn add_farm_triple(
+        &self,
+        pair_address: ManagedAddress,
+        lp_farm_address: ManagedAddress,
+        staking_farm_address: ManagedAddress,
+        staking_token_id: TokenIdentifier,
+        lp_farm_token_id: TokenIdentifier,
+        staking_farm_token_id: TokenIdentifier,
+    ) -> u32 {
+        let triple = FarmTriple {
+            pair_address,
+            lp_farm_address,
+            staking_farm_address,
+            staking_token_id,
+            lp_farm_token_id,
+            staking_farm_token_id,
+        };
+        self.require_valid_farm_triple(&triple);
+
+        self.farm_triples().push(&triple) as u32
+    }
+
+    fn require_valid_farm_triple(&self, triple: &FarmTriple<Self::Api>) {
+        require!(
+            self.blockchain().is_smart_contract(&triple.lp_farm_address),
+            "Invalid LP Farm address"
+        );
+        require!(
+            self.blockchain()
+                .is_smart_contract(&triple.staking_farm_address),
+            "Invalid Staking Farm address"
+        );
+        require!(
+            self.blockchain().is_smart_contract(&triple.pair_address),
+            "Invalid Pair address"
+        );
+        require!(
+            triple.staking_token_id.is_valid_esdt_identifier(),
+            "Invalid Staking token ID"
+        );
+        require!(
+            triple.lp_farm_token_id.is_valid_esdt_identifier(),
+            "Invalid LP token ID"
+        );
+        require!(
+            triple.staking_farm_token_id.is_valid_esdt_identifier(),
+            "Invalid Staking Farm token ID"
+        );
+    }
+
+    #[view(getFarmTriple)]
+    fn get_farm_triple(&self, farm_triple_id: u32) -> FarmTriple<Self::Api> {
+        if farm_triple_id == DEFAULT_FARM_TRIPLE_ID {
+            return FarmTriple {
+                pair_address: self.pair_address().get(),
+                lp_farm_address: self.lp_farm_address().get(),
+                staking_farm_address: self.staking_farm_address().get(),
+                staking_token_id: self.staking_token_id().get(),
+                lp_farm_token_id: self.lp_farm_token_id().get(),
+                staking_farm_token_id: self.staking_farm_token_id().get(),
+            };
+        }
+
+        let farm_triples = self.farm_triples();
+        require!(
+            farm_triple_id as usize <= farm_triples.len(),
+            "Unknown farm triple"
+        );
+
+        farm_triples.get(farm_triple_id as usize)
+    }
+
+    #[view(getFarmTriples)]
+    fn get_farm_triples(&self) -> ManagedMultiResultVec<FarmTriple<Self::Api>> {
+        let mut result = ManagedVec::new();
+        result.push(self.get_farm_triple(DEFAULT_FARM_TRIPLE_ID));
+        for triple in self.farm_triples().iter() {
+            result.push(triple);
+        }
+
+        result.into()
+    }
+
+    #[storage_mapper("farmTriples")]
+    fn farm_triples(&self) -> VecMapper<FarmTriple<Self::Api>>;
+}
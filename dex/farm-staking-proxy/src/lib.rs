@@ -4,19 +4,25 @@ elrond_wasm::imports!();
 
 pub mod dual_yield_token;
 pub mod external_contracts_interactions;
+pub mod farm_registry;
 pub mod lp_farm_token;
+pub mod price_check;
 pub mod result_types;
 
 pub type StakeResult<Api> = EsdtTokenPayment<Api>;
 pub type ClaimDualYieldResult<Api> = ManagedMultiResultVec<Api, EsdtTokenPayment<Api>>;
 pub type UnstakeResult<Api> = ManagedMultiResultVec<Api, EsdtTokenPayment<Api>>;
+pub type CompoundDualYieldResult<Api> = ManagedMultiResultVec<Api, EsdtTokenPayment<Api>>;
 
 #[elrond_wasm::contract]
 pub trait FarmStakingProxy:
     dual_yield_token::DualYieldTokenModule
     + external_contracts_interactions::ExternalContractsInteractionsModule
+    + farm_registry::FarmRegistryModule
     + lp_farm_token::LpFarmTokenModule
+    + price_check::PriceCheckModule
     + token_merge::TokenMergeModule
+    + token_metadata::TokenMetadataModule
 {
     #[init]
     fn init(
@@ -63,25 +69,34 @@ pub trait FarmStakingProxy:
 
     #[payable("*")]
     #[endpoint(stakeFarmTokens)]
-    fn stake_farm_tokens(&self) -> StakeResult<Self::Api> {
+    fn stake_farm_tokens(
+        &self,
+        #[var_args] opt_farm_triple_id: OptionalArg<u32>,
+    ) -> StakeResult<Self::Api> {
+        let farm_triple_id = opt_farm_triple_id
+            .into_option()
+            .unwrap_or(farm_registry::DEFAULT_FARM_TRIPLE_ID);
+        let farm_triple = self.get_farm_triple(farm_triple_id);
+
         let payments = self.call_value().all_esdt_transfers();
         let lp_farm_token_payment: EsdtTokenPayment<Self::Api> = payments
             .try_get(0)
             .unwrap_or_else(|| sc_panic!("empty payments"));
         let additional_payments = payments.slice(1, payments.len()).unwrap_or_default();
 
-        let lp_farm_token_id = self.lp_farm_token_id().get();
+        let lp_farm_token_id = &farm_triple.lp_farm_token_id;
         require!(
-            lp_farm_token_payment.token_identifier == lp_farm_token_id,
+            &lp_farm_token_payment.token_identifier == lp_farm_token_id,
             "Invalid first payment"
         );
         self.require_all_payments_dual_yield_tokens(&additional_payments);
 
-        let staking_farm_token_id = self.staking_farm_token_id().get();
+        let staking_farm_token_id = &farm_triple.staking_farm_token_id;
         let mut staking_farm_tokens = ManagedVec::new();
         let mut additional_lp_farm_tokens = ManagedVec::new();
         for p in &additional_payments {
             let attributes = self.get_dual_yield_token_attributes(p.token_nonce);
+            self.require_same_farm_triple(&attributes, farm_triple_id);
 
             staking_farm_tokens.push(EsdtTokenPayment::new(
                 staking_farm_token_id.clone(),
@@ -99,15 +114,20 @@ pub trait FarmStakingProxy:
         }
 
         let lp_tokens_in_farm = self.get_lp_tokens_in_farm_position(
+            lp_farm_token_id,
             lp_farm_token_payment.token_nonce,
             &lp_farm_token_payment.amount,
         );
-        let merged_lp_farm_tokens =
-            self.merge_lp_farm_tokens(lp_farm_token_payment, additional_lp_farm_tokens);
+        let merged_lp_farm_tokens = self.merge_lp_farm_tokens(
+            &farm_triple,
+            lp_farm_token_payment,
+            additional_lp_farm_tokens,
+        );
 
-        let staking_token_amount = self.get_lp_tokens_safe_price(lp_tokens_in_farm);
+        let staking_token_amount =
+            self.get_lp_tokens_checked_safe_price(&farm_triple, lp_tokens_in_farm);
         let received_staking_farm_token = self
-            .staking_farm_enter(staking_token_amount, staking_farm_tokens)
+            .staking_farm_enter(&farm_triple, staking_token_amount, staking_farm_tokens)
             .received_staking_farm_token;
 
         let caller = self.blockchain().get_caller();
@@ -117,6 +137,7 @@ pub trait FarmStakingProxy:
             merged_lp_farm_tokens.amount,
             received_staking_farm_token.token_nonce,
             received_staking_farm_token.amount,
+            farm_triple_id,
         )
     }
 
@@ -126,15 +147,24 @@ pub trait FarmStakingProxy:
         let payments = self.call_value().all_esdt_transfers();
         self.require_all_payments_dual_yield_tokens(&payments);
 
+        let first_payment: EsdtTokenPayment<Self::Api> = payments
+            .try_get(0)
+            .unwrap_or_else(|| sc_panic!("empty payments"));
+        let farm_triple_id = self
+            .get_dual_yield_token_attributes(first_payment.token_nonce)
+            .farm_triple_id;
+        let farm_triple = self.get_farm_triple(farm_triple_id);
+
         let mut lp_farm_tokens = ManagedVec::new();
         let mut staking_farm_tokens = ManagedVec::new();
         let mut new_staking_farm_values = ManagedVec::new();
 
-        let lp_farm_token_id = self.lp_farm_token_id().get();
-        let staking_farm_token_id = self.staking_farm_token_id().get();
+        let lp_farm_token_id = &farm_triple.lp_farm_token_id;
+        let staking_farm_token_id = &farm_triple.staking_farm_token_id;
 
         for p in &payments {
             let attributes = self.get_dual_yield_token_attributes(p.token_nonce);
+            self.require_same_farm_triple(&attributes, farm_triple_id);
             let staking_farm_token_amount =
                 self.get_staking_farm_token_amount_equivalent(&p.amount);
 
@@ -147,10 +177,12 @@ pub trait FarmStakingProxy:
             let lp_farm_token_amount =
                 self.get_lp_farm_token_amount_equivalent(&attributes, &p.amount);
             let lp_tokens_in_position = self.get_lp_tokens_in_farm_position(
+                lp_farm_token_id,
                 attributes.lp_farm_token_nonce,
                 &lp_farm_token_amount,
             );
-            let new_staking_farm_value = self.get_lp_tokens_safe_price(lp_tokens_in_position);
+            let new_staking_farm_value =
+                self.get_lp_tokens_checked_safe_price(&farm_triple, lp_tokens_in_position);
 
             lp_farm_tokens.push(EsdtTokenPayment::new(
                 lp_farm_token_id.clone(),
@@ -162,9 +194,12 @@ pub trait FarmStakingProxy:
             self.burn_dual_yield_tokens(p.token_nonce, &p.amount);
         }
 
-        let lp_farm_claim_rewards_result = self.lp_farm_claim_rewards(lp_farm_tokens);
-        let staking_farm_claim_rewards_result =
-            self.staking_farm_claim_rewards(new_staking_farm_values, staking_farm_tokens);
+        let lp_farm_claim_rewards_result = self.lp_farm_claim_rewards(&farm_triple, lp_farm_tokens);
+        let staking_farm_claim_rewards_result = self.staking_farm_claim_rewards(
+            &farm_triple,
+            new_staking_farm_values,
+            staking_farm_tokens,
+        );
 
         let new_lp_farm_tokens = lp_farm_claim_rewards_result.new_lp_farm_tokens;
         let new_staking_farm_tokens = staking_farm_claim_rewards_result.new_staking_farm_tokens;
@@ -173,6 +208,7 @@ pub trait FarmStakingProxy:
             new_lp_farm_tokens.amount,
             new_staking_farm_tokens.token_nonce,
             new_staking_farm_tokens.amount,
+            farm_triple_id,
         );
 
         self.send_claim_payments(
@@ -209,6 +245,160 @@ pub trait FarmStakingProxy:
         user_output_payments.into()
     }
 
+    /// Claims the rewards of both farms and reinvests them into the position.
+    /// The staking farm rewards are always compounded. The LP farm rewards are either sent to the caller,
+    /// or, if `compound_lp_farm_rewards` is set, swapped into LP tokens and added to the LP farm position.
+    /// The minimum amounts are used for the swap and for adding the liquidity.
+    #[payable("*")]
+    #[endpoint(compoundDualYield)]
+    fn compound_dual_yield(
+        &self,
+        compound_lp_farm_rewards: bool,
+        swap_amount_out_min: BigUint,
+        pair_first_token_min_amount: BigUint,
+        pair_second_token_min_amount: BigUint,
+    ) -> CompoundDualYieldResult<Self::Api> {
+        let payments = self.call_value().all_esdt_transfers();
+        self.require_all_payments_dual_yield_tokens(&payments);
+
+        let first_payment: EsdtTokenPayment<Self::Api> = payments
+            .try_get(0)
+            .unwrap_or_else(|| sc_panic!("empty payments"));
+        let farm_triple_id = self
+            .get_dual_yield_token_attributes(first_payment.token_nonce)
+            .farm_triple_id;
+        let farm_triple = self.get_farm_triple(farm_triple_id);
+
+        let mut lp_farm_tokens = ManagedVec::new();
+        let mut staking_farm_tokens = ManagedVec::new();
+        for p in &payments {
+            let attributes = self.get_dual_yield_token_attributes(p.token_nonce);
+            self.require_same_farm_triple(&attributes, farm_triple_id);
+
+            lp_farm_tokens.push(EsdtTokenPayment::new(
+                farm_triple.lp_farm_token_id.clone(),
+                attributes.lp_farm_token_nonce,
+                self.get_lp_farm_token_amount_equivalent(&attributes, &p.amount),
+            ));
+            staking_farm_tokens.push(EsdtTokenPayment::new(
+                farm_triple.staking_farm_token_id.clone(),
+                attributes.staking_farm_token_nonce,
+                self.get_staking_farm_token_amount_equivalent(&p.amount),
+            ));
+
+            self.burn_dual_yield_tokens(p.token_nonce, &p.amount);
+        }
+
+        let lp_farm_claim_rewards_result = self.lp_farm_claim_rewards(&farm_triple, lp_farm_tokens);
+        let mut new_lp_farm_tokens = lp_farm_claim_rewards_result.new_lp_farm_tokens;
+        let lp_farm_rewards = lp_farm_claim_rewards_result.lp_farm_rewards;
+        let mut new_staking_farm_tokens = self
+            .staking_farm_compound_rewards(&farm_triple, staking_farm_tokens)
+            .new_staking_farm_tokens;
+
+        let mut user_output_payments = ManagedVec::new();
+        if compound_lp_farm_rewards && lp_farm_rewards.amount > 0 {
+            let add_liq_result = self.add_rewards_as_liquidity(
+                &farm_triple,
+                lp_farm_rewards,
+                swap_amount_out_min,
+                pair_first_token_min_amount,
+                pair_second_token_min_amount,
+            );
+            if add_liq_result.first_token_leftover.amount > 0 {
+                user_output_payments.push(add_liq_result.first_token_leftover);
+            }
+            if add_liq_result.second_token_leftover.amount > 0 {
+                user_output_payments.push(add_liq_result.second_token_leftover);
+            }
+
+            let lp_tokens = add_liq_result.lp_tokens;
+            let staking_token_amount =
+                self.get_lp_tokens_checked_safe_price(&farm_triple, lp_tokens.amount.clone());
+            new_lp_farm_tokens = self
+                .lp_farm_enter(
+                    &farm_triple,
+                    lp_tokens,
+                    ManagedVec::from_single_item(new_lp_farm_tokens),
+                )
+                .received_lp_farm_token;
+            new_staking_farm_tokens = self
+                .staking_farm_enter(
+                    &farm_triple,
+                    staking_token_amount,
+                    ManagedVec::from_single_item(new_staking_farm_tokens),
+                )
+                .received_staking_farm_token;
+        } else if lp_farm_rewards.amount > 0 {
+            user_output_payments.push(lp_farm_rewards);
+        }
+
+        let new_dual_yield_tokens = self.create_dual_yield_tokens(
+            new_lp_farm_tokens.token_nonce,
+            new_lp_farm_tokens.amount,
+            new_staking_farm_tokens.token_nonce,
+            new_staking_farm_tokens.amount,
+            farm_triple_id,
+        );
+        user_output_payments.push(new_dual_yield_tokens);
+
+        let caller = self.blockchain().get_caller();
+        let _ = Self::Api::send_api_impl().direct_multi_esdt_transfer_execute(
+            &caller,
+            &user_output_payments,
+            0,
+            &ManagedBuffer::new(),
+            &ManagedArgBuffer::new_empty(),
+        );
+
+        user_output_payments.into()
+    }
+
+    /// Swaps half of the rewards into the other pair token and adds both halves as liquidity.
+    fn add_rewards_as_liquidity(
+        &self,
+        farm_triple: &farm_registry::FarmTriple<Self::Api>,
+        rewards: EsdtTokenPayment<Self::Api>,
+        swap_amount_out_min: BigUint,
+        pair_first_token_min_amount: BigUint,
+        pair_second_token_min_amount: BigUint,
+    ) -> result_types::PairAddLiquidityResult<Self::Api> {
+        let (first_token_id, second_token_id) = self.get_pair_tokens(farm_triple);
+        let reward_token_id = rewards.token_identifier.clone();
+        let rewards_are_first_token = reward_token_id == first_token_id;
+        let other_token_id = if rewards_are_first_token {
+            second_token_id
+        } else if reward_token_id == second_token_id {
+            first_token_id
+        } else {
+            sc_panic!("LP farm rewards are not a pair token");
+        };
+
+        let swap_amount = &rewards.amount / 2u32;
+        let remaining_amount = &rewards.amount - &swap_amount;
+        let swapped_tokens = self.pair_swap_fixed_input(
+            farm_triple,
+            EsdtTokenPayment::new(reward_token_id.clone(), 0, swap_amount),
+            other_token_id,
+            swap_amount_out_min,
+        );
+        let remaining_rewards = EsdtTokenPayment::new(reward_token_id, 0, remaining_amount);
+
+        let (first_token_payment, second_token_payment) = if rewards_are_first_token {
+            (remaining_rewards, swapped_tokens)
+        } else {
+            (swapped_tokens, remaining_rewards)
+        };
+
+        self.pair_add_liquidity(
+            farm_triple,
+            first_token_payment,
+            second_token_payment,
+            pair_first_token_min_amount,
+            pair_second_token_min_amount,
+        )
+    }
+
     #[payable("*")]
     #[endpoint(unstakeFarmTokens)]
     fn unstake_farm_tokens(
@@ -222,12 +412,17 @@ pub trait FarmStakingProxy:
         self.require_dual_yield_token(&payment_token);
 
         let attributes = self.get_dual_yield_token_attributes(payment_nonce);
+        let farm_triple = self.get_farm_triple(attributes.farm_triple_id);
         let lp_farm_token_amount =
             self.get_lp_farm_token_amount_equivalent(&attributes, &payment_amount);
-        let lp_farm_exit_result =
-            self.lp_farm_exit(attributes.lp_farm_token_nonce, lp_farm_token_amount);
+        let lp_farm_exit_result = self.lp_farm_exit(
+            &farm_triple,
+            attributes.lp_farm_token_nonce,
+            lp_farm_token_amount,
+        );
 
         let remove_liq_result = self.pair_remove_liquidity(
+            &farm_triple,
             lp_farm_exit_result.lp_tokens,
             pair_first_token_min_amount,
             pair_second_token_min_amount,
@@ -236,6 +431,7 @@ pub trait FarmStakingProxy:
         let staking_farm_token_amount =
             self.get_staking_farm_token_amount_equivalent(&payment_amount);
         let staking_farm_exit_result = self.staking_farm_unstake(
+            &farm_triple,
             remove_liq_result.staking_token_payment,
             attributes.staking_farm_token_nonce,
             staking_farm_token_amount,
@@ -252,6 +448,73 @@ pub trait FarmStakingProxy:
         unstake_result
     }
 
+    /// Exits the dual yield position while keeping the liquidity.
+    /// The staking farm side is unstaked, while the LP farm tokens are sent back to the caller,
+    /// or, if `exit_lp_farm` is set, the LP tokens along with the LP farm rewards.
+    #[payable("*")]
+    #[endpoint(unstakeFarmTokensKeepLiquidity)]
+    fn unstake_farm_tokens_keep_liquidity(
+        &self,
+        #[var_args] opt_exit_lp_farm: OptionalArg<bool>,
+    ) -> UnstakeResult<Self::Api> {
+        let (payment_amount, payment_token) = self.call_value().payment_token_pair();
+        let payment_nonce = self.call_value().esdt_token_nonce();
+
+        self.require_dual_yield_token(&payment_token);
+
+        let attributes = self.get_dual_yield_token_attributes(payment_nonce);
+        let farm_triple = self.get_farm_triple(attributes.farm_triple_id);
+        let lp_farm_token_amount =
+            self.get_lp_farm_token_amount_equivalent(&attributes, &payment_amount);
+
+        let mut user_payments = ManagedVec::new();
+        let exit_lp_farm = opt_exit_lp_farm.into_option().unwrap_or_default();
+        if exit_lp_farm {
+            let lp_farm_exit_result = self.lp_farm_exit(
+                &farm_triple,
+                attributes.lp_farm_token_nonce,
+                lp_farm_token_amount,
+            );
+            user_payments.push(lp_farm_exit_result.lp_tokens);
+            if lp_farm_exit_result.lp_farm_rewards.amount > 0 {
+                user_payments.push(lp_farm_exit_result.lp_farm_rewards);
+            }
+        } else {
+            user_payments.push(EsdtTokenPayment::new(
+                farm_triple.lp_farm_token_id.clone(),
+                attributes.lp_farm_token_nonce,
+                lp_farm_token_amount,
+            ));
+        }
+
+        let staking_farm_token_amount =
+            self.get_staking_farm_token_amount_equivalent(&payment_amount);
+        let staking_farm_exit_result = self.staking_farm_unstake_keep_liquidity(
+            &farm_triple,
+            attributes.staking_farm_token_nonce,
+            staking_farm_token_amount,
+        );
+        if staking_farm_exit_result.staking_rewards.amount > 0 {
+            user_payments.push(staking_farm_exit_result.staking_rewards);
+        }
+        if staking_farm_exit_result.unbond_staking_farm_token.amount > 0 {
+            user_payments.push(staking_farm_exit_result.unbond_staking_farm_token);
+        }
+
+        self.burn_dual_yield_tokens(payment_nonce, &payment_amount);
+
+        let caller = self.blockchain().get_caller();
+        let _ = Self::Api::send_api_impl().direct_multi_esdt_transfer_execute(
+            &caller,
+            &user_payments,
+            0,
+            &ManagedBuffer::new(),
+            &ManagedArgBuffer::new_empty(),
+        );
+
+        user_payments.into()
+    }
+
     fn send_unstake_payments(
         &self,
         other_token_payment: EsdtTokenPayment<Self::Api>,
@@ -0,0 +1,95 @@
+elrond_wasm::imports!();
+
+use config::MAX_PERCENT;
+
+use crate::farm_registry::FarmTriple;
+
+pub type PriceDivergenceResult<BigUint> = MultiResult3<BigUint, BigUint, BigUint>;
+
+#[elrond_wasm::module]
+pub trait PriceCheckModule:
+    crate::farm_registry::FarmRegistryModule
+    + crate::external_contracts_interactions::ExternalContractsInteractionsModule
+    + crate::lp_farm_token::LpFarmTokenModule
+    + token_merge::TokenMergeModule
+{
+    /// Sets the maximum accepted divergence between the safe price and the spot price,
+    /// in hundredths of a percent. Zero disables the check.
+    #[only_owner]
+    #[endpoint(setMaxPriceDivergence)]
+    fn set_max_price_divergence(&self, max_divergence_percent: u64) {
+        require!(
+            max_divergence_percent < MAX_PERCENT,
+            "Invalid divergence percent"
+        );
+
+        self.max_price_divergence_percent()
+            .set(&max_divergence_percent);
+    }
+
+    /// Returns the safe price of the LP tokens, in staking tokens.
+    /// Fails if the spot price diverges from it more than the configured tolerance.
+    fn get_lp_tokens_checked_safe_price(
+        &self,
+        farm_triple: &FarmTriple<Self::Api>,
+        lp_tokens_amount: BigUint,
+    ) -> BigUint {
+        let max_divergence_percent = self.max_price_divergence_percent().get();
+        if max_divergence_percent == 0 {
+            return self.get_lp_tokens_safe_price(farm_triple, lp_tokens_amount);
+        }
+
+        let spot_price = self.get_lp_tokens_spot_price(farm_triple, lp_tokens_amount.clone());
+        let safe_price = self.get_lp_tokens_safe_price(farm_triple, lp_tokens_amount);
+        let divergence_percent = self.get_divergence_percent(&safe_price, &spot_price);
+        require!(
+            divergence_percent <= max_divergence_percent,
+            "Safe price diverges from spot price"
+        );
+
+        safe_price
+    }
+
+    fn get_divergence_percent(&self, safe_price: &BigUint, spot_price: &BigUint) -> BigUint {
+        if spot_price == &0 {
+            return if safe_price == &0 {
+                BigUint::zero()
+            } else {
+                BigUint::from(MAX_PERCENT)
+            };
+        }
+
+        let difference = if safe_price > spot_price {
+            safe_price - spot_price
+        } else {
+            spot_price - safe_price
+        };
+
+        difference * MAX_PERCENT / spot_price
+    }
+
+    /// Returns the safe price and the spot price of the given LP tokens amount, in staking tokens,
+    /// and the divergence between them, in hundredths of a percent.
+    /// The safe price is read as of the last update of the pair.
+    #[view(getPriceDivergence)]
+    fn get_price_divergence(
+        &self,
+        lp_tokens_amount: BigUint,
+        #[var_args] opt_farm_triple_id: OptionalArg<u32>,
+    ) -> PriceDivergenceResult<Self::Api> {
+        let farm_triple_id = opt_farm_triple_id
+            .into_option()
+            .unwrap_or(crate::farm_registry::DEFAULT_FARM_TRIPLE_ID);
+        let farm_triple = self.get_farm_triple(farm_triple_id);
+
+        let spot_price = self.get_lp_tokens_spot_price(&farm_triple, lp_tokens_amount.clone());
+        let safe_price = self.get_lp_tokens_last_safe_price(&farm_triple, lp_tokens_amount);
+        let divergence_percent = self.get_divergence_percent(&safe_price, &spot_price);
+
+        (safe_price, spot_price, divergence_percent).into()
+    }
+
+    #[view(getMaxPriceDivergencePercent)]
+    #[storage_mapper("maxPriceDivergencePercent")]
+    fn max_price_divergence_percent(&self) -> SingleValueMapper<u64>;
+}
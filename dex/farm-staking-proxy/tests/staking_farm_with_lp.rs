@@ -5,9 +5,18 @@ pub mod staking_farm_with_lp_staking_contract_setup;
 
 elrond_wasm::imports!();
 
+use ::config::ConfigModule as _;
 use constants::*;
-use elrond_wasm_debug::{managed_biguint, rust_biguint, DebugApi};
-use farm_staking_proxy::dual_yield_token::DualYieldTokenAttributes;
+use elrond_wasm_debug::testing_framework::StateChange;
+use elrond_wasm_debug::tx_mock::TxInputESDT;
+use elrond_wasm_debug::{
+    managed_address, managed_biguint, managed_token_id, rust_biguint, DebugApi,
+};
+use farm_staking::farm_token_merge::StakingFarmTokenAttributes;
+use farm_staking_proxy::dual_yield_token::{DualYieldTokenAttributes, DualYieldTokenModule};
+use farm_staking_proxy::farm_registry::FarmRegistryModule;
+use farm_staking_proxy::price_check::PriceCheckModule;
+use farm_staking_proxy::*;
 use staking_farm_with_lp_staking_contract_interactions::*;
 
 #[test]
@@ -160,6 +169,242 @@ fn test_unstake_through_proxy_no_claim() {
     );
 }
 
+#[test]
+fn test_compound_dual_yield_then_unstake() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    let expected_staking_token_amount = 1_001_000_000;
+    let dual_yield_token_nonce_after_stake =
+        setup.stake_farm_lp_proxy(1, USER_TOTAL_LP_TOKENS, 1, expected_staking_token_amount);
+
+    setup
+        .b_mock
+        .set_block_nonce(BLOCK_NONCE_AFTER_PAIR_SETUP + 20);
+    setup.b_mock.set_block_epoch(20);
+
+    // staking rewards are compounded, LP farm rewards are sent to the user
+    let dual_yield_token_nonce_after_compound = setup.compound_dual_yield_proxy(
+        dual_yield_token_nonce_after_stake,
+        expected_staking_token_amount,
+        99_999,
+        1_001_001_900,
+    );
+
+    // the compounded rewards are unbonded along with the staking tokens from the pair
+    setup.unstake_proxy(
+        dual_yield_token_nonce_after_compound,
+        1_001_001_900,
+        1_001_000_000,
+        0,
+        0,
+        1_001_001_900,
+        30,
+    );
+}
+
+#[test]
+fn test_compound_dual_yield_then_claim() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    let expected_staking_token_amount = 1_001_000_000;
+    let dual_yield_token_nonce_after_stake =
+        setup.stake_farm_lp_proxy(1, USER_TOTAL_LP_TOKENS, 1, expected_staking_token_amount);
+
+    setup
+        .b_mock
+        .set_block_nonce(BLOCK_NONCE_AFTER_PAIR_SETUP + 20);
+    setup.b_mock.set_block_epoch(20);
+
+    let dual_yield_token_nonce_after_compound = setup.compound_dual_yield_proxy(
+        dual_yield_token_nonce_after_stake,
+        expected_staking_token_amount,
+        99_999,
+        1_001_001_900,
+    );
+
+    // claiming in the same block only updates the staking farm value,
+    // which must still contain the compounded rewards
+    let mut dual_yield_token_nonce_after_claim = 0;
+    setup
+        .b_mock
+        .execute_esdt_transfer(
+            &setup.user_addr,
+            &setup.proxy_wrapper,
+            DUAL_YIELD_TOKEN_ID,
+            dual_yield_token_nonce_after_compound,
+            &rust_biguint!(1_001_001_900),
+            |sc| {
+                let received_tokens = sc.claim_dual_yield().to_vec();
+                assert_eq!(received_tokens.len(), 1);
+
+                let new_dual_yield_tokens = received_tokens.get(0);
+                assert_eq!(new_dual_yield_tokens.amount, 1_001_001_900);
+                dual_yield_token_nonce_after_claim = new_dual_yield_tokens.token_nonce;
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup
+        .b_mock
+        .execute_query(&setup.proxy_wrapper, |sc| {
+            let dual_yield_attributes =
+                sc.get_dual_yield_token_attributes(dual_yield_token_nonce_after_claim);
+            assert_eq!(
+                dual_yield_attributes.staking_farm_token_amount,
+                managed_biguint!(1_001_001_900)
+            );
+
+            let staking_farm_token_data = sc.blockchain().get_esdt_token_data(
+                &sc.blockchain().get_sc_address(),
+                &managed_token_id!(STAKING_FARM_TOKEN_ID),
+                dual_yield_attributes.staking_farm_token_nonce,
+            );
+            let staking_farm_attributes = staking_farm_token_data
+                .decode_attributes_or_exit::<StakingFarmTokenAttributes<DebugApi>>();
+            assert_eq!(
+                staking_farm_attributes.current_farm_amount,
+                managed_biguint!(1_001_001_900)
+            );
+            assert_eq!(
+                staking_farm_attributes.compounded_reward,
+                managed_biguint!(1_900)
+            );
+        })
+        .assert_ok();
+
+    // the compounded rewards are unbonded along with the staking tokens from the pair
+    setup.unstake_proxy(
+        dual_yield_token_nonce_after_claim,
+        1_001_001_900,
+        1_001_000_000,
+        0,
+        0,
+        1_001_001_900,
+        30,
+    );
+}
+
+#[test]
+fn test_claim_dual_yield_with_several_compounded_tokens() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    let expected_staking_token_amount = 1_001_000_000;
+    let dual_yield_token_nonce_after_stake =
+        setup.stake_farm_lp_proxy(1, USER_TOTAL_LP_TOKENS, 1, expected_staking_token_amount);
+
+    setup
+        .b_mock
+        .set_block_nonce(BLOCK_NONCE_AFTER_PAIR_SETUP + 20);
+    setup.b_mock.set_block_epoch(20);
+
+    let dual_yield_token_nonce_after_compound = setup.compound_dual_yield_proxy(
+        dual_yield_token_nonce_after_stake,
+        expected_staking_token_amount,
+        99_999,
+        1_001_001_900,
+    );
+
+    // claiming half of the position in the same block splits it in two compounded positions
+    let mut split_dual_yield_token_nonce = 0;
+    setup
+        .b_mock
+        .execute_esdt_transfer(
+            &setup.user_addr,
+            &setup.proxy_wrapper,
+            DUAL_YIELD_TOKEN_ID,
+            dual_yield_token_nonce_after_compound,
+            &rust_biguint!(500_500_950),
+            |sc| {
+                let received_tokens = sc.claim_dual_yield().to_vec();
+                assert_eq!(received_tokens.len(), 1);
+
+                let new_dual_yield_tokens = received_tokens.get(0);
+                assert_eq!(new_dual_yield_tokens.amount, 500_500_950);
+                split_dual_yield_token_nonce = new_dual_yield_tokens.token_nonce;
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    let transfers = vec![
+        TxInputESDT {
+            token_identifier: DUAL_YIELD_TOKEN_ID.to_vec(),
+            nonce: dual_yield_token_nonce_after_compound,
+            value: rust_biguint!(500_500_950),
+        },
+        TxInputESDT {
+            token_identifier: DUAL_YIELD_TOKEN_ID.to_vec(),
+            nonce: split_dual_yield_token_nonce,
+            value: rust_biguint!(500_500_950),
+        },
+    ];
+    let mut merged_dual_yield_token_nonce = 0;
+    setup
+        .b_mock
+        .execute_esdt_multi_transfer(&setup.user_addr, &setup.proxy_wrapper, &transfers, |sc| {
+            let received_tokens = sc.claim_dual_yield().to_vec();
+            assert_eq!(received_tokens.len(), 1);
+
+            let new_dual_yield_tokens = received_tokens.get(0);
+            assert_eq!(new_dual_yield_tokens.amount, 1_001_001_900);
+            merged_dual_yield_token_nonce = new_dual_yield_tokens.token_nonce;
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    // the compounded rewards of both positions are kept
+    setup
+        .b_mock
+        .execute_query(&setup.proxy_wrapper, |sc| {
+            let dual_yield_attributes =
+                sc.get_dual_yield_token_attributes(merged_dual_yield_token_nonce);
+            let staking_farm_token_data = sc.blockchain().get_esdt_token_data(
+                &sc.blockchain().get_sc_address(),
+                &managed_token_id!(STAKING_FARM_TOKEN_ID),
+                dual_yield_attributes.staking_farm_token_nonce,
+            );
+            let staking_farm_attributes = staking_farm_token_data
+                .decode_attributes_or_exit::<StakingFarmTokenAttributes<DebugApi>>();
+            assert_eq!(
+                staking_farm_attributes.current_farm_amount,
+                managed_biguint!(1_001_001_900)
+            );
+            assert_eq!(
+                staking_farm_attributes.compounded_reward,
+                managed_biguint!(1_900)
+            );
+        })
+        .assert_ok();
+    setup
+        .b_mock
+        .execute_query(&setup.staking_farm_wrapper, |sc| {
+            assert_eq!(
+                sc.farm_token_supply().get(),
+                managed_biguint!(1_001_001_900)
+            );
+        })
+        .assert_ok();
+}
+
 #[test]
 fn unstake_through_proxy_after_claim() {
     let mut setup = FarmStakingSetup::new(
@@ -295,6 +540,7 @@ fn test_stake_farm_through_proxy_with_merging() {
                 lp_farm_token_amount: managed_biguint!(400_000_000),
                 staking_farm_token_nonce: 1,
                 staking_farm_token_amount: managed_biguint!(400_000_000),
+                farm_triple_id: 0,
             },
         )
     });
@@ -326,6 +572,7 @@ fn test_stake_farm_through_proxy_with_merging() {
                 lp_farm_token_amount: managed_biguint!(1_000_000_000),
                 staking_farm_token_nonce: 2,
                 staking_farm_token_amount: managed_biguint!(1_000_000_000),
+                farm_triple_id: 0,
             },
         )
     });
@@ -348,3 +595,213 @@ fn test_stake_farm_through_proxy_with_merging() {
         )
     });
 }
+
+#[test]
+fn test_stake_farm_proxy_registered_farm_triple() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    let pair_addr = setup.pair_wrapper.address_ref().clone();
+    let lp_farm_addr = setup.lp_farm_wrapper.address_ref().clone();
+    let staking_farm_addr = setup.staking_farm_wrapper.address_ref().clone();
+    setup
+        .b_mock
+        .execute_tx(
+            &setup.owner_addr,
+            &setup.proxy_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let farm_triple_id = sc.add_farm_triple(
+                    managed_address!(&pair_addr),
+                    managed_address!(&lp_farm_addr),
+                    managed_address!(&staking_farm_addr),
+                    managed_token_id!(STAKING_TOKEN_ID),
+                    managed_token_id!(LP_FARM_TOKEN_ID),
+                    managed_token_id!(STAKING_FARM_TOKEN_ID),
+                );
+                assert_eq!(farm_triple_id, 1);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    let expected_staking_token_amount = 1_001_000_000;
+    let mut dual_yield_token_nonce = 0;
+    setup
+        .b_mock
+        .execute_esdt_transfer(
+            &setup.user_addr,
+            &setup.proxy_wrapper,
+            LP_FARM_TOKEN_ID,
+            1,
+            &rust_biguint!(USER_TOTAL_LP_TOKENS),
+            |sc| {
+                let dual_yield_tokens = sc.stake_farm_tokens(OptionalArg::Some(1));
+                dual_yield_token_nonce = dual_yield_tokens.token_nonce;
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup.b_mock.execute_in_managed_environment(|| {
+        setup.b_mock.check_nft_balance(
+            &setup.user_addr,
+            DUAL_YIELD_TOKEN_ID,
+            dual_yield_token_nonce,
+            &rust_biguint!(expected_staking_token_amount),
+            &DualYieldTokenAttributes::<DebugApi> {
+                lp_farm_token_nonce: 1,
+                lp_farm_token_amount: managed_biguint!(USER_TOTAL_LP_TOKENS),
+                staking_farm_token_nonce: 1,
+                staking_farm_token_amount: managed_biguint!(expected_staking_token_amount),
+                farm_triple_id: 1,
+            },
+        )
+    });
+
+    // unknown triples are rejected
+    setup
+        .b_mock
+        .execute_tx(
+            &setup.user_addr,
+            &setup.proxy_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let _ = sc.get_farm_triple(2);
+
+                StateChange::Revert
+            },
+        )
+        .assert_user_error("Unknown farm triple");
+}
+
+#[test]
+fn test_stake_farm_proxy_with_price_divergence_check() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    setup
+        .b_mock
+        .execute_tx(
+            &setup.owner_addr,
+            &setup.proxy_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_price_divergence(100);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup
+        .b_mock
+        .execute_query(&setup.proxy_wrapper, |sc| {
+            let (safe_price, spot_price, divergence_percent) = sc
+                .get_price_divergence(managed_biguint!(USER_TOTAL_LP_TOKENS), OptionalArg::None)
+                .into_tuple();
+            assert_eq!(safe_price, managed_biguint!(1_001_000_000));
+            assert!(spot_price > 0u32);
+            assert!(divergence_percent <= 100u32);
+        })
+        .assert_ok();
+
+    // prices are in sync, so staking is allowed
+    let expected_staking_token_amount = 1_001_000_000;
+    let _dual_yield_token_nonce =
+        setup.stake_farm_lp_proxy(1, USER_TOTAL_LP_TOKENS, 1, expected_staking_token_amount);
+
+    setup
+        .b_mock
+        .execute_tx(
+            &setup.owner_addr,
+            &setup.proxy_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_price_divergence(10_000);
+
+                StateChange::Revert
+            },
+        )
+        .assert_user_error("Invalid divergence percent");
+}
+
+#[test]
+fn test_unstake_through_proxy_keep_liquidity() {
+    let mut setup = FarmStakingSetup::new(
+        pair::contract_obj,
+        farm::contract_obj,
+        farm_staking::contract_obj,
+        farm_staking_proxy::contract_obj,
+    );
+
+    let expected_staking_token_amount = 1_001_000_000;
+    let dual_yield_token_nonce_after_stake =
+        setup.stake_farm_lp_proxy(1, USER_TOTAL_LP_TOKENS, 1, expected_staking_token_amount);
+
+    setup
+        .b_mock
+        .set_block_nonce(BLOCK_NONCE_AFTER_PAIR_SETUP + 20);
+    setup.b_mock.set_block_epoch(20);
+
+    setup
+        .b_mock
+        .execute_esdt_transfer(
+            &setup.user_addr,
+            &setup.proxy_wrapper,
+            DUAL_YIELD_TOKEN_ID,
+            dual_yield_token_nonce_after_stake,
+            &rust_biguint!(expected_staking_token_amount),
+            |sc| {
+                let received_tokens = sc
+                    .unstake_farm_tokens_keep_liquidity(OptionalArg::None)
+                    .to_vec();
+                assert_eq!(received_tokens.len(), 2);
+
+                let lp_farm_tokens = received_tokens.get(0);
+                assert_eq!(
+                    lp_farm_tokens.token_identifier,
+                    managed_token_id!(LP_FARM_TOKEN_ID)
+                );
+                assert_eq!(lp_farm_tokens.token_nonce, 1);
+                assert_eq!(
+                    lp_farm_tokens.amount,
+                    managed_biguint!(USER_TOTAL_LP_TOKENS)
+                );
+
+                let staking_rewards = received_tokens.get(1);
+                assert_eq!(staking_rewards.amount, managed_biguint!(1_900));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    // the LP farm position is kept, nothing is left to unbond
+    setup.b_mock.execute_in_managed_environment(|| {
+        setup.b_mock.check_nft_balance(
+            &setup.user_addr,
+            LP_FARM_TOKEN_ID,
+            1,
+            &rust_biguint!(USER_TOTAL_LP_TOKENS),
+            &(),
+        )
+    });
+    setup.b_mock.check_nft_balance(
+        &setup.user_addr,
+        DUAL_YIELD_TOKEN_ID,
+        dual_yield_token_nonce_after_stake,
+        &rust_biguint!(0),
+        &(),
+    );
+}
@@ -1,4 +1,4 @@
-use elrond_wasm::types::{Address, BigUint};
+use elrond_wasm::types::{Address, BigUint, OptionalArg};
 use elrond_wasm_debug::{
     managed_biguint, rust_biguint,
     testing_framework::{BlockchainStateWrapper, ContractObjWrapper, StateChange},
@@ -124,7 +124,7 @@ where
                 lp_farm_token_nonce,
                 &rust_biguint!(lp_farm_token_stake_amount),
                 |sc| {
-                    let dual_yield_tokens = sc.stake_farm_tokens();
+                    let dual_yield_tokens = sc.stake_farm_tokens(OptionalArg::None);
                     dual_yield_nonce = dual_yield_tokens.token_nonce;
 
                     assert_eq!(
@@ -143,6 +143,7 @@ where
                 lp_farm_token_amount: managed_biguint!(lp_farm_token_stake_amount),
                 staking_farm_token_nonce: expected_staking_farm_token_nonce,
                 staking_farm_token_amount: managed_biguint!(expected_staking_token_amount),
+                farm_triple_id: 0,
             };
 
             self.b_mock.check_nft_balance(
@@ -182,7 +183,7 @@ where
 
         self.b_mock
             .execute_esdt_multi_transfer(&self.user_addr, &self.proxy_wrapper, &transfers, |sc| {
-                let new_dual_yield_token = sc.stake_farm_tokens();
+                let new_dual_yield_token = sc.stake_farm_tokens(OptionalArg::None);
                 dual_yield_nonce = new_dual_yield_token.token_nonce;
 
                 StateChange::Commit
@@ -235,6 +236,50 @@ where
         dual_yield_nonce
     }
 
+    pub fn compound_dual_yield_proxy(
+        &mut self,
+        dual_yield_token_nonce: u64,
+        dual_yield_token_amount: u64,
+        expected_lp_farm_reward_amount: u64,
+        expected_new_dual_yield_token_amount: u64,
+    ) -> u64 {
+        let mut dual_yield_nonce = 0;
+
+        self.b_mock
+            .execute_esdt_transfer(
+                &self.user_addr,
+                &self.proxy_wrapper,
+                DUAL_YIELD_TOKEN_ID,
+                dual_yield_token_nonce,
+                &rust_biguint!(dual_yield_token_amount),
+                |sc| {
+                    let received_tokens = sc
+                        .compound_dual_yield(
+                            false,
+                            managed_biguint!(1),
+                            managed_biguint!(1),
+                            managed_biguint!(1),
+                        )
+                        .to_vec();
+                    let lp_farm_rewards = received_tokens.get(0);
+                    let new_dual_yield_tokens = received_tokens.get(1);
+
+                    dual_yield_nonce = new_dual_yield_tokens.token_nonce;
+
+                    assert_eq!(lp_farm_rewards.amount, expected_lp_farm_reward_amount);
+                    assert_eq!(
+                        new_dual_yield_tokens.amount,
+                        expected_new_dual_yield_token_amount
+                    );
+
+                    StateChange::Commit
+                },
+            )
+            .assert_ok();
+
+        dual_yield_nonce
+    }
+
     pub fn unstake_proxy(
         &mut self,
         dual_yield_token_nonce: u64,
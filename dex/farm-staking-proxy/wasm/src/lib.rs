@@ -8,16 +8,28 @@ elrond_wasm_node::wasm_endpoints! {
     farm_staking_proxy
     (
         callBack
+        addFarmTriple
         claimDualYield
+        compoundDualYield
         getDualYieldTokenId
         getFarmTokenId
+        getFarmTriple
+        getFarmTriples
         getLpFarmAddress
         getLpFarmTokenId
+        getMaxPriceDivergencePercent
         getPairAddress
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
+        getPriceDivergence
         getStakingFarmAddress
         getStakingTokenId
         issueDualYieldToken
+        setMaxPriceDivergence
+        setPositionTokenMetadata
         stakeFarmTokens
         unstakeFarmTokens
+        unstakeFarmTokensKeepLiquidity
     )
 }
@@ -2,9 +2,20 @@ elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
 use common_structs::Nonce;
-use config::MAX_PERCENT;
-
-pub const BLOCKS_IN_YEAR: u64 = 31_536_000 / 6; // seconds_in_year / 6_seconds_per_block
+use config::{DEFAULT_BLOCK_TIME_SECONDS, MAX_PERCENT, SECONDS_IN_YEAR};
+
+pub const BLOCKS_IN_YEAR: u64 = SECONDS_IN_YEAR / DEFAULT_BLOCK_TIME_SECONDS;
+
+#[derive(TopEncode, TopDecode, PartialEq, TypeAbi, Clone, Copy)]
+pub enum AprCapMode {
+    /// Rewards are capped at `max_annual_percentage_rewards`.
+    FixedApr,
+    /// The cap decreases linearly from the max APR, when nothing is staked,
+    /// down to the min APR, when the staked supply reaches the utilization target.
+    UtilizationCurve,
+    /// Rewards are only limited by the reward capacity.
+    UncappedWithinReserve,
+}
 
 #[elrond_wasm::module]
 pub trait CustomRewardsModule:
@@ -74,10 +85,52 @@ pub trait CustomRewardsModule:
     #[endpoint(setMaxApr)]
     fn set_max_apr(&self, max_apr: BigUint) {
         require!(max_apr != 0, "Max APR cannot be zero");
+        require!(
+            max_apr >= self.min_annual_percentage_rewards().get(),
+            "Max APR cannot be below min APR"
+        );
 
         self.max_annual_percentage_rewards().set(&max_apr);
     }
 
+    #[only_owner]
+    #[endpoint(setAprCapMode)]
+    fn set_apr_cap_mode(&self, mode: AprCapMode) {
+        if mode == AprCapMode::UtilizationCurve {
+            require!(
+                self.utilization_target_supply().get() != 0,
+                "Utilization curve not set"
+            );
+        }
+
+        self.apr_cap_mode().set(&mode);
+    }
+
+    #[only_owner]
+    #[endpoint(setUtilizationCurve)]
+    fn set_utilization_curve(&self, min_apr: BigUint, target_supply: BigUint) {
+        require!(target_supply != 0, "Target supply cannot be zero");
+        require!(
+            min_apr <= self.max_annual_percentage_rewards().get(),
+            "Min APR cannot exceed max APR"
+        );
+
+        self.min_annual_percentage_rewards().set(&min_apr);
+        self.utilization_target_supply().set(&target_supply);
+    }
+
+    #[only_owner]
+    #[endpoint(setBlockTime)]
+    fn set_block_time(&self, block_time_seconds: u64) {
+        require!(
+            block_time_seconds != 0 && block_time_seconds <= SECONDS_IN_YEAR,
+            "Invalid block time"
+        );
+
+        self.generate_aggregated_rewards();
+        self.block_time_seconds().set(&block_time_seconds);
+    }
+
     #[only_owner]
     #[endpoint(setMinUnbondEpochs)]
     fn set_min_unbond_epochs(&self, min_unbond_epochs: u64) {
@@ -143,16 +196,90 @@ pub trait CustomRewardsModule:
             return unbounded_rewards;
         }
 
-        let max_apr = self.max_annual_percentage_rewards().get();
+        let max_apr = match self.get_apr_cap() {
+            Some(max_apr) => max_apr,
+            None => return unbounded_rewards,
+        };
         let current_block = self.blockchain().get_block_nonce();
         let block_diff = current_block - last_claim_block;
 
-        let max_rewards_for_user_per_block = amount * &max_apr / MAX_PERCENT / BLOCKS_IN_YEAR;
+        let max_rewards_for_user_per_block =
+            amount * &max_apr / MAX_PERCENT / self.get_blocks_in_year();
         let max_rewards_for_user = max_rewards_for_user_per_block * block_diff;
 
         core::cmp::min(unbounded_rewards, max_rewards_for_user)
     }
 
+    /// The APR cap for the current mode, or None if rewards are only limited by the reserve.
+    fn get_apr_cap(&self) -> Option<BigUint> {
+        let max_apr = self.max_annual_percentage_rewards().get();
+        match self.apr_cap_mode().get() {
+            AprCapMode::FixedApr => Some(max_apr),
+            AprCapMode::UtilizationCurve => {
+                let min_apr = self.min_annual_percentage_rewards().get();
+                let target_supply = self.utilization_target_supply().get();
+                let farm_token_supply = self.farm_token_supply().get();
+                let utilized_supply = core::cmp::min(farm_token_supply, target_supply.clone());
+
+                let apr_decrease = (&max_apr - &min_apr) * &utilized_supply / &target_supply;
+                Some(max_apr - apr_decrease)
+            }
+            AprCapMode::UncappedWithinReserve => None,
+        }
+    }
+
+    fn get_blocks_in_year(&self) -> u64 {
+        let block_time_seconds = self.block_time_seconds().get();
+        if block_time_seconds == 0 {
+            return BLOCKS_IN_YEAR;
+        }
+
+        SECONDS_IN_YEAR / block_time_seconds
+    }
+
+    /// Returns the effective APR, which is the emission APR limited by the current cap,
+    /// and the per block emission amount that is clipped by the cap or by the exhausted reserve.
+    #[view(getEffectiveAprInfo)]
+    fn get_effective_apr_info(&self) -> MultiResult2<BigUint, BigUint> {
+        let per_block_reward = if self.produces_per_block_rewards() {
+            self.per_block_reward_amount().get()
+        } else {
+            BigUint::zero()
+        };
+        let blocks_in_year = self.get_blocks_in_year();
+        let farm_token_supply = self.farm_token_supply().get();
+        let opt_apr_cap = self.get_apr_cap();
+
+        if farm_token_supply == 0 {
+            let apr = opt_apr_cap.unwrap_or_else(BigUint::zero);
+            return (apr, BigUint::zero()).into();
+        }
+
+        let emission_apr =
+            per_block_reward.clone() * blocks_in_year * MAX_PERCENT / &farm_token_supply;
+        match opt_apr_cap {
+            Some(apr_cap) => {
+                if emission_apr <= apr_cap {
+                    return (emission_apr, BigUint::zero()).into();
+                }
+
+                let max_per_block_reward =
+                    &farm_token_supply * &apr_cap / MAX_PERCENT / blocks_in_year;
+                let clipped_per_block = per_block_reward - max_per_block_reward;
+                (apr_cap, clipped_per_block).into()
+            }
+            None => {
+                let reward_capacity = self.reward_capacity().get();
+                let accumulated_rewards = self.accumulated_rewards().get();
+                if accumulated_rewards >= reward_capacity {
+                    (BigUint::zero(), per_block_reward).into()
+                } else {
+                    (emission_apr, BigUint::zero()).into()
+                }
+            }
+        }
+    }
+
     #[only_owner]
     #[endpoint(startProduceRewards)]
     fn start_produce_rewards(&self) {
@@ -190,6 +317,22 @@ pub trait CustomRewardsModule:
     #[storage_mapper("annualPercentageRewards")]
     fn max_annual_percentage_rewards(&self) -> SingleValueMapper<BigUint>;
 
+    #[view(getAprCapMode)]
+    #[storage_mapper("aprCapMode")]
+    fn apr_cap_mode(&self) -> SingleValueMapper<AprCapMode>;
+
+    #[view(getMinAnnualPercentageRewards)]
+    #[storage_mapper("minAnnualPercentageRewards")]
+    fn min_annual_percentage_rewards(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getUtilizationTargetSupply)]
+    #[storage_mapper("utilizationTargetSupply")]
+    fn utilization_target_supply(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getBlockTimeSeconds)]
+    #[storage_mapper("blockTimeSeconds")]
+    fn block_time_seconds(&self) -> SingleValueMapper<u64>;
+
     #[view(getMinUnbondEpochs)]
     #[storage_mapper("minUnbondEpochs")]
     fn min_unbond_epochs(&self) -> SingleValueMapper<u64>;
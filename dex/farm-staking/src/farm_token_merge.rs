@@ -2,6 +2,7 @@ elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
 use token_merge::ValueWeight;
+use token_metadata::{DisplayAttributesWriter, PositionDisplayAttributes, PositionEnteringEpoch};
 
 #[derive(
     ManagedVecItem,
@@ -21,6 +22,21 @@ pub struct StakingFarmTokenAttributes<M: ManagedTypeApi> {
     pub current_farm_amount: BigUint<M>,
 }
 
+impl<M: ManagedTypeApi> PositionEnteringEpoch for StakingFarmTokenAttributes<M> {
+    fn get_entering_epoch(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for StakingFarmTokenAttributes<M> {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_biguint(b"rewardPerShare", &self.reward_per_share);
+        writer.add_u64(b"lastClaimBlock", self.last_claim_block);
+        writer.add_biguint(b"compoundedReward", &self.compounded_reward);
+        writer.add_biguint(b"currentFarmAmount", &self.current_farm_amount);
+    }
+}
+
 #[derive(ManagedVecItem, Clone)]
 pub struct StakingFarmToken<M: ManagedTypeApi> {
     pub token_amount: EsdtTokenPayment<M>,
@@ -54,6 +70,90 @@ pub trait FarmTokenMergeModule:
         self.create_payment(&farm_token_id, new_nonce, &new_amount)
     }
 
+    #[payable("*")]
+    #[endpoint(splitFarmToken)]
+    fn split_farm_token(
+        &self,
+        split_amounts: ManagedVec<BigUint>,
+    ) -> ManagedMultiResultVec<EsdtTokenPayment<Self::Api>> {
+        let caller = self.blockchain().get_caller();
+        let (payment_amount, payment_token_id) = self.call_value().payment_token_pair();
+        let payment_token_nonce = self.call_value().esdt_token_nonce();
+
+        let farm_token_id = self.farm_token_id().get();
+        require!(payment_token_id == farm_token_id, "Not a farm token");
+        require!(payment_token_nonce != 0, "Not a farm token");
+        require!(payment_amount != 0u64, "Zero amount");
+        require!(split_amounts.len() > 1, "Must split in at least two tokens");
+
+        let attributes: StakingFarmTokenAttributes<Self::Api> =
+            self.get_attributes(&payment_token_id, payment_token_nonce);
+        let split_attributes =
+            self.get_split_farm_token_attributes(&payment_amount, &attributes, &split_amounts);
+
+        self.burn_farm_tokens(&farm_token_id, payment_token_nonce, &payment_amount);
+
+        let mut new_farm_tokens = ManagedVec::new();
+        for attrs in split_attributes.iter() {
+            let new_amount = attrs.current_farm_amount.clone();
+            let new_nonce = self.mint_farm_tokens(&farm_token_id, &new_amount, &attrs);
+            new_farm_tokens.push(self.create_payment(&farm_token_id, new_nonce, &new_amount));
+        }
+
+        self.send_multiple_tokens(&caller, &new_farm_tokens, &OptionalArg::None);
+
+        new_farm_tokens.into()
+    }
+
+    fn get_split_farm_token_attributes(
+        &self,
+        payment_amount: &BigUint,
+        attributes: &StakingFarmTokenAttributes<Self::Api>,
+        split_amounts: &ManagedVec<BigUint>,
+    ) -> ManagedVec<StakingFarmTokenAttributes<Self::Api>> {
+        let mut split_amounts_sum = BigUint::zero();
+        for amount in split_amounts.iter() {
+            require!(*amount != 0u64, "zero split amount");
+            split_amounts_sum += &*amount;
+        }
+        require!(
+            &split_amounts_sum == payment_amount,
+            "Split amounts do not sum up to payment amount"
+        );
+
+        // The last position receives the rounding leftovers, so that the sum of
+        // the new positions is always equal to the position that was split.
+        let mut remaining_compounded_reward = self.rule_of_three(
+            payment_amount,
+            &attributes.current_farm_amount,
+            &attributes.compounded_reward,
+        );
+        let last_index = split_amounts.len() - 1;
+
+        let mut result = ManagedVec::new();
+        for (i, amount) in split_amounts.iter().enumerate() {
+            let compounded_reward = if i == last_index {
+                remaining_compounded_reward.clone()
+            } else {
+                self.rule_of_three(
+                    &amount,
+                    &attributes.current_farm_amount,
+                    &attributes.compounded_reward,
+                )
+            };
+            remaining_compounded_reward -= &compounded_reward;
+
+            result.push(StakingFarmTokenAttributes {
+                reward_per_share: attributes.reward_per_share.clone(),
+                last_claim_block: attributes.last_claim_block,
+                compounded_reward,
+                current_farm_amount: (*amount).clone(),
+            });
+        }
+
+        result
+    }
+
     fn get_merged_farm_token_attributes(
         &self,
         payments: &ManagedVec<EsdtTokenPayment<Self::Api>>,
@@ -0,0 +1,99 @@
+elrond_wasm::imports!();
+
+use config::MAX_PERCENT;
+
+#[elrond_wasm::module]
+pub trait InstantUnbondModule:
+    crate::custom_rewards::CustomRewardsModule
+    + config::ConfigModule
+    + token_send::TokenSendModule
+    + farm_token::FarmTokenModule
+{
+    #[only_owner]
+    #[endpoint(setInstantUnbondEnabled)]
+    fn set_instant_unbond_enabled(&self, enabled: bool) {
+        self.instant_unbond_enabled().set(&enabled);
+    }
+
+    #[only_owner]
+    #[endpoint(setInstantUnbondFee)]
+    fn set_instant_unbond_fee(&self, fee_percent: u64) {
+        require!(fee_percent < MAX_PERCENT, "Invalid fee percent");
+
+        self.instant_unbond_fee_percent().set(&fee_percent);
+    }
+
+    /// Sets the address receiving the unbond fees.
+    /// If no address is given, the fees are added to the reward capacity instead.
+    #[only_owner]
+    #[endpoint(setUnbondFeeTreasury)]
+    fn set_unbond_fee_treasury(&self, #[var_args] opt_treasury: OptionalArg<ManagedAddress>) {
+        match opt_treasury {
+            OptionalArg::Some(treasury) => {
+                require!(!treasury.is_zero(), "Invalid treasury address");
+                self.unbond_fee_treasury().set(&treasury);
+            }
+            OptionalArg::None => self.unbond_fee_treasury().clear(),
+        }
+    }
+
+    fn require_instant_unbond_enabled(&self) {
+        require!(
+            self.instant_unbond_enabled().get(),
+            "Instant unbond is not enabled"
+        );
+    }
+
+    fn get_instant_unbond_fee(&self, amount: &BigUint) -> BigUint {
+        amount * self.instant_unbond_fee_percent().get() / MAX_PERCENT
+    }
+
+    /// The fee for redeeming an unbond position early decreases linearly
+    /// with the number of epochs left until the unlock epoch.
+    fn get_early_redeem_fee(&self, unlock_epoch: u64, amount: &BigUint) -> BigUint {
+        let current_epoch = self.blockchain().get_block_epoch();
+        if current_epoch >= unlock_epoch {
+            return BigUint::zero();
+        }
+
+        let min_unbond_epochs = self.min_unbond_epochs().get();
+        let remaining_epochs = unlock_epoch - current_epoch;
+        if min_unbond_epochs == 0 || remaining_epochs >= min_unbond_epochs {
+            return self.get_instant_unbond_fee(amount);
+        }
+
+        self.get_instant_unbond_fee(amount) * remaining_epochs / min_unbond_epochs
+    }
+
+    fn send_unbond_fee(&self, fee_amount: &BigUint) {
+        if fee_amount == &0 {
+            return;
+        }
+
+        let treasury_mapper = self.unbond_fee_treasury();
+        if treasury_mapper.is_empty() {
+            self.reward_capacity().update(|r| *r += fee_amount);
+        } else {
+            let farming_token_id = self.farming_token_id().get();
+            self.send().direct(
+                &treasury_mapper.get(),
+                &farming_token_id,
+                0,
+                fee_amount,
+                &[],
+            );
+        }
+    }
+
+    #[view(isInstantUnbondEnabled)]
+    #[storage_mapper("instantUnbondEnabled")]
+    fn instant_unbond_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[view(getInstantUnbondFeePercent)]
+    #[storage_mapper("instantUnbondFeePercent")]
+    fn instant_unbond_fee_percent(&self) -> SingleValueMapper<u64>;
+
+    #[view(getUnbondFeeTreasury)]
+    #[storage_mapper("unbondFeeTreasury")]
+    fn unbond_fee_treasury(&self) -> SingleValueMapper<ManagedAddress>;
+}
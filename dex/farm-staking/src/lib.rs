@@ -5,6 +5,8 @@
 
 pub mod custom_rewards;
 pub mod farm_token_merge;
+pub mod instant_unbond;
+pub mod unbond_queue;
 pub mod whitelist;
 
 use common_structs::Nonce;
@@ -18,6 +20,7 @@ use config::{
     DEFAULT_BURN_GAS_LIMIT, DEFAULT_MINUMUM_FARMING_EPOCHS, DEFAULT_TRANSFER_EXEC_GAS_LIMIT,
 };
 use farm_token_merge::StakingFarmToken;
+use token_metadata::{DisplayAttributesWriter, PositionDisplayAttributes};
 
 pub type EnterFarmResultType<BigUint> = EsdtTokenPayment<BigUint>;
 pub type CompoundRewardsResultType<BigUint> = EsdtTokenPayment<BigUint>;
@@ -32,6 +35,12 @@ pub struct UnbondSftAttributes {
     pub unlock_epoch: u64,
 }
 
+impl<M: ManagedTypeApi> PositionDisplayAttributes<M> for UnbondSftAttributes {
+    fn write_display_attributes(&self, writer: &mut DisplayAttributesWriter<M>) {
+        writer.add_u64(b"unlockEpoch", self.unlock_epoch);
+    }
+}
+
 #[elrond_wasm::contract]
 pub trait Farm:
     custom_rewards::CustomRewardsModule
@@ -39,8 +48,11 @@ pub trait Farm:
     + token_send::TokenSendModule
     + token_merge::TokenMergeModule
     + farm_token::FarmTokenModule
+    + token_metadata::TokenMetadataModule
     + farm_token_merge::FarmTokenMergeModule
     + whitelist::WhitelistModule
+    + instant_unbond::InstantUnbondModule
+    + unbond_queue::UnbondQueueModule
 {
     #[proxy]
     fn pair_contract_proxy(&self, to: ManagedAddress) -> pair::Proxy<Self::Api>;
@@ -169,36 +181,102 @@ pub trait Farm:
         self.unstake_farm_common(payment_token_id, token_nonce, amount, None)
     }
 
+    /// Exits the position without going through the unbond period.
+    /// The farming tokens are returned immediately, minus the instant unbond fee.
+    #[payable("*")]
+    #[endpoint(unstakeFarmInstant)]
+    fn unstake_farm_instant(&self) -> ExitFarmResultType<Self::Api> {
+        self.require_instant_unbond_enabled();
+
+        let (amount, payment_token_id) = self.call_value().payment_token_pair();
+        let token_nonce = self.call_value().esdt_token_nonce();
+
+        let (unbond_amount, reward) =
+            self.exit_staking_position(payment_token_id, token_nonce, amount, None);
+
+        let fee_amount = self.get_instant_unbond_fee(&unbond_amount);
+        self.send_unbond_fee(&fee_amount);
+
+        let caller = self.blockchain().get_caller();
+        let farming_token_id = self.farming_token_id().get();
+        let farming_token_amount = unbond_amount - fee_amount;
+        self.send()
+            .direct(&caller, &farming_token_id, 0, &farming_token_amount, &[]);
+
+        let reward_token_id = self.reward_token_id().get();
+        self.send_rewards(&reward_token_id, &reward, &caller);
+
+        MultiResult2::from((
+            EsdtTokenPayment::new(farming_token_id, 0, farming_token_amount),
+            EsdtTokenPayment::new(reward_token_id, 0, reward),
+        ))
+    }
+
+    /// The unbond amount is the amount of staking tokens sent along with the farm tokens.
+    /// If `unbond_compounded_rewards` is set, the rewards compounded into the position are unbonded as well.
     #[payable("*")]
     #[endpoint(unstakeFarmThroughProxy)]
-    fn unstake_farm_through_proxy(&self) -> ExitFarmResultType<Self::Api> {
+    fn unstake_farm_through_proxy(
+        &self,
+        #[var_args] opt_unbond_compounded_rewards: OptionalArg<bool>,
+    ) -> ExitFarmResultType<Self::Api> {
         let caller = self.blockchain().get_caller();
         self.require_whitelisted(&caller);
 
         let payments = self.call_value().all_esdt_transfers();
-        require!(payments.len() == 2, "Invalid payments amount");
+        require!(
+            payments.len() == 1 || payments.len() == 2,
+            "Invalid payments amount"
+        );
 
         // first payment are the staking tokens, taken from the liquidity pool
         // they will be sent to the user on unbond
-        let first_payment = payments.get(0);
-        let staking_token_id = self.farming_token_id().get();
-        require!(
-            first_payment.token_identifier == staking_token_id,
-            "Invalid first payment"
-        );
+        // they are missing if the user keeps providing liquidity
+        let staking_tokens_amount = if payments.len() == 2 {
+            let first_payment = payments.get(0);
+            let staking_token_id = self.farming_token_id().get();
+            require!(
+                first_payment.token_identifier == staking_token_id,
+                "Invalid first payment"
+            );
 
-        let second_payment = payments.get(1);
+            first_payment.amount
+        } else {
+            BigUint::zero()
+        };
+
+        let farm_token_payment = payments.get(payments.len() - 1);
         let farm_token_id = self.farm_token_id().get();
         require!(
-            second_payment.token_identifier == farm_token_id,
+            farm_token_payment.token_identifier == farm_token_id,
             "Invalid second payment"
         );
 
+        // rewards compounded into the position are not part of the liquidity pool tokens
+        let unbond_compounded_rewards = opt_unbond_compounded_rewards
+            .into_option()
+            .unwrap_or_default();
+        let unbond_amount = if unbond_compounded_rewards {
+            let farm_attributes = self.get_attributes::<StakingFarmTokenAttributes<Self::Api>>(
+                &farm_token_payment.token_identifier,
+                farm_token_payment.token_nonce,
+            );
+            let compounded_amount = self.rule_of_three(
+                &farm_token_payment.amount,
+                &farm_attributes.current_farm_amount,
+                &farm_attributes.compounded_reward,
+            );
+
+            staking_tokens_amount + compounded_amount
+        } else {
+            staking_tokens_amount
+        };
+
         self.unstake_farm_common(
-            second_payment.token_identifier,
-            second_payment.token_nonce,
-            second_payment.amount,
-            Some(first_payment.amount),
+            farm_token_payment.token_identifier,
+            farm_token_payment.token_nonce,
+            farm_token_payment.amount,
+            Some(unbond_amount),
         )
     }
 
@@ -209,6 +287,38 @@ pub trait Farm:
         payment_amount: BigUint,
         opt_unbond_amount: Option<BigUint>,
     ) -> ExitFarmResultType<Self::Api> {
+        let (unbond_token_amount, reward) = self.exit_staking_position(
+            payment_token_id,
+            token_nonce,
+            payment_amount,
+            opt_unbond_amount,
+        );
+
+        let caller = self.blockchain().get_caller();
+        let farm_token_id = self.farm_token_id().get();
+        let farm_token_payment = if unbond_token_amount > 0u32 {
+            self.create_and_send_unbond_tokens(&caller, farm_token_id, unbond_token_amount)
+        } else {
+            EsdtTokenPayment::new(farm_token_id, 0, unbond_token_amount)
+        };
+
+        let reward_token_id = self.reward_token_id().get();
+        self.send_rewards(&reward_token_id, &reward, &caller);
+
+        MultiResult2::from((
+            farm_token_payment,
+            EsdtTokenPayment::new(reward_token_id, 0, reward),
+        ))
+    }
+
+    /// Burns the farm tokens and returns the amount to be unbonded and the rewards.
+    fn exit_staking_position(
+        &self,
+        payment_token_id: TokenIdentifier,
+        token_nonce: Nonce,
+        payment_amount: BigUint,
+        opt_unbond_amount: Option<BigUint>,
+    ) -> (BigUint, BigUint) {
         require!(self.is_active(), "Not active");
         require!(!self.farm_token_id().is_empty(), "No farm token");
 
@@ -220,7 +330,6 @@ pub trait Farm:
             &payment_token_id,
             token_nonce,
         );
-        let reward_token_id = self.reward_token_id().get();
         self.generate_aggregated_rewards();
 
         let reward = self.calculate_rewards_with_apr_limit(
@@ -230,22 +339,14 @@ pub trait Farm:
             farm_attributes.last_claim_block,
         );
 
-        let caller = self.blockchain().get_caller();
         self.burn_farm_tokens(&payment_token_id, token_nonce, &payment_amount);
 
         let unbond_token_amount = match opt_unbond_amount {
             Some(amt) => amt,
             None => payment_amount, // payment_amount = initial_farming + compounded_rewards
         };
-        let farm_token_payment =
-            self.create_and_send_unbond_tokens(&caller, farm_token_id, unbond_token_amount);
 
-        self.send_rewards(&reward_token_id, &reward, &caller);
-
-        MultiResult2::from((
-            farm_token_payment,
-            EsdtTokenPayment::new(reward_token_id, 0, reward),
-        ))
+        (unbond_token_amount, reward)
     }
 
     fn create_and_send_unbond_tokens(
@@ -256,25 +357,70 @@ pub trait Farm:
     ) -> EsdtTokenPayment<Self::Api> {
         let min_unbond_epochs = self.min_unbond_epochs().get();
         let current_epoch = self.blockchain().get_block_epoch();
-        let nft_nonce = self.nft_create_tokens(
+        let unlock_epoch = current_epoch + min_unbond_epochs;
+        let nft_nonce = self.create_position_tokens(
             &farm_token_id,
             &amount,
-            &UnbondSftAttributes {
-                unlock_epoch: current_epoch + min_unbond_epochs,
-            },
+            &UnbondSftAttributes { unlock_epoch },
+            None,
         );
+        self.add_pending_unbond(unlock_epoch, &amount);
         self.send()
             .direct(to, &farm_token_id, nft_nonce, &amount, &[]);
 
         EsdtTokenPayment::new(farm_token_id, nft_nonce, amount)
     }
 
+    /// Accepts one or more unbond tokens. The matured ones are redeemed for farming tokens,
+    /// while the others are sent back to the caller.
     #[payable("*")]
     #[endpoint(unbondFarm)]
     fn unbond_farm(&self) -> UnbondFarmResultType<Self::Api> {
         require!(self.is_active(), "Not active");
         require!(!self.farm_token_id().is_empty(), "No farm token");
 
+        let payments = self.call_value().all_esdt_transfers();
+        require!(!payments.is_empty(), "empty payments");
+
+        let farm_token_id = self.farm_token_id().get();
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut unbond_amount = BigUint::zero();
+        let mut returned_payments = ManagedVec::new();
+        for payment in payments.iter() {
+            require!(payment.token_identifier == farm_token_id, "Bad input token");
+            require!(payment.amount > 0, "Payment amount cannot be zero");
+
+            let unlock_epoch =
+                self.get_unbond_sft_unlock_epoch(&farm_token_id, payment.token_nonce);
+            if current_epoch >= unlock_epoch {
+                self.remove_pending_unbond(unlock_epoch, &payment.amount);
+                unbond_amount += &payment.amount;
+            } else {
+                returned_payments.push(payment);
+            }
+        }
+        require!(unbond_amount > 0, "Unbond period not over");
+
+        let caller = self.blockchain().get_caller();
+        let farming_token_id = self.farming_token_id().get();
+        self.send()
+            .direct(&caller, &farming_token_id, 0, &unbond_amount, &[]);
+        if !returned_payments.is_empty() {
+            self.send_multiple_tokens(&caller, &returned_payments, &OptionalArg::None);
+        }
+
+        EsdtTokenPayment::new(farming_token_id, 0, unbond_amount)
+    }
+
+    /// Redeems an unbond position before its unlock epoch.
+    /// The instant unbond fee is applied proportionally to the remaining unbond epochs.
+    #[payable("*")]
+    #[endpoint(unbondFarmEarly)]
+    fn unbond_farm_early(&self) -> UnbondFarmResultType<Self::Api> {
+        require!(self.is_active(), "Not active");
+        require!(!self.farm_token_id().is_empty(), "No farm token");
+        self.require_instant_unbond_enabled();
+
         let (amount, payment_token_id) = self.call_value().payment_token_pair();
         let token_nonce = self.call_value().esdt_token_nonce();
 
@@ -282,23 +428,42 @@ pub trait Farm:
         require!(payment_token_id == farm_token_id, "Bad input token");
         require!(amount > 0, "Payment amount cannot be zero");
 
-        let token_info = self.blockchain().get_esdt_token_data(
-            &self.blockchain().get_sc_address(),
-            &farm_token_id,
-            token_nonce,
-        );
-        let unlock_epoch = token_info
-            .decode_attributes_or_exit::<UnbondSftAttributes>()
-            .unlock_epoch;
-        let current_epoch = self.blockchain().get_block_epoch();
-        require!(current_epoch >= unlock_epoch, "Unbond period not over");
+        let unlock_epoch = self.get_unbond_sft_unlock_epoch(&farm_token_id, token_nonce);
+        self.remove_pending_unbond(unlock_epoch, &amount);
+        let fee_amount = self.get_early_redeem_fee(unlock_epoch, &amount);
+        self.send_unbond_fee(&fee_amount);
 
         let caller = self.blockchain().get_caller();
         let farming_token_id = self.farming_token_id().get();
+        let farming_token_amount = amount - fee_amount;
         self.send()
-            .direct(&caller, &farming_token_id, 0, &amount, &[]);
+            .direct(&caller, &farming_token_id, 0, &farming_token_amount, &[]);
+
+        EsdtTokenPayment::new(farming_token_id, 0, farming_token_amount)
+    }
+
+    #[view(getUnbondFarmEarlyFee)]
+    fn get_unbond_farm_early_fee(&self, token_nonce: Nonce, amount: BigUint) -> BigUint {
+        let farm_token_id = self.farm_token_id().get();
+        let unlock_epoch = self.get_unbond_sft_unlock_epoch(&farm_token_id, token_nonce);
 
-        EsdtTokenPayment::new(farming_token_id, 0, amount)
+        self.get_early_redeem_fee(unlock_epoch, &amount)
+    }
+
+    fn get_unbond_sft_unlock_epoch(
+        &self,
+        farm_token_id: &TokenIdentifier,
+        token_nonce: Nonce,
+    ) -> u64 {
+        let token_info = self.blockchain().get_esdt_token_data(
+            &self.blockchain().get_sc_address(),
+            farm_token_id,
+            token_nonce,
+        );
+
+        token_info
+            .decode_attributes_or_exit::<UnbondSftAttributes>()
+            .unlock_epoch
     }
 
     #[payable("*")]
@@ -309,6 +474,8 @@ pub trait Farm:
         self.claim_rewards_common(payments, None)
     }
 
+    /// The new values only account for the farming tokens backing the positions.
+    /// The rewards compounded into each position are kept on top of its new value.
     #[payable("*")]
     #[endpoint(claimRewardsWithNewValue)]
     fn claim_rewards_with_new_value(
@@ -367,7 +534,7 @@ pub trait Farm:
             &farm_attributes.compounded_reward,
         );
         let new_farming_amount = match &opt_new_farm_values {
-            Some(new_values) => (*new_values.get(0)).clone(),
+            Some(new_values) => &*new_values.get(0) + &new_compound_reward_amount,
             None => old_farming_amount.clone(),
         };
 
@@ -422,25 +589,53 @@ pub trait Farm:
             .slice(1, new_farm_values.len())
             .unwrap_or_default();
 
-        let mut additional_payments_attributes = ManagedVec::new();
+        // Each position is merged with its new value, plus its share of compounded rewards.
+        let mut updated_payments = ManagedVec::new();
+        let mut updated_attributes = ManagedVec::new();
         for (p, new_val) in additional_payments.iter().zip(new_additional_values.iter()) {
             let mut attr = self.get_attributes::<StakingFarmTokenAttributes<Self::Api>>(
                 &p.token_identifier,
                 p.token_nonce,
             );
-            attr.current_farm_amount = (*new_val).clone();
+            let compounded_reward = self.rule_of_three(
+                &p.amount,
+                &attr.current_farm_amount,
+                &attr.compounded_reward,
+            );
+            let new_amount = &*new_val + &compounded_reward;
+            attr.compounded_reward = compounded_reward;
+            attr.current_farm_amount = new_amount.clone();
 
-            additional_payments_attributes.push(attr);
+            updated_payments.push(EsdtTokenPayment::new(
+                p.token_identifier.clone(),
+                p.token_nonce,
+                new_amount,
+            ));
+            updated_attributes.push(attr);
         }
 
         let farm_token_id = self.farm_token_id().get();
-        self.create_farm_tokens_by_merging_with_updated_attributes(
-            new_farming_amount,
-            &farm_token_id,
-            new_attributes,
-            additional_payments,
-            &additional_payments_attributes,
-        )
+        let current_position_replic = StakingFarmToken {
+            token_amount: self.create_payment(&farm_token_id, 0, new_farming_amount),
+            attributes: new_attributes.clone(),
+        };
+        let merged_attributes = self.get_merged_farm_token_attributes(
+            &updated_payments,
+            Some(current_position_replic),
+            Some(&updated_attributes),
+        );
+        self.burn_farm_tokens_from_payments(additional_payments);
+
+        let new_amount = &merged_attributes.current_farm_amount;
+        let new_nonce = self.mint_farm_tokens(&farm_token_id, new_amount, &merged_attributes);
+
+        let new_farm_token = StakingFarmToken {
+            token_amount: self.create_payment(&farm_token_id, new_nonce, new_amount),
+            attributes: merged_attributes,
+        };
+        let is_merged = !additional_payments.is_empty();
+
+        (new_farm_token, is_merged)
     }
 
     #[payable("*")]
@@ -551,39 +746,6 @@ pub trait Farm:
         (new_farm_token, is_merged)
     }
 
-    fn create_farm_tokens_by_merging_with_updated_attributes(
-        &self,
-        amount: &BigUint,
-        token_id: &TokenIdentifier,
-        attributes: &StakingFarmTokenAttributes<Self::Api>,
-        additional_payments: &ManagedVec<EsdtTokenPayment<Self::Api>>,
-        new_additional_attributes: &ManagedVec<StakingFarmTokenAttributes<Self::Api>>,
-    ) -> (StakingFarmToken<Self::Api>, bool) {
-        let current_position_replic = StakingFarmToken {
-            token_amount: self.create_payment(token_id, 0, amount),
-            attributes: attributes.clone(),
-        };
-
-        let additional_payments_len = additional_payments.len();
-        let merged_attributes = self.get_merged_farm_token_attributes(
-            additional_payments,
-            Some(current_position_replic),
-            Some(new_additional_attributes),
-        );
-        self.burn_farm_tokens_from_payments(additional_payments);
-
-        let new_amount = &merged_attributes.current_farm_amount;
-        let new_nonce = self.mint_farm_tokens(token_id, new_amount, &merged_attributes);
-
-        let new_farm_token = StakingFarmToken {
-            token_amount: self.create_payment(token_id, new_nonce, new_amount),
-            attributes: merged_attributes,
-        };
-        let is_merged = additional_payments_len != 0;
-
-        (new_farm_token, is_merged)
-    }
-
     fn send_rewards(
         &self,
         reward_token_id: &TokenIdentifier,
@@ -0,0 +1,96 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+#[derive(ManagedVecItem, TopEncode, TopDecode, PartialEq, TypeAbi, Debug)]
+pub struct UnbondScheduleEntry<M: ManagedTypeApi> {
+    pub unlock_epoch: u64,
+    pub amount: BigUint<M>,
+}
+
+#[elrond_wasm::module]
+pub trait UnbondQueueModule {
+    fn add_pending_unbond(&self, unlock_epoch: u64, amount: &BigUint) {
+        let mut pending_unbond = self.pending_unbond_amounts();
+        let new_amount = match pending_unbond.get(&unlock_epoch) {
+            Some(pending_amount) => pending_amount + amount,
+            None => amount.clone(),
+        };
+        pending_unbond.insert(unlock_epoch, new_amount);
+
+        self.total_unbonding_supply()
+            .update(|total| *total += amount);
+    }
+
+    /// Unbond tokens created before the tracking was introduced are not accounted,
+    /// so the amounts are only decreased as much as they were tracked.
+    fn remove_pending_unbond(&self, unlock_epoch: u64, amount: &BigUint) {
+        let mut pending_unbond = self.pending_unbond_amounts();
+        let pending_amount = match pending_unbond.get(&unlock_epoch) {
+            Some(pending_amount) => pending_amount,
+            None => return,
+        };
+
+        let removed_amount = if &pending_amount > amount {
+            pending_unbond.insert(unlock_epoch, &pending_amount - amount);
+            amount.clone()
+        } else {
+            pending_unbond.remove(&unlock_epoch);
+            pending_amount
+        };
+
+        self.total_unbonding_supply()
+            .update(|total| *total -= &removed_amount);
+    }
+
+    /// Returns the pending unbond amounts, grouped by their unlock epoch, in ascending epoch order.
+    #[view(getUnbondSchedule)]
+    fn get_unbond_schedule(&self) -> ManagedMultiResultVec<UnbondScheduleEntry<Self::Api>> {
+        let mut schedule: ManagedVec<UnbondScheduleEntry<Self::Api>> = ManagedVec::new();
+        for (unlock_epoch, amount) in self.pending_unbond_amounts().iter() {
+            let mut insert_index = schedule.len();
+            for (index, entry) in schedule.iter().enumerate() {
+                if entry.unlock_epoch > unlock_epoch {
+                    insert_index = index;
+                    break;
+                }
+            }
+
+            let new_entry = UnbondScheduleEntry {
+                unlock_epoch,
+                amount,
+            };
+            schedule = self.insert_schedule_entry(&schedule, insert_index, new_entry);
+        }
+
+        schedule.into()
+    }
+
+    fn insert_schedule_entry(
+        &self,
+        schedule: &ManagedVec<UnbondScheduleEntry<Self::Api>>,
+        index: usize,
+        new_entry: UnbondScheduleEntry<Self::Api>,
+    ) -> ManagedVec<UnbondScheduleEntry<Self::Api>> {
+        let mut result = schedule.slice(0, index).unwrap_or_default();
+        result.push(new_entry);
+        if let Some(remaining) = schedule.slice(index, schedule.len()) {
+            result.append_vec(remaining);
+        }
+
+        result
+    }
+
+    #[view(getPendingUnbondAmount)]
+    fn get_pending_unbond_amount(&self, unlock_epoch: u64) -> BigUint {
+        self.pending_unbond_amounts()
+            .get(&unlock_epoch)
+            .unwrap_or_else(BigUint::zero)
+    }
+
+    #[storage_mapper("pendingUnbondAmounts")]
+    fn pending_unbond_amounts(&self) -> MapMapper<u64, BigUint>;
+
+    #[view(getTotalUnbondingSupply)]
+    #[storage_mapper("totalUnbondingSupply")]
+    fn total_unbonding_supply(&self) -> SingleValueMapper<BigUint>;
+}
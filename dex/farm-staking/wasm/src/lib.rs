@@ -16,44 +16,69 @@ elrond_wasm_node::wasm_endpoints! {
         end_produce_rewards
         getAccumulatedRewards
         getAnnualPercentageRewards
+        getAprCapMode
+        getBlockTimeSeconds
         getBurnGasLimit
         getDivisionSafetyConstant
+        getEffectiveAprInfo
         getFarmTokenId
         getFarmTokenSupply
         getFarmingTokenId
+        getInstantUnbondFeePercent
         getLastErrorMessage
         getLastRewardBlockNonce
         getLockedAssetFactoryManagedAddress
+        getMinAnnualPercentageRewards
         getMinUnbondEpochs
         getMinimumFarmingEpoch
         getPairContractManagedAddress
         getPenaltyPercent
+        getPendingUnbondAmount
         getPerBlockRewardAmount
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
         getRewardCapacity
         getRewardPerShare
         getRewardTokenId
         getState
+        getTotalUnbondingSupply
         getTransferExecGasLimit
+        getUnbondFarmEarlyFee
+        getUnbondFeeTreasury
+        getUnbondSchedule
+        getUtilizationTargetSupply
+        isInstantUnbondEnabled
         isWhitelisted
         mergeFarmTokens
         pause
         registerFarmToken
         removeAddressFromWhitelist
         resume
+        setAprCapMode
+        setBlockTime
+        setInstantUnbondEnabled
+        setInstantUnbondFee
         setLocalRolesFarmToken
         setMaxApr
         setMinUnbondEpochs
         setPerBlockRewardAmount
+        setPositionTokenMetadata
+        setUnbondFeeTreasury
+        setUtilizationCurve
         set_burn_gas_limit
         set_minimum_farming_epochs
         set_penalty_percent
         set_transfer_exec_gas_limit
+        splitFarmToken
         stakeFarm
         stakeFarmThroughProxy
         startProduceRewards
         topUpRewards
         unbondFarm
+        unbondFarmEarly
         unstakeFarm
+        unstakeFarmInstant
         unstakeFarmThroughProxy
     )
 }
@@ -3,6 +3,7 @@ elrond_wasm::derive_imports!();
 
 use common_errors::*;
 
+use config::{DEFAULT_BLOCK_TIME_SECONDS, SECONDS_IN_DAY, SECONDS_IN_YEAR};
 use contexts::generic::StorageCache;
 
 #[elrond_wasm::module]
@@ -11,15 +12,20 @@ pub trait CustomRewardsModule:
     + token_send::TokenSendModule
     + farm_token::FarmTokenModule
     + rewards::RewardsModule
+    + funded_rewards::FundedRewardsModule
 {
     fn mint_per_block_rewards(&self, token_id: &TokenIdentifier) -> BigUint {
         let current_block_nonce = self.blockchain().get_block_nonce();
         let last_reward_nonce = self.last_reward_block_nonce().get();
 
         if current_block_nonce > last_reward_nonce {
-            let to_mint = self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
+            let mut to_mint =
+                self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
 
-            if to_mint != 0 {
+            if self.funded_rewards_enabled().get() {
+                // Rewards are taken from the funded reserve, nothing is minted.
+                to_mint = self.allocate_funded_rewards(to_mint);
+            } else if to_mint != 0 {
                 self.send().esdt_local_mint(token_id, 0, &to_mint);
             }
             self.last_reward_block_nonce().set(&current_block_nonce);
@@ -43,10 +49,7 @@ pub trait CustomRewardsModule:
         }
     }
 
-    #[only_owner]
-    #[endpoint]
-    fn end_produce_rewards(&self) {
-        // TODO: duplicated code
+    fn generate_and_store_aggregated_rewards(&self) {
         let mut storage = StorageCache {
             reward_token_id: Some(self.reward_token_id().get()),
             division_safety_constant: Some(self.division_safety_constant().get()),
@@ -61,6 +64,12 @@ pub trait CustomRewardsModule:
             .set(storage.reward_per_share.as_ref().unwrap());
         self.reward_reserve()
             .set(storage.reward_reserve.as_ref().unwrap());
+    }
+
+    #[only_owner]
+    #[endpoint]
+    fn end_produce_rewards(&self) {
+        self.generate_and_store_aggregated_rewards();
 
         self.produce_rewards_enabled().set(&false);
     }
@@ -70,22 +79,40 @@ pub trait CustomRewardsModule:
     fn set_per_block_rewards(&self, per_block_amount: BigUint) {
         require!(per_block_amount != 0u64, ERROR_ZERO_AMOUNT);
 
-        // TODO: duplicated code
-        let mut storage = StorageCache {
-            reward_token_id: Some(self.reward_token_id().get()),
-            division_safety_constant: Some(self.division_safety_constant().get()),
-            farm_token_supply: Some(self.farm_token_supply().get()),
-            reward_reserve: Some(self.reward_reserve().get()),
-            reward_per_share: Some(self.reward_per_share().get()),
-            ..Default::default()
-        };
-
-        self.generate_aggregated_rewards(&mut storage);
-        self.reward_per_share()
-            .set(storage.reward_per_share.as_ref().unwrap());
-        self.reward_reserve()
-            .set(storage.reward_reserve.as_ref().unwrap());
+        self.generate_and_store_aggregated_rewards();
 
         self.per_block_reward_amount().set(&per_block_amount);
     }
+
+    /// Only used to turn block nonces into epochs and per block rates into APRs.
+    #[only_owner]
+    #[endpoint(setBlockTime)]
+    fn set_block_time(&self, block_time_seconds: u64) {
+        require!(
+            block_time_seconds != 0 && block_time_seconds <= SECONDS_IN_DAY,
+            ERROR_BAD_BLOCK_TIME
+        );
+        self.block_time_seconds().set(&block_time_seconds);
+    }
+
+    fn get_block_time_seconds(&self) -> u64 {
+        let block_time_seconds = self.block_time_seconds().get();
+        if block_time_seconds == 0 {
+            return DEFAULT_BLOCK_TIME_SECONDS;
+        }
+
+        block_time_seconds
+    }
+
+    fn get_blocks_in_epoch(&self) -> u64 {
+        SECONDS_IN_DAY / self.get_block_time_seconds()
+    }
+
+    fn get_blocks_in_year(&self) -> u64 {
+        SECONDS_IN_YEAR / self.get_block_time_seconds()
+    }
+
+    #[view(getBlockTimeSeconds)]
+    #[storage_mapper("block_time_seconds")]
+    fn block_time_seconds(&self) -> SingleValueMapper<u64>;
 }
@@ -13,6 +13,7 @@ pub trait FarmTokenMergeModule:
     + farm_token::FarmTokenModule
     + config::ConfigModule
     + token_merge::TokenMergeModule
+    + position_index::PositionIndexModule
 {
     #[payable("*")]
     #[endpoint(mergeFarmTokens)]
@@ -38,7 +39,119 @@ pub trait FarmTokenMergeModule:
             &opt_accept_funds_func,
         );
 
-        self.create_payment(&farm_token_id, new_nonce, &new_amount)
+        let new_farm_token = self.create_payment(&farm_token_id, new_nonce, &new_amount);
+        self.unindex_positions(&caller, &payments);
+        self.index_position(&caller, &new_farm_token, &attrs.reward_per_share);
+
+        new_farm_token
+    }
+
+    #[payable("*")]
+    #[endpoint(splitFarmToken)]
+    fn split_farm_token(
+        &self,
+        split_amounts: ManagedVec<BigUint>,
+        #[var_args] opt_accept_funds_func: OptionalArg<ManagedBuffer>,
+    ) -> ManagedMultiResultVec<EsdtTokenPayment<Self::Api>> {
+        let caller = self.blockchain().get_caller();
+        let (payment_amount, payment_token_id) = self.call_value().payment_token_pair();
+        let payment_token_nonce = self.call_value().esdt_token_nonce();
+
+        let farm_token_id = self.farm_token_id().get();
+        require!(payment_token_id == farm_token_id, ERROR_NOT_A_FARM_TOKEN);
+        require!(payment_token_nonce != 0, ERROR_NOT_A_FARM_TOKEN);
+        require!(payment_amount != 0u64, ERROR_ZERO_AMOUNT);
+        require!(split_amounts.len() > 1, ERROR_PARAMETERS);
+
+        let attributes = self.get_farm_attributes(&payment_token_id, payment_token_nonce);
+        let split_attributes =
+            self.get_split_farm_token_attributes(&payment_amount, &attributes, &split_amounts);
+
+        self.burn_farm_tokens(&farm_token_id, payment_token_nonce, &payment_amount);
+
+        let payment = self.create_payment(&farm_token_id, payment_token_nonce, &payment_amount);
+        self.unindex_positions(&caller, &ManagedVec::from_single_item(payment));
+
+        let mut new_farm_tokens = ManagedVec::new();
+        for attrs in split_attributes.iter() {
+            let new_amount = attrs.current_farm_amount.clone();
+            let new_nonce = self.mint_farm_tokens(&farm_token_id, &new_amount, &attrs);
+            let new_farm_token = self.create_payment(&farm_token_id, new_nonce, &new_amount);
+            self.index_position(&caller, &new_farm_token, &attrs.reward_per_share);
+            new_farm_tokens.push(new_farm_token);
+        }
+
+        self.send_multiple_tokens(&caller, &new_farm_tokens, &opt_accept_funds_func);
+
+        new_farm_tokens.into()
+    }
+
+    fn get_split_farm_token_attributes(
+        &self,
+        payment_amount: &BigUint,
+        attributes: &FarmTokenAttributes<Self::Api>,
+        split_amounts: &ManagedVec<BigUint>,
+    ) -> ManagedVec<FarmTokenAttributes<Self::Api>> {
+        let mut split_amounts_sum = BigUint::zero();
+        for amount in split_amounts.iter() {
+            require!(*amount != 0u64, ERROR_ZERO_AMOUNT);
+            split_amounts_sum += &*amount;
+        }
+        require!(&split_amounts_sum == payment_amount, ERROR_PARAMETERS);
+
+        let total_initial_farming_amount = self.rule_of_three_non_zero_result(
+            payment_amount,
+            &attributes.current_farm_amount,
+            &attributes.initial_farming_amount,
+        );
+        let total_compounded_reward = self.rule_of_three(
+            payment_amount,
+            &attributes.current_farm_amount,
+            &attributes.compounded_reward,
+        );
+
+        // The last position receives the rounding leftovers, so that the sum of
+        // the new positions is always equal to the position that was split.
+        let mut remaining_initial_farming_amount = total_initial_farming_amount;
+        let mut remaining_compounded_reward = total_compounded_reward;
+        let last_index = split_amounts.len() - 1;
+
+        let mut result = ManagedVec::new();
+        for (i, amount) in split_amounts.iter().enumerate() {
+            let (initial_farming_amount, compounded_reward) = if i == last_index {
+                require!(remaining_initial_farming_amount != 0u64, ERROR_ZERO_AMOUNT);
+                (
+                    remaining_initial_farming_amount.clone(),
+                    remaining_compounded_reward.clone(),
+                )
+            } else {
+                (
+                    self.rule_of_three_non_zero_result(
+                        &amount,
+                        &attributes.current_farm_amount,
+                        &attributes.initial_farming_amount,
+                    ),
+                    self.rule_of_three(
+                        &amount,
+                        &attributes.current_farm_amount,
+                        &attributes.compounded_reward,
+                    ),
+                )
+            };
+            remaining_initial_farming_amount -= &initial_farming_amount;
+            remaining_compounded_reward -= &compounded_reward;
+
+            result.push(FarmTokenAttributes {
+                reward_per_share: attributes.reward_per_share.clone(),
+                original_entering_epoch: attributes.original_entering_epoch,
+                entering_epoch: attributes.entering_epoch,
+                initial_farming_amount,
+                compounded_reward,
+                current_farm_amount: (*amount).clone(),
+            });
+        }
+
+        result
     }
 
     fn get_merged_farm_token_attributes(
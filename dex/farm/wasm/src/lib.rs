@@ -8,44 +8,87 @@ elrond_wasm_node::wasm_endpoints! {
     farm
     (
         callBack
+        acceptMigratedPosition
+        addMigrationSource
+        authorizeClaimer
         calculateRewardsForGivenPosition
         claimRewards
+        claimRewardsOnBehalf
+        clearRewardRecipient
         compoundRewards
+        depositInCustody
         end_produce_rewards
         enterFarm
         exitFarm
+        getAuthorizedClaimers
+        getBlockTimeSeconds
         getBurnGasLimit
+        getCustodyPositions
         getDivisionSafetyConstant
         getFarmMigrationConfiguration
         getFarmTokenId
         getFarmTokenSupply
         getFarmingTokenId
+        getFundedRewardCapacity
+        getFundedRewardsDistributed
         getLastErrorMessage
         getLastRewardBlockNonce
         getLockedAssetFactoryManagedAddress
+        getLowRewardReserveThreshold
+        getMigratedFarmTokenSupply
+        getMigratedFarmingAmount
+        getMigrationProgress
+        getMigrationSourceVersion
+        getMigrationTarget
         getMinimumFarmingEpoch
         getPairContractManagedAddress
         getPenaltyPercent
         getPerBlockRewardAmount
+        getPositionRewardRecipient
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
+        getRemainingFundedRewards
+        getRemainingRewardBlocks
         getRewardPerShare
+        getRewardRecipient
         getRewardReserve
         getRewardTokenId
         getState
         getTransferExecGasLimit
+        getUserPositions
+        isFundedRewardsModeEnabled
+        isPositionIndexEnabled
         mergeFarmTokens
+        migrateCustodyPositions
         migrateFromV1_2Farm
+        migratePosition
         pause
         registerFarmToken
+        removeMigrationSource
         resume
+        revokeClaimer
+        setBlockTime
         setFarmMigrationConfig
         setFarmTokenSupply
+        setFundedRewardsMode
         setLocalRolesFarmToken
+        setLowRewardReserveThreshold
+        setMigrationTarget
         setPerBlockRewardAmount
+        setPositionIndexEnabled
+        setPositionTokenMetadata
+        setRewardRecipient
         setRpsAndStartRewards
         set_burn_gas_limit
         set_minimum_farming_epochs
         set_penalty_percent
         set_transfer_exec_gas_limit
+        simulateRewards
+        splitFarmToken
         startProduceRewards
+        topUpRewards
+        withdrawFromCustody
+        withdrawFundedRewards
     )
 }
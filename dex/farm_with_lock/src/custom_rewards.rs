@@ -11,15 +11,22 @@ pub trait CustomRewardsModule:
     + token_send::TokenSendModule
     + farm_token::FarmTokenModule
     + rewards::RewardsModule
+    + funded_rewards::FundedRewardsModule
 {
     fn mint_per_block_rewards(&self) -> BigUint {
         let current_block_nonce = self.blockchain().get_block_nonce();
         let last_reward_nonce = self.last_reward_block_nonce().get();
 
         if current_block_nonce > last_reward_nonce {
-            let to_mint = self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
+            let mut to_mint =
+                self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
 
             // Skip the actual minting. Since this SC will deliver locked rewards.
+            // In funded mode, the allocated rewards are burned from the reserve instead,
+            // to make up for the assets the factory creates when they are unlocked.
+            if self.funded_rewards_enabled().get() {
+                to_mint = self.allocate_and_burn_funded_rewards(to_mint);
+            }
 
             self.last_reward_block_nonce().set(&current_block_nonce);
             to_mint
@@ -42,10 +49,7 @@ pub trait CustomRewardsModule:
         }
     }
 
-    #[only_owner]
-    #[endpoint]
-    fn end_produce_rewards(&self) {
-        // TODO: duplicated code
+    fn generate_and_store_aggregated_rewards(&self) {
         let mut storage = StorageCache {
             reward_token_id: Some(self.reward_token_id().get()),
             division_safety_constant: Some(self.division_safety_constant().get()),
@@ -60,6 +64,12 @@ pub trait CustomRewardsModule:
             .set(storage.reward_per_share.as_ref().unwrap());
         self.reward_reserve()
             .set(storage.reward_reserve.as_ref().unwrap());
+    }
+
+    #[only_owner]
+    #[endpoint]
+    fn end_produce_rewards(&self) {
+        self.generate_and_store_aggregated_rewards();
 
         self.produce_rewards_enabled().set(&false);
     }
@@ -69,22 +79,20 @@ pub trait CustomRewardsModule:
     fn set_per_block_rewards(&self, per_block_amount: BigUint) {
         require!(per_block_amount != 0u64, ERROR_ZERO_AMOUNT);
 
-        // TODO: duplicated code
-        let mut storage = StorageCache {
-            reward_token_id: Some(self.reward_token_id().get()),
-            division_safety_constant: Some(self.division_safety_constant().get()),
-            farm_token_supply: Some(self.farm_token_supply().get()),
-            reward_reserve: Some(self.reward_reserve().get()),
-            reward_per_share: Some(self.reward_per_share().get()),
-            ..Default::default()
-        };
-
-        self.generate_aggregated_rewards(&mut storage);
-        self.reward_per_share()
-            .set(storage.reward_per_share.as_ref().unwrap());
-        self.reward_reserve()
-            .set(storage.reward_reserve.as_ref().unwrap());
+        self.generate_and_store_aggregated_rewards();
 
         self.per_block_reward_amount().set(&per_block_amount);
     }
+
+    /// Rewards are delivered as locked assets created by the factory,
+    /// so the funded reserve is burned as it is allocated.
+    fn allocate_and_burn_funded_rewards(&self, amount: BigUint) -> BigUint {
+        let allocated = self.allocate_funded_rewards(amount);
+        if allocated != 0u64 {
+            self.send()
+                .esdt_local_burn(&self.reward_token_id().get(), 0, &allocated);
+        }
+
+        allocated
+    }
 }
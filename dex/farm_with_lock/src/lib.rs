@@ -4,6 +4,7 @@
 
 pub mod custom_rewards;
 pub mod farm_token_merge;
+pub mod lock_options;
 
 use common_errors::*;
 
@@ -11,6 +12,7 @@ use common_structs::FarmTokenAttributes;
 use config::State;
 use contexts::generic::{GenericContext, StorageCache};
 use farm_token::FarmToken;
+use position_index::UserPosition;
 
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
@@ -26,19 +28,27 @@ type ClaimRewardsResultType<BigUint> =
     MultiResult2<EsdtTokenPayment<BigUint>, EsdtTokenPayment<BigUint>>;
 type ExitFarmResultType<BigUint> =
     MultiResult2<EsdtTokenPayment<BigUint>, EsdtTokenPayment<BigUint>>;
+type MigratePositionResultType<BigUint> =
+    MultiResult2<EsdtTokenPayment<BigUint>, EsdtTokenPayment<BigUint>>;
 
 #[elrond_wasm::contract]
 pub trait Farm:
     custom_rewards::CustomRewardsModule
     + rewards::RewardsModule
+    + funded_rewards::FundedRewardsModule
     + config::ConfigModule
     + token_send::TokenSendModule
     + token_merge::TokenMergeModule
     + farm_token::FarmTokenModule
+    + token_metadata::TokenMetadataModule
     + farm_token_merge::FarmTokenMergeModule
     + events::EventsModule
     + contexts::ctx_helper::CtxHelper
     + migration_from_v1_2::MigrationModule
+    + claim_delegation::ClaimDelegationModule
+    + farm_migration::FarmMigrationModule
+    + position_index::PositionIndexModule
+    + lock_options::LockOptionsModule
 {
     #[proxy]
     fn locked_asset_factory(&self, to: ManagedAddress) -> factory::Proxy<Self::Api>;
@@ -86,6 +96,7 @@ pub trait Farm:
         &self,
         #[var_args] opt_accept_funds_func: OptionalArg<ManagedBuffer>,
     ) -> EnterFarmResultType<Self::Api> {
+        self.require_not_migrating();
         let mut context = self.new_farm_context(opt_accept_funds_func);
 
         self.load_state(&mut context);
@@ -150,6 +161,7 @@ pub trait Farm:
 
         self.commit_changes(&context);
         self.execute_output_payments(&context);
+        self.update_position_index(&context);
         self.emit_enter_farm_event(&context);
 
         context.get_output_payments().get(0)
@@ -197,9 +209,11 @@ pub trait Farm:
         self.burn_position(&context);
         self.commit_changes(&context);
 
-        self.send_rewards(&mut context);
+        let caller = context.get_caller().clone();
+        self.send_rewards(&mut context, &caller);
         self.construct_output_payments_exit(&mut context);
         self.execute_output_payments(&context);
+        self.update_position_index(&context);
         self.emit_exit_farm_event(&context);
 
         self.construct_and_get_result(&context)
@@ -212,37 +226,84 @@ pub trait Farm:
         #[var_args] opt_accept_funds_func: OptionalArg<ManagedBuffer>,
     ) -> ClaimRewardsResultType<Self::Api> {
         let mut context = self.new_farm_context(opt_accept_funds_func);
+        self.claim_rewards_common(&mut context);
 
-        self.load_state(&mut context);
+        let reward_destination = self.get_reward_destination(context.get_caller());
+        self.send_rewards(&mut context, &reward_destination);
+        self.execute_output_payments(&context);
+        self.update_position_index(&context);
+        self.emit_claim_rewards_event(&context);
+
+        self.construct_and_get_result(&context)
+    }
+
+    #[endpoint(claimRewardsOnBehalf)]
+    fn claim_rewards_on_behalf(
+        &self,
+        owner: ManagedAddress,
+    ) -> ManagedMultiResultVec<EsdtTokenPayment<Self::Api>> {
+        let caller = self.blockchain().get_caller();
+        self.require_can_claim_on_behalf(&owner, &caller);
+
+        let positions = self.get_custody_positions_vec(&owner);
+        require!(!positions.is_empty(), "No positions in custody");
+
+        let mut rewards = ManagedVec::new();
+        for position in positions.iter() {
+            let mut context = self.new_farm_context_from_payments(
+                owner.clone(),
+                ManagedVec::from_single_item(position.clone()),
+                OptionalArg::None,
+            );
+            self.claim_rewards_common(&mut context);
+
+            let reward_destination =
+                self.get_custody_reward_destination(&owner, position.token_nonce);
+            self.send_rewards(&mut context, &reward_destination);
+
+            // The new position is kept in custody instead of being sent to the owner.
+            let new_position = context.get_output_payments().get(0);
+            self.replace_custody_position(&owner, position.token_nonce, &new_position);
+            self.update_position_index(&context);
+            self.emit_claim_rewards_event(&context);
+
+            rewards.push(context.get_final_reward().unwrap().clone());
+        }
+
+        rewards.into()
+    }
+
+    fn claim_rewards_common(&self, context: &mut GenericContext<Self::Api>) {
+        self.load_state(context);
         require!(
             context.get_contract_state().unwrap() == &State::Active,
             ERROR_NOT_ACTIVE
         );
 
-        self.load_farm_token_id(&mut context);
+        self.load_farm_token_id(context);
         require!(
             !context.get_farm_token_id().unwrap().is_empty(),
             ERROR_NO_FARM_TOKEN
         );
 
-        self.load_farming_token_id(&mut context);
+        self.load_farming_token_id(context);
         require!(context.is_accepted_payment_claim(), ERROR_BAD_PAYMENTS);
 
-        self.load_reward_reserve(&mut context);
-        self.load_reward_token_id(&mut context);
-        self.load_block_nonce(&mut context);
-        self.load_block_epoch(&mut context);
-        self.load_reward_per_share(&mut context);
-        self.load_farm_token_supply(&mut context);
-        self.load_division_safety_constant(&mut context);
-        self.load_farm_attributes(&mut context);
+        self.load_reward_reserve(context);
+        self.load_reward_token_id(context);
+        self.load_block_nonce(context);
+        self.load_block_epoch(context);
+        self.load_reward_per_share(context);
+        self.load_farm_token_supply(context);
+        self.load_division_safety_constant(context);
+        self.load_farm_attributes(context);
 
         self.generate_aggregated_rewards(context.get_storage_cache_mut());
-        self.calculate_reward(&mut context);
+        self.calculate_reward(context);
         context.decrease_reward_reserve();
 
-        self.calculate_initial_farming_amount(&mut context);
-        let new_compound_reward_amount = self.calculate_new_compound_reward_amount(&context);
+        self.calculate_initial_farming_amount(context);
+        let new_compound_reward_amount = self.calculate_new_compound_reward_amount(context);
 
         let virtual_position_token_amount = EsdtTokenPayment::new(
             context.get_farm_token_id().unwrap().clone(),
@@ -286,14 +347,8 @@ pub trait Farm:
         );
         context.set_output_position(new_farm_token, created_with_merge);
 
-        self.burn_position(&context);
-        self.commit_changes(&context);
-
-        self.send_rewards(&mut context);
-        self.execute_output_payments(&context);
-        self.emit_claim_rewards_event(&context);
-
-        self.construct_and_get_result(&context)
+        self.burn_position(context);
+        self.commit_changes(context);
     }
 
     #[payable("*")]
@@ -388,6 +443,7 @@ pub trait Farm:
         self.commit_changes(&context);
 
         self.execute_output_payments(&context);
+        self.update_position_index(&context);
 
         context.set_final_reward_for_emit_compound_event();
         self.emit_compound_rewards_event(&context);
@@ -421,6 +477,130 @@ pub trait Farm:
         self.aggregated_original_entering_epoch(&items)
     }
 
+    /// Moves a position to the farm set with `setMigrationTarget`.
+    /// The rewards are paid as on exit, without any penalty, and the farming tokens
+    /// are forwarded to the target, which mints the new position for `orig_caller`
+    /// (the caller by default) keeping the entering epochs.
+    #[payable("*")]
+    #[endpoint(migratePosition)]
+    fn migrate_position(
+        &self,
+        #[var_args] opt_orig_caller: OptionalArg<ManagedAddress>,
+    ) -> MigratePositionResultType<Self::Api> {
+        let mut context = self.new_farm_context(OptionalArg::None);
+        let position_owner = match opt_orig_caller {
+            OptionalArg::Some(orig_caller) => orig_caller,
+            OptionalArg::None => context.get_caller().clone(),
+        };
+
+        let new_position = self.migrate_position_common(&mut context, position_owner);
+        self.update_position_index(&context);
+
+        MultiResult2::from((new_position, context.get_final_reward().unwrap().clone()))
+    }
+
+    /// Moves the positions held in custody for the given owners, in batches,
+    /// as they can't be moved by their owners without withdrawing them first.
+    /// The new positions are sent to their owners.
+    #[only_owner]
+    #[endpoint(migrateCustodyPositions)]
+    fn migrate_custody_positions(&self, #[var_args] owners: ManagedVarArgs<ManagedAddress>) {
+        for owner in owners.to_vec().iter() {
+            let positions = self.get_custody_positions_vec(&owner);
+            for position in positions.iter() {
+                self.remove_custody_position(&owner, position.token_nonce);
+
+                let mut context = self.new_farm_context_from_payments(
+                    owner.clone(),
+                    ManagedVec::from_single_item(position),
+                    OptionalArg::None,
+                );
+                self.migrate_position_common(&mut context, owner.clone());
+                self.update_position_index(&context);
+            }
+        }
+    }
+
+    fn migrate_position_common(
+        &self,
+        context: &mut GenericContext<Self::Api>,
+        position_owner: ManagedAddress,
+    ) -> EsdtTokenPayment<Self::Api> {
+        self.load_state(context);
+        require!(
+            context.get_contract_state().unwrap() == &State::Active,
+            ERROR_NOT_ACTIVE
+        );
+        require!(
+            !self.migration_target().is_empty(),
+            ERROR_NO_MIGRATION_TARGET
+        );
+
+        self.load_farm_token_id(context);
+        require!(
+            !context.get_farm_token_id().unwrap().is_empty(),
+            ERROR_NO_FARM_TOKEN
+        );
+
+        self.load_farming_token_id(context);
+        require!(context.is_accepted_payment_exit(), ERROR_BAD_PAYMENTS);
+
+        self.load_reward_token_id(context);
+        self.load_reward_reserve(context);
+        self.load_block_nonce(context);
+        self.load_block_epoch(context);
+        self.load_reward_per_share(context);
+        self.load_farm_token_supply(context);
+        self.load_division_safety_constant(context);
+        self.load_farm_attributes(context);
+        let source_attributes =
+            self.get_raw_position_attributes(context.get_tx_input().get_payments().get_first());
+
+        self.generate_aggregated_rewards(context.get_storage_cache_mut());
+        self.calculate_reward(context);
+        context.decrease_reward_reserve();
+        self.calculate_initial_farming_amount(context);
+        self.increase_reward_with_compounded_rewards(context);
+
+        self.burn_position(context);
+        self.commit_changes(context);
+        let caller = context.get_caller().clone();
+        self.send_rewards(context, &caller);
+
+        let farming_tokens = self.create_payment(
+            context.get_farming_token_id().unwrap(),
+            0,
+            context.get_initial_farming_amount().unwrap(),
+        );
+        let farm_amount = context
+            .get_tx_input()
+            .get_payments()
+            .get_first()
+            .amount
+            .clone();
+        self.forward_position_to_migration_target(
+            farming_tokens,
+            source_attributes,
+            &farm_amount,
+            position_owner,
+        )
+    }
+
+    /// Called by a farm registered with `addMigrationSource`, with the farming tokens
+    /// of a position moved from it. A new position is minted for `orig_caller`.
+    #[payable("*")]
+    #[endpoint(acceptMigratedPosition)]
+    fn accept_migrated_position(
+        &self,
+        source_attributes: ManagedBuffer,
+        orig_caller: ManagedAddress,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let mut context = self.new_migrated_position_context(orig_caller);
+        self.generate_aggregated_rewards(context.get_storage_cache_mut());
+
+        self.create_migrated_position(&mut context, &source_attributes)
+    }
+
     fn burn_farming_tokens(
         &self,
         farming_token_id: &TokenIdentifier,
@@ -493,18 +673,47 @@ pub trait Farm:
         );
     }
 
-    fn send_rewards(&self, context: &mut GenericContext<Self::Api>) {
+    fn send_rewards(&self, context: &mut GenericContext<Self::Api>, destination: &ManagedAddress) {
         if context.get_position_reward().unwrap() > &0u64 {
+            let opt_accept_funds_func = if destination == context.get_caller() {
+                context.get_opt_accept_funds_func().clone()
+            } else {
+                OptionalArg::None
+            };
+
             let locked_asset_factory_address = self.locked_asset_factory_address().get();
-            let result = self
-                .locked_asset_factory(locked_asset_factory_address)
-                .create_and_forward(
-                    context.get_position_reward().unwrap().clone(),
-                    context.get_caller().clone(),
-                    context.get_input_attributes().unwrap().entering_epoch,
-                    context.get_opt_accept_funds_func().clone(),
-                )
-                .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
+            let entering_epoch = context.get_input_attributes().unwrap().entering_epoch;
+            let lock_option_id = self.get_user_lock_option_id(context.get_caller().clone());
+            let result = if lock_option_id == lock_options::DEFAULT_LOCK_OPTION_ID {
+                self.locked_asset_factory(locked_asset_factory_address)
+                    .create_and_forward(
+                        context.get_position_reward().unwrap().clone(),
+                        destination.clone(),
+                        entering_epoch,
+                        opt_accept_funds_func,
+                    )
+                    .execute_on_dest_context_custom_range(|_, after| (after - 1, after))
+            } else {
+                // The rewards are locked from the current epoch, since the option can be changed
+                // at any time. Otherwise, old positions would get the multiplier on rewards
+                // that are already unlocked.
+                let lock_option = self.lock_options().get(lock_option_id);
+                let reward = context.get_position_reward().unwrap();
+                let mut bonus =
+                    self.apply_reward_multiplier(reward, lock_option.reward_multiplier) - reward;
+                if self.funded_rewards_enabled().get() {
+                    bonus = self.allocate_and_burn_funded_rewards(bonus);
+                }
+
+                self.locked_asset_factory(locked_asset_factory_address)
+                    .create_and_forward_custom_period(
+                        reward + &bonus,
+                        destination.clone(),
+                        self.blockchain().get_block_epoch(),
+                        lock_option.unlock_period,
+                    )
+                    .execute_on_dest_context_custom_range(|_, after| (after - 1, after))
+            };
             context.set_final_reward(result);
         } else {
             context.set_final_reward(self.create_payment(
@@ -525,17 +734,67 @@ pub trait Farm:
         let farm_token_supply = self.farm_token_supply().get();
         require!(farm_token_supply >= amount, ERROR_ZERO_AMOUNT);
 
+        let future_reward_per_share = self.calculate_future_reward_per_share();
+        self.calculate_pending_rewards(
+            &amount,
+            &attributes.reward_per_share,
+            &future_reward_per_share,
+        )
+    }
+
+    /// Lists the positions indexed for a user, starting from the `from`-th one,
+    /// along with the rewards they would get if claimed now.
+    #[view(getUserPositions)]
+    fn get_user_positions(
+        &self,
+        user: ManagedAddress,
+        from: usize,
+        size: usize,
+    ) -> ManagedMultiResultVec<UserPosition<Self::Api>> {
+        let future_reward_per_share = self.calculate_future_reward_per_share();
+
+        let mut result = ManagedVec::new();
+        for (token_nonce, position) in self.user_positions(&user).iter().skip(from).take(size) {
+            let pending_rewards = self.calculate_pending_rewards(
+                &position.amount,
+                &position.reward_per_share,
+                &future_reward_per_share,
+            );
+            result.push(UserPosition {
+                token_nonce,
+                amount: position.amount,
+                pending_rewards,
+            });
+        }
+
+        result.into()
+    }
+
+    fn calculate_future_reward_per_share(&self) -> BigUint {
+        let reward_per_share = self.reward_per_share().get();
+        let farm_token_supply = self.farm_token_supply().get();
+        if farm_token_supply == 0u64 {
+            return reward_per_share;
+        }
+
         let last_reward_nonce = self.last_reward_block_nonce().get();
         let current_block_nonce = self.blockchain().get_block_nonce();
         let reward_increase =
-            self.calculate_per_block_rewards(current_block_nonce, last_reward_nonce);
-        let reward_per_share_increase = reward_increase * &self.division_safety_constant().get()
-            / self.farm_token_supply().get();
+            self.calculate_emitted_rewards(current_block_nonce, last_reward_nonce);
+        let reward_per_share_increase =
+            reward_increase * &self.division_safety_constant().get() / farm_token_supply;
 
-        let future_reward_per_share = self.reward_per_share().get() + reward_per_share_increase;
+        reward_per_share + reward_per_share_increase
+    }
 
-        if future_reward_per_share > attributes.reward_per_share {
-            let reward_per_share_diff = future_reward_per_share - attributes.reward_per_share;
+    fn calculate_pending_rewards(
+        &self,
+        amount: &BigUint,
+        position_reward_per_share: &BigUint,
+        future_reward_per_share: &BigUint,
+    ) -> BigUint {
+        if future_reward_per_share > position_reward_per_share {
+            let reward_per_share_diff = future_reward_per_share - position_reward_per_share;
             amount * &reward_per_share_diff / self.division_safety_constant().get()
         } else {
             BigUint::zero()
@@ -0,0 +1,143 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_errors::*;
+
+use common_structs::{UnlockMilestone, UnlockPeriod};
+use config::MAX_PERCENT;
+
+/// Rewards are locked with the factory's default unlock period, without a multiplier.
+pub const DEFAULT_LOCK_OPTION_ID: usize = 0;
+
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct LockOption<M: ManagedTypeApi> {
+    pub unlock_period: UnlockPeriod<M>,
+    pub reward_multiplier: u64,
+}
+
+#[elrond_wasm::module]
+pub trait LockOptionsModule: config::ConfigModule {
+    /// Adds a lock option and returns its id.
+    /// The reward multiplier is expressed in MAX_PERCENT units, so MAX_PERCENT keeps the rewards unchanged.
+    /// The unlock epochs are relative to the month in which the rewards are claimed.
+    /// In funded mode, the bonus from the multiplier is drawn from the funded reserve.
+    #[only_owner]
+    #[endpoint(addLockOption)]
+    fn add_lock_option(
+        &self,
+        reward_multiplier: u64,
+        #[var_args] milestones: ManagedVarArgs<UnlockMilestone>,
+    ) -> usize {
+        require!(
+            reward_multiplier >= MAX_PERCENT,
+            ERROR_BAD_REWARD_MULTIPLIER
+        );
+        let unlock_milestones = milestones.to_vec();
+        self.require_valid_unlock_milestones(&unlock_milestones);
+
+        self.lock_options().push(&LockOption {
+            unlock_period: UnlockPeriod { unlock_milestones },
+            reward_multiplier,
+        })
+    }
+
+    fn require_valid_unlock_milestones(&self, unlock_milestones: &ManagedVec<UnlockMilestone>) {
+        require!(!unlock_milestones.is_empty(), ERROR_BAD_UNLOCK_MILESTONES);
+
+        let mut percents_sum = 0u64;
+        let mut last_milestone_unlock_epoch = 0u64;
+        for milestone in unlock_milestones.iter() {
+            require!(
+                milestone.unlock_epoch >= last_milestone_unlock_epoch,
+                ERROR_BAD_UNLOCK_MILESTONES
+            );
+            last_milestone_unlock_epoch = milestone.unlock_epoch;
+            percents_sum += milestone.unlock_percent as u64;
+        }
+
+        require!(percents_sum == 100, ERROR_BAD_UNLOCK_MILESTONES);
+    }
+
+    /// Sets the lock option used for users that did not select one.
+    #[only_owner]
+    #[endpoint(setDefaultLockOption)]
+    fn set_default_lock_option(&self, lock_option_id: usize) {
+        self.require_known_lock_option(lock_option_id);
+
+        self.default_lock_option_id().set(&lock_option_id);
+    }
+
+    #[endpoint(selectLockOption)]
+    fn select_lock_option(&self, lock_option_id: usize) {
+        self.require_known_lock_option(lock_option_id);
+
+        let caller = self.blockchain().get_caller();
+        self.selected_lock_option_id(&caller).set(&lock_option_id);
+    }
+
+    #[endpoint(clearLockOption)]
+    fn clear_lock_option(&self) {
+        let caller = self.blockchain().get_caller();
+        self.selected_lock_option_id(&caller).clear();
+    }
+
+    fn require_known_lock_option(&self, lock_option_id: usize) {
+        require!(
+            lock_option_id == DEFAULT_LOCK_OPTION_ID || lock_option_id <= self.lock_options().len(),
+            ERROR_UNKNOWN_LOCK_OPTION
+        );
+    }
+
+    /// Returns the lock option the rewards of the given user are locked with.
+    #[view(getUserLockOption)]
+    fn get_user_lock_option_id(&self, user: ManagedAddress) -> usize {
+        let selected_mapper = self.selected_lock_option_id(&user);
+        let lock_option_id = if selected_mapper.is_empty() {
+            self.default_lock_option_id().get()
+        } else {
+            selected_mapper.get()
+        };
+
+        // options are never removed, but keep the default if the storage is inconsistent
+        if lock_option_id > self.lock_options().len() {
+            DEFAULT_LOCK_OPTION_ID
+        } else {
+            lock_option_id
+        }
+    }
+
+    fn apply_reward_multiplier(&self, reward: &BigUint, reward_multiplier: u64) -> BigUint {
+        reward.clone() * reward_multiplier / MAX_PERCENT
+    }
+
+    #[view(getLockOption)]
+    fn get_lock_option(&self, lock_option_id: usize) -> LockOption<Self::Api> {
+        require!(
+            lock_option_id != DEFAULT_LOCK_OPTION_ID && lock_option_id <= self.lock_options().len(),
+            ERROR_UNKNOWN_LOCK_OPTION
+        );
+
+        self.lock_options().get(lock_option_id)
+    }
+
+    /// Returns all the lock options. The option at position `i` has the id `i + 1`.
+    #[view(getLockOptions)]
+    fn get_lock_options(&self) -> ManagedMultiResultVec<LockOption<Self::Api>> {
+        let mut result = ManagedVec::new();
+        for lock_option in self.lock_options().iter() {
+            result.push(lock_option);
+        }
+
+        result.into()
+    }
+
+    #[storage_mapper("lockOptions")]
+    fn lock_options(&self) -> VecMapper<LockOption<Self::Api>>;
+
+    #[view(getDefaultLockOption)]
+    #[storage_mapper("defaultLockOptionId")]
+    fn default_lock_option_id(&self) -> SingleValueMapper<usize>;
+
+    #[storage_mapper("selectedLockOptionId")]
+    fn selected_lock_option_id(&self, user: &ManagedAddress) -> SingleValueMapper<usize>;
+}
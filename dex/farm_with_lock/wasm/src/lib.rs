@@ -8,44 +8,92 @@ elrond_wasm_node::wasm_endpoints! {
     farm_with_lock
     (
         callBack
+        acceptMigratedPosition
+        addLockOption
+        addMigrationSource
+        authorizeClaimer
         calculateRewardsForGivenPosition
         claimRewards
+        claimRewardsOnBehalf
+        clearLockOption
+        clearRewardRecipient
         compoundRewards
+        depositInCustody
         end_produce_rewards
         enterFarm
         exitFarm
+        getAuthorizedClaimers
         getBurnGasLimit
+        getCustodyPositions
+        getDefaultLockOption
         getDivisionSafetyConstant
         getFarmMigrationConfiguration
         getFarmTokenId
         getFarmTokenSupply
         getFarmingTokenId
+        getFundedRewardCapacity
+        getFundedRewardsDistributed
         getLastErrorMessage
         getLastRewardBlockNonce
+        getLockOption
+        getLockOptions
         getLockedAssetFactoryManagedAddress
+        getLowRewardReserveThreshold
+        getMigratedFarmTokenSupply
+        getMigratedFarmingAmount
+        getMigrationProgress
+        getMigrationSourceVersion
+        getMigrationTarget
         getMinimumFarmingEpoch
         getPairContractManagedAddress
         getPenaltyPercent
         getPerBlockRewardAmount
+        getPositionRewardRecipient
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
+        getRemainingFundedRewards
+        getRemainingRewardBlocks
         getRewardPerShare
+        getRewardRecipient
         getRewardReserve
         getRewardTokenId
         getState
         getTransferExecGasLimit
+        getUserLockOption
+        getUserPositions
+        isFundedRewardsModeEnabled
+        isPositionIndexEnabled
         mergeFarmTokens
+        migrateCustodyPositions
         migrateFromV1_2Farm
+        migratePosition
         pause
         registerFarmToken
+        removeMigrationSource
         resume
+        revokeClaimer
+        selectLockOption
+        setDefaultLockOption
         setFarmMigrationConfig
         setFarmTokenSupply
+        setFundedRewardsMode
         setLocalRolesFarmToken
+        setLowRewardReserveThreshold
+        setMigrationTarget
         setPerBlockRewardAmount
+        setPositionIndexEnabled
+        setPositionTokenMetadata
+        setRewardRecipient
         setRpsAndStartRewards
         set_burn_gas_limit
         set_minimum_farming_epochs
         set_penalty_percent
         set_transfer_exec_gas_limit
+        splitFarmToken
         startProduceRewards
+        topUpRewards
+        withdrawFromCustody
+        withdrawFundedRewards
     )
 }
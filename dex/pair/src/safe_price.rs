@@ -104,6 +104,16 @@ pub trait SafePriceModule:
     ) -> MultiResult2<EsdtTokenPayment<Self::Api>, EsdtTokenPayment<Self::Api>> {
         self.update_safe_state_on_the_fly();
 
+        self.get_tokens_for_given_position_with_safe_price(liquidity)
+    }
+
+    /// Same as `updateAndGetTokensForGivenPositionWithSafePrice`,
+    /// using the safe price state as of its last update.
+    #[view(getTokensForGivenPositionWithSafePrice)]
+    fn get_tokens_for_given_position_with_safe_price(
+        &self,
+        liquidity: BigUint,
+    ) -> MultiResult2<EsdtTokenPayment<Self::Api>, EsdtTokenPayment<Self::Api>> {
         let c_state = self.get_current_state_or_default();
         let total_supply = self.lp_token_supply().get();
         let first_token_id = self.first_token_id().get();
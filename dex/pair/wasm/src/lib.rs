@@ -33,6 +33,7 @@ elrond_wasm_node::wasm_endpoints! {
         getSpecialFee
         getState
         getTokensForGivenPosition
+        getTokensForGivenPositionWithSafePrice
         getTotalFeePercent
         getTotalSupply
         getTransferExecGasLimit
@@ -1,18 +1,27 @@
 use common_structs::FarmTokenAttributes;
 use elrond_wasm::types::{
-    Address, BigUint, EsdtLocalRole, EsdtTokenPayment, ManagedAddress, OptionalArg, TokenIdentifier,
+    Address, BigUint, EsdtLocalRole, EsdtTokenPayment, ManagedAddress, ManagedVec, OptionalArg,
+    TokenIdentifier,
 };
 use elrond_wasm_debug::tx_mock::{TxContextStack, TxInputESDT};
 use elrond_wasm_debug::{
-    managed_address, managed_biguint, managed_token_id, rust_biguint, testing_framework::*,
-    DebugApi,
+    managed_address, managed_biguint, managed_buffer, managed_token_id, rust_biguint,
+    testing_framework::*, DebugApi,
 };
 
 type RustBigUint = num_bigint::BigUint;
 use migration_from_v1_2::{FarmTokenAttributesV1_2, MigrationModule};
+use position_index::PositionIndexModule;
 
+use claim_delegation::ClaimDelegationModule;
 use config::*;
+use farm::custom_rewards::CustomRewardsModule;
+use farm::farm_token_merge::FarmTokenMergeModule;
 use farm::*;
+use farm_migration::{FarmAttributesVersion, FarmMigrationModule};
+use funded_rewards::FundedRewardsModule;
+use rewards::RewardsModule;
+use token_metadata::TokenMetadataModule;
 
 const GENERATED_FILE_PREFIX: &'static str = "_generated_";
 const MANDOS_FILE_EXTENSION: &'static str = ".scen.json";
@@ -22,10 +31,13 @@ const MEX_TOKEN_ID: &[u8] = b"MEX-abcdef"; // reward token ID
 const LP_TOKEN_ID: &[u8] = b"LPTOK-abcdef"; // farming token ID
 const FARM_TOKEN_ID: &[u8] = b"FARM-abcdef";
 const OLD_FARM_TOKEN_ID: &[u8] = b"OFARM-abcdef";
+const NEW_FARM_TOKEN_ID: &[u8] = b"NFARM-abcdef";
 const DIVISION_SAFETY_CONSTANT: u64 = 1_000_000_000_000;
 const MIN_FARMING_EPOCHS: u8 = 2;
 const PENALTY_PERCENT: u64 = 10;
 const PER_BLOCK_REWARD_AMOUNT: u64 = 5_000;
+const BLOCKS_IN_EPOCH: u64 = SECONDS_IN_DAY / DEFAULT_BLOCK_TIME_SECONDS;
+const BLOCKS_IN_YEAR: u64 = SECONDS_IN_YEAR / DEFAULT_BLOCK_TIME_SECONDS;
 
 const USER_TOTAL_LP_TOKENS: u64 = 5_000_000_000;
 
@@ -336,6 +348,170 @@ fn claim_rewards<FarmObjBuilder>(
     let _ = TxContextStack::static_pop();
 }
 
+fn split_farm_token<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    farm_token_nonce: u64,
+    split_amounts: &[u64],
+    expected_first_new_nonce: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let total_amount: u64 = split_amounts.iter().sum();
+
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_esdt_transfer(
+            &farm_setup.user_address,
+            &farm_setup.farm_wrapper,
+            FARM_TOKEN_ID,
+            farm_token_nonce,
+            &rust_biguint!(total_amount),
+            |sc| {
+                let mut amounts = ManagedVec::new();
+                for amount in split_amounts {
+                    amounts.push(managed_biguint!(*amount));
+                }
+
+                let result = sc.split_farm_token(amounts, OptionalArg::None).to_vec();
+                assert_eq!(result.len(), split_amounts.len());
+                for (i, payment) in result.iter().enumerate() {
+                    assert_eq!(payment.token_identifier, managed_token_id!(FARM_TOKEN_ID));
+                    assert_eq!(payment.token_nonce, expected_first_new_nonce + i as u64);
+                    assert_eq!(payment.amount, managed_biguint!(split_amounts[i]));
+                }
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    let _ = DebugApi::dummy();
+    for (i, amount) in split_amounts.iter().enumerate() {
+        let expected_attributes = FarmTokenAttributes::<DebugApi> {
+            reward_per_share: managed_biguint!(0),
+            original_entering_epoch: 0,
+            entering_epoch: 0,
+            initial_farming_amount: managed_biguint!(*amount),
+            compounded_reward: managed_biguint!(0),
+            current_farm_amount: managed_biguint!(*amount),
+        };
+        b_mock.check_nft_balance(
+            &farm_setup.user_address,
+            FARM_TOKEN_ID,
+            expected_first_new_nonce + i as u64,
+            &rust_biguint!(*amount),
+            &expected_attributes,
+        );
+    }
+    let _ = TxContextStack::static_pop();
+}
+
+fn simulate_rewards<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    farm_token_amount: u64,
+    block_nonce: u64,
+    opt_per_block_reward_amount: Option<u64>,
+    opt_farm_token_supply: Option<u64>,
+    expected_rewards: u64,
+    expected_penalty: u64,
+    expected_apr: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            let attributes = FarmTokenAttributes {
+                reward_per_share: managed_biguint!(0),
+                original_entering_epoch: 0,
+                entering_epoch: 0,
+                initial_farming_amount: managed_biguint!(farm_token_amount),
+                compounded_reward: managed_biguint!(0),
+                current_farm_amount: managed_biguint!(farm_token_amount),
+            };
+            let opt_per_block_reward_amount = match opt_per_block_reward_amount {
+                Some(amount) => OptionalArg::Some(managed_biguint!(amount)),
+                None => OptionalArg::None,
+            };
+            let opt_farm_token_supply = match opt_farm_token_supply {
+                Some(supply) => OptionalArg::Some(managed_biguint!(supply)),
+                None => OptionalArg::None,
+            };
+
+            let (rewards, penalty, apr) = sc
+                .simulate_rewards(
+                    managed_biguint!(farm_token_amount),
+                    attributes,
+                    block_nonce,
+                    opt_per_block_reward_amount,
+                    opt_farm_token_supply,
+                )
+                .into_tuple();
+            assert_eq!(rewards, managed_biguint!(expected_rewards));
+            assert_eq!(penalty, managed_biguint!(expected_penalty));
+            assert_eq!(apr, managed_biguint!(expected_apr));
+        })
+        .assert_ok();
+}
+
+fn enable_funded_rewards<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    top_up_amount: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.produce_rewards_enabled().set(&false);
+                sc.set_funded_rewards_mode(true);
+                sc.start_produce_rewards();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock.set_esdt_balance(
+        &farm_setup.owner_address,
+        MEX_TOKEN_ID,
+        &rust_biguint!(top_up_amount),
+    );
+    b_mock
+        .execute_esdt_transfer(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            MEX_TOKEN_ID,
+            0,
+            &rust_biguint!(top_up_amount),
+            |sc| {
+                sc.top_up_rewards();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+fn check_remaining_reward_blocks<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    expected_remaining_blocks: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            assert_eq!(sc.get_remaining_reward_blocks(), expected_remaining_blocks);
+        })
+        .assert_ok();
+}
+
 fn check_farm_token_supply<FarmObjBuilder>(
     farm_setup: &mut FarmSetup<FarmObjBuilder>,
     expected_farm_token_supply: u64,
@@ -386,102 +562,653 @@ fn test_farm_setup() {
         .write_mandos_output(&file_name);
 }
 
-#[test]
-fn test_enter_farm() {
-    let mut farm_setup = setup_farm(farm::contract_obj);
+#[test]
+fn test_enter_farm() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+
+    let file_name = create_generated_mandos_file_name("enter_farm");
+    farm_setup
+        .blockchain_wrapper
+        .write_mandos_output(&file_name);
+}
+
+#[test]
+fn test_exit_farm() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 10);
+
+    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
+    let expected_lp_token_balance = rust_biguint!(USER_TOTAL_LP_TOKENS);
+    exit_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        expected_farm_token_nonce,
+        expected_mex_out,
+        &rust_biguint!(expected_mex_out),
+        &expected_lp_token_balance,
+    );
+    check_farm_token_supply(&mut farm_setup, 0);
+}
+
+#[test]
+fn test_claim_rewards() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 10);
+
+    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
+    let expected_lp_token_balance = rust_biguint!(USER_TOTAL_LP_TOKENS - farm_in_amount);
+    let expected_reward_per_share = 500_000_000;
+    claim_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        expected_farm_token_nonce,
+        expected_mex_out,
+        &rust_biguint!(expected_mex_out),
+        &expected_lp_token_balance,
+        expected_farm_token_nonce + 1,
+        expected_reward_per_share,
+    );
+    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+}
+
+#[test]
+fn test_split_farm_token() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+
+    split_farm_token(
+        &mut farm_setup,
+        expected_farm_token_nonce,
+        &[25_000_000, 75_000_000],
+        expected_farm_token_nonce + 1,
+    );
+    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 10);
+
+    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT / 4;
+    exit_farm(
+        &mut farm_setup,
+        25_000_000,
+        expected_farm_token_nonce + 1,
+        expected_mex_out,
+        &rust_biguint!(expected_mex_out),
+        &rust_biguint!(USER_TOTAL_LP_TOKENS - 75_000_000),
+    );
+    check_farm_token_supply(&mut farm_setup, 75_000_000);
+}
+
+#[test]
+fn test_simulate_rewards() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+
+    let expected_apr = PER_BLOCK_REWARD_AMOUNT * BLOCKS_IN_YEAR * MAX_PERCENT / farm_in_amount;
+    let expected_penalty = farm_in_amount * PENALTY_PERCENT / MAX_PERCENT;
+    simulate_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        10,
+        None,
+        None,
+        10 * PER_BLOCK_REWARD_AMOUNT,
+        expected_penalty,
+        expected_apr,
+    );
+
+    // past the minimum farming epochs, no penalty is applied
+    let block_nonce = MIN_FARMING_EPOCHS as u64 * BLOCKS_IN_EPOCH;
+    simulate_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        block_nonce,
+        None,
+        None,
+        block_nonce * PER_BLOCK_REWARD_AMOUNT,
+        0,
+        expected_apr,
+    );
+
+    // hypothetical reward rate and supply, from the current block onwards
+    set_block_nonce(&mut farm_setup, 10);
+    simulate_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        20,
+        Some(4 * PER_BLOCK_REWARD_AMOUNT),
+        Some(2 * farm_in_amount),
+        10 * PER_BLOCK_REWARD_AMOUNT + 20 * PER_BLOCK_REWARD_AMOUNT,
+        expected_penalty,
+        2 * expected_apr,
+    );
+
+    // with 3 seconds blocks, twice as many blocks are produced in a year and in an epoch
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_block_time(3);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+    let block_nonce = 10 + MIN_FARMING_EPOCHS as u64 * BLOCKS_IN_EPOCH;
+    simulate_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        block_nonce,
+        None,
+        None,
+        block_nonce * PER_BLOCK_REWARD_AMOUNT,
+        expected_penalty,
+        2 * expected_apr,
+    );
+}
+
+#[test]
+fn test_funded_rewards() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let top_up_amount = 30 * PER_BLOCK_REWARD_AMOUNT;
+    enable_funded_rewards(&mut farm_setup, top_up_amount);
+    check_remaining_reward_blocks(&mut farm_setup, 30);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+
+    set_block_nonce(&mut farm_setup, 10);
+    check_remaining_reward_blocks(&mut farm_setup, 20);
+
+    // emission halts once the reserve is depleted
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 50);
+    check_remaining_reward_blocks(&mut farm_setup, 0);
+    exit_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        expected_farm_token_nonce,
+        top_up_amount,
+        &rust_biguint!(top_up_amount),
+        &rust_biguint!(USER_TOTAL_LP_TOKENS),
+    );
+}
+
+#[test]
+fn test_funded_rewards_withdraw() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let top_up_amount = 30 * PER_BLOCK_REWARD_AMOUNT;
+    enable_funded_rewards(&mut farm_setup, top_up_amount);
+
+    let farm_in_amount = 100_000_000;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        1,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+    set_block_nonce(&mut farm_setup, 10);
+
+    let rust_zero = rust_biguint!(0u64);
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+
+    // the rewards emitted so far stay in the reserve
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.withdraw_funded_rewards(managed_biguint!(21 * PER_BLOCK_REWARD_AMOUNT));
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Not enough funded rewards");
+
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.end_produce_rewards();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_funded_rewards_mode(false);
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Funded rewards remaining");
+
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.withdraw_funded_rewards(managed_biguint!(20 * PER_BLOCK_REWARD_AMOUNT));
+                sc.set_funded_rewards_mode(false);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock.check_esdt_balance(
+        &farm_setup.owner_address,
+        MEX_TOKEN_ID,
+        &rust_biguint!(20 * PER_BLOCK_REWARD_AMOUNT),
+    );
+    b_mock.check_esdt_balance(
+        farm_setup.farm_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &rust_biguint!(10 * PER_BLOCK_REWARD_AMOUNT),
+    );
+}
+
+fn claim_rewards_on_behalf<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    claimer: &Address,
+    expected_reward: u64,
+    opt_expected_error: Option<&str>,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let owner = farm_setup.user_address.clone();
+    let result = farm_setup.blockchain_wrapper.execute_tx(
+        claimer,
+        &farm_setup.farm_wrapper,
+        &rust_biguint!(0),
+        |sc| {
+            let rewards = sc
+                .claim_rewards_on_behalf(managed_address!(&owner))
+                .to_vec();
+            assert_eq!(rewards.len(), 1);
+            assert_eq!(rewards.get(0).amount, managed_biguint!(expected_reward));
+
+            StateChange::Commit
+        },
+    );
+
+    match opt_expected_error {
+        Some(expected_error) => result.assert_user_error(expected_error),
+        None => result.assert_ok(),
+    }
+}
+
+#[test]
+fn test_claim_delegation() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+    let rust_zero = rust_biguint!(0u64);
+    let claimer = farm_setup
+        .blockchain_wrapper
+        .create_user_account(&rust_zero);
+    let recipient = farm_setup
+        .blockchain_wrapper
+        .create_user_account(&rust_zero);
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_position_index_enabled(true);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
 
     let farm_in_amount = 100_000_000;
-    let expected_farm_token_nonce = 1;
     enter_farm(
         &mut farm_setup,
         farm_in_amount,
         &[],
-        expected_farm_token_nonce,
+        1,
         0,
         0,
         0,
         farm_in_amount,
         0,
     );
-    check_farm_token_supply(&mut farm_setup, farm_in_amount);
 
-    let file_name = create_generated_mandos_file_name("enter_farm");
+    let user_address = farm_setup.user_address.clone();
     farm_setup
         .blockchain_wrapper
-        .write_mandos_output(&file_name);
-}
+        .execute_tx(&user_address, &farm_setup.farm_wrapper, &rust_zero, |sc| {
+            sc.set_reward_recipient(managed_address!(&recipient));
+            sc.authorize_claimer(managed_address!(&claimer));
 
-#[test]
-fn test_exit_farm() {
-    let mut farm_setup = setup_farm(farm::contract_obj);
+            StateChange::Commit
+        })
+        .assert_ok();
+    farm_setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &user_address,
+            &farm_setup.farm_wrapper,
+            FARM_TOKEN_ID,
+            1,
+            &rust_biguint!(farm_in_amount),
+            |sc| {
+                sc.deposit_in_custody(OptionalArg::None);
 
-    let farm_in_amount = 100_000_000;
-    let expected_farm_token_nonce = 1;
-    enter_farm(
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    set_block_nonce(&mut farm_setup, 10);
+
+    // only authorized claimers can claim, and the rewards go to the recipient
+    claim_rewards_on_behalf(&mut farm_setup, &recipient, 0, Some("Permission denied"));
+    claim_rewards_on_behalf(
         &mut farm_setup,
-        farm_in_amount,
-        &[],
-        expected_farm_token_nonce,
-        0,
-        0,
-        0,
-        farm_in_amount,
-        0,
+        &claimer,
+        10 * PER_BLOCK_REWARD_AMOUNT,
+        None,
     );
-    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+    farm_setup.blockchain_wrapper.check_esdt_balance(
+        &recipient,
+        MEX_TOKEN_ID,
+        &rust_biguint!(10 * PER_BLOCK_REWARD_AMOUNT),
+    );
+    check_user_positions(&mut farm_setup, 0, 10, &[(2, farm_in_amount, 0)]);
 
-    set_block_epoch(&mut farm_setup, 5);
-    set_block_nonce(&mut farm_setup, 10);
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(&user_address, &farm_setup.farm_wrapper, &rust_zero, |sc| {
+            sc.revoke_claimer(managed_address!(&claimer));
+            let _ = sc.withdraw_from_custody();
 
-    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
-    let expected_lp_token_balance = rust_biguint!(USER_TOTAL_LP_TOKENS);
+            StateChange::Commit
+        })
+        .assert_ok();
+    claim_rewards_on_behalf(&mut farm_setup, &claimer, 0, Some("Permission denied"));
+
+    // the recipient only applies to claims, exit rewards go to the owner
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 20);
     exit_farm(
         &mut farm_setup,
         farm_in_amount,
-        expected_farm_token_nonce,
-        expected_mex_out,
-        &rust_biguint!(expected_mex_out),
-        &expected_lp_token_balance,
+        2,
+        10 * PER_BLOCK_REWARD_AMOUNT,
+        &rust_biguint!(10 * PER_BLOCK_REWARD_AMOUNT),
+        &rust_biguint!(USER_TOTAL_LP_TOKENS),
+    );
+    farm_setup.blockchain_wrapper.check_esdt_balance(
+        &recipient,
+        MEX_TOKEN_ID,
+        &rust_biguint!(10 * PER_BLOCK_REWARD_AMOUNT),
     );
-    check_farm_token_supply(&mut farm_setup, 0);
 }
 
 #[test]
-fn test_claim_rewards() {
+fn test_position_token_metadata() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            assert_eq!(sc.build_position_token_name(5), managed_buffer!(b""));
+        })
+        .assert_ok();
+
+    b_mock
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_position_token_metadata(
+                    managed_buffer!(b"LP Farm"),
+                    managed_buffer!(b"https://dex.example/positions/"),
+                );
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            assert_eq!(
+                sc.build_position_token_name(5),
+                managed_buffer!(b"LP Farm - epoch 5")
+            );
+            assert_eq!(
+                sc.build_position_token_name(120),
+                managed_buffer!(b"LP Farm - epoch 120")
+            );
+
+            let attributes = FarmTokenAttributes {
+                reward_per_share: managed_biguint!(0),
+                original_entering_epoch: 3,
+                entering_epoch: 5,
+                initial_farming_amount: managed_biguint!(1_000_000_000_000_000_000u64),
+                compounded_reward: managed_biguint!(7),
+                current_farm_amount: managed_biguint!(12_000_000_000_000_000_005u64),
+            };
+            assert_eq!(
+                sc.build_position_attributes_uri(&attributes),
+                managed_buffer!(
+                    b"data:application/json,%7B%22rewardPerShare%22%3A%220%22%2C\
+                    %22originalEnteringEpoch%22%3A3%2C\
+                    %22enteringEpoch%22%3A5%2C\
+                    %22initialFarmingAmount%22%3A%221000000000000000000%22%2C\
+                    %22compoundedReward%22%3A%227%22%2C\
+                    %22currentFarmAmount%22%3A%2212000000000000000005%22%7D"
+                )
+            );
+        })
+        .assert_ok();
+}
+
+fn check_user_positions<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    from: usize,
+    size: usize,
+    expected_positions: &[(u64, u64, u64)],
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm::ContractObj<DebugApi>,
+{
+    let user = farm_setup.user_address.clone();
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            let positions = sc
+                .get_user_positions(managed_address!(&user), from, size)
+                .to_vec();
+            assert_eq!(positions.len(), expected_positions.len());
+
+            for (position, expected) in positions.iter().zip(expected_positions.iter()) {
+                let (nonce, amount, pending_rewards) = *expected;
+                assert_eq!(position.token_nonce, nonce);
+                assert_eq!(position.amount, managed_biguint!(amount));
+                assert_eq!(position.pending_rewards, managed_biguint!(pending_rewards));
+            }
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_user_positions_index() {
     let mut farm_setup = setup_farm(farm::contract_obj);
 
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_position_index_enabled(true);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
     let farm_in_amount = 100_000_000;
-    let expected_farm_token_nonce = 1;
     enter_farm(
         &mut farm_setup,
         farm_in_amount,
         &[],
-        expected_farm_token_nonce,
+        1,
         0,
         0,
         0,
         farm_in_amount,
         0,
     );
-    check_farm_token_supply(&mut farm_setup, farm_in_amount);
 
     set_block_epoch(&mut farm_setup, 5);
     set_block_nonce(&mut farm_setup, 10);
 
-    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
-    let expected_lp_token_balance = rust_biguint!(USER_TOTAL_LP_TOKENS - farm_in_amount);
     let expected_reward_per_share = 500_000_000;
-    claim_rewards(
+    enter_farm(
         &mut farm_setup,
         farm_in_amount,
-        expected_farm_token_nonce,
+        &[],
+        2,
+        expected_reward_per_share,
+        5,
+        5,
+        farm_in_amount,
+        0,
+    );
+
+    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
+    check_user_positions(
+        &mut farm_setup,
+        0,
+        10,
+        &[
+            (1, farm_in_amount, expected_mex_out),
+            (2, farm_in_amount, 0),
+        ],
+    );
+    check_user_positions(&mut farm_setup, 1, 10, &[(2, farm_in_amount, 0)]);
+
+    exit_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        1,
         expected_mex_out,
         &rust_biguint!(expected_mex_out),
-        &expected_lp_token_balance,
-        expected_farm_token_nonce + 1,
-        expected_reward_per_share,
+        &rust_biguint!(USER_TOTAL_LP_TOKENS - farm_in_amount),
     );
-    check_farm_token_supply(&mut farm_setup, farm_in_amount);
+    check_user_positions(&mut farm_setup, 0, 10, &[(2, farm_in_amount, 0)]);
 }
 
 fn steps_enter_farm_twice<FarmObjBuilder>(farm_builder: FarmObjBuilder) -> FarmSetup<FarmObjBuilder>
@@ -684,3 +1411,170 @@ fn test_migration() {
 
     do_basic_migration(&mut farm_setup);
 }
+
+#[test]
+fn test_migrate_position_to_new_farm() {
+    let mut farm_setup = setup_farm(farm::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    enter_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+        farm_in_amount,
+        0,
+    );
+
+    let rust_zero = rust_biguint!(0u64);
+    let owner = farm_setup.owner_address.clone();
+    let user = farm_setup.user_address.clone();
+    let old_farm = farm_setup.farm_wrapper.address_ref().clone();
+
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    let new_farm_wrapper =
+        b_mock.create_sc_account(&rust_zero, Some(&owner), farm::contract_obj, FARM_WASM_PATH);
+    b_mock
+        .execute_tx(&owner, &new_farm_wrapper, &rust_zero, |sc| {
+            sc.init(
+                managed_token_id!(MEX_TOKEN_ID),
+                managed_token_id!(LP_TOKEN_ID),
+                managed_biguint!(DIVISION_SAFETY_CONSTANT),
+                managed_address!(&Address::zero()),
+            );
+            sc.farm_token_id()
+                .set(&managed_token_id!(NEW_FARM_TOKEN_ID));
+            sc.per_block_reward_amount()
+                .set(&managed_biguint!(PER_BLOCK_REWARD_AMOUNT));
+            sc.minimum_farming_epochs().set(&MIN_FARMING_EPOCHS);
+            sc.penalty_percent().set(&PENALTY_PERCENT);
+            sc.state().set(&State::Active);
+            sc.produce_rewards_enabled().set(&true);
+
+            sc.add_migration_source(managed_address!(&old_farm), FarmAttributesVersion::V1_3);
+            sc.set_position_index_enabled(true);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    let farm_token_roles = [
+        EsdtLocalRole::NftCreate,
+        EsdtLocalRole::NftAddQuantity,
+        EsdtLocalRole::NftBurn,
+    ];
+    b_mock.set_esdt_local_roles(
+        new_farm_wrapper.address_ref(),
+        NEW_FARM_TOKEN_ID,
+        &farm_token_roles[..],
+    );
+    b_mock.set_esdt_local_roles(
+        new_farm_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &[EsdtLocalRole::Mint][..],
+    );
+
+    let new_farm = new_farm_wrapper.address_ref().clone();
+    b_mock
+        .execute_tx(&owner, &farm_setup.farm_wrapper, &rust_zero, |sc| {
+            sc.set_migration_target(managed_address!(&new_farm));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    b_mock.set_block_epoch(5);
+    b_mock.set_block_nonce(10);
+
+    let expected_mex_out = 10 * PER_BLOCK_REWARD_AMOUNT;
+    b_mock
+        .execute_esdt_transfer(
+            &user,
+            &farm_setup.farm_wrapper,
+            FARM_TOKEN_ID,
+            expected_farm_token_nonce,
+            &rust_biguint!(farm_in_amount),
+            |sc| {
+                let (new_position, reward) = sc.migrate_position(OptionalArg::None).into_tuple();
+                assert_eq!(
+                    new_position.token_identifier,
+                    managed_token_id!(NEW_FARM_TOKEN_ID)
+                );
+                assert_eq!(new_position.token_nonce, 1);
+                assert_eq!(new_position.amount, managed_biguint!(farm_in_amount));
+                assert_eq!(reward.amount, managed_biguint!(expected_mex_out));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock.check_esdt_balance(&user, MEX_TOKEN_ID, &rust_biguint!(expected_mex_out));
+
+    let _ = DebugApi::dummy();
+    b_mock.check_nft_balance(
+        &user,
+        NEW_FARM_TOKEN_ID,
+        1,
+        &rust_biguint!(farm_in_amount),
+        &FarmTokenAttributes::<DebugApi> {
+            reward_per_share: managed_biguint!(0),
+            original_entering_epoch: 0,
+            entering_epoch: 0,
+            initial_farming_amount: managed_biguint!(farm_in_amount),
+            compounded_reward: managed_biguint!(0),
+            current_farm_amount: managed_biguint!(farm_in_amount),
+        },
+    );
+    let _ = TxContextStack::static_pop();
+
+    b_mock
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            let (migrated_supply, total_supply) = sc.get_migration_progress().into_tuple();
+            assert_eq!(migrated_supply, managed_biguint!(farm_in_amount));
+            assert_eq!(total_supply, managed_biguint!(farm_in_amount));
+        })
+        .assert_ok();
+
+    b_mock
+        .execute_query(&new_farm_wrapper, |sc| {
+            assert_eq!(
+                sc.migrated_farming_amount(&managed_address!(&old_farm))
+                    .get(),
+                managed_biguint!(farm_in_amount)
+            );
+            assert_eq!(
+                sc.farm_token_supply().get(),
+                managed_biguint!(farm_in_amount)
+            );
+
+            let positions = sc
+                .get_user_positions(managed_address!(&user), 0, 10)
+                .to_vec();
+            assert_eq!(positions.len(), 1);
+            let position = positions.get(0);
+            assert_eq!(position.token_nonce, 1);
+            assert_eq!(position.amount, managed_biguint!(farm_in_amount));
+        })
+        .assert_ok();
+
+    // the old farm can no longer be entered
+    b_mock
+        .execute_esdt_transfer(
+            &user,
+            &farm_setup.farm_wrapper,
+            LP_TOKEN_ID,
+            0,
+            &rust_biguint!(farm_in_amount),
+            |sc| {
+                let _ = sc.enter_farm(OptionalArg::None);
+
+                StateChange::Revert
+            },
+        )
+        .assert_user_error("Farm is migrating");
+}
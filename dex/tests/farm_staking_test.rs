@@ -7,8 +7,10 @@ use elrond_wasm_debug::{
 type RustBigUint = num_bigint::BigUint;
 
 use config::*;
-use farm_staking::custom_rewards::{CustomRewardsModule, BLOCKS_IN_YEAR};
+use farm_staking::custom_rewards::{AprCapMode, CustomRewardsModule, BLOCKS_IN_YEAR};
 use farm_staking::farm_token_merge::StakingFarmTokenAttributes;
+use farm_staking::instant_unbond::InstantUnbondModule;
+use farm_staking::unbond_queue::{UnbondQueueModule, UnbondScheduleEntry};
 use farm_staking::*;
 
 const FARM_WASM_PATH: &'static str = "farm/output/farm-staking.wasm";
@@ -294,6 +296,132 @@ fn unstake_farm<FarmObjBuilder>(
     );
 }
 
+fn enable_instant_unbond<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    fee_percent: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_instant_unbond_enabled(true);
+                sc.set_instant_unbond_fee(fee_percent);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+fn unbond_farm_early<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    farm_token_nonce: u64,
+    farm_token_amount: u64,
+    expected_farming_token_out: u64,
+    expected_user_farming_token_balance: &RustBigUint,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_esdt_transfer(
+            &farm_setup.user_address,
+            &farm_setup.farm_wrapper,
+            FARM_TOKEN_ID,
+            farm_token_nonce,
+            &rust_biguint!(farm_token_amount),
+            |sc| {
+                let payment = sc.unbond_farm_early();
+                assert_eq!(
+                    payment.token_identifier,
+                    managed_token_id!(FARMING_TOKEN_ID)
+                );
+                assert_eq!(payment.token_nonce, 0);
+                assert_eq!(payment.amount, managed_biguint!(expected_farming_token_out));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock.check_esdt_balance(
+        &farm_setup.user_address,
+        FARMING_TOKEN_ID,
+        expected_user_farming_token_balance,
+    );
+}
+
+fn unbond_farm_multiple<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    unbond_tokens: &[TxInputESDT],
+    expected_farming_token_out: u64,
+    expected_user_farming_token_balance: &RustBigUint,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    let b_mock = &mut farm_setup.blockchain_wrapper;
+    b_mock
+        .execute_esdt_multi_transfer(
+            &farm_setup.user_address,
+            &farm_setup.farm_wrapper,
+            unbond_tokens,
+            |sc| {
+                let payment = sc.unbond_farm();
+                assert_eq!(
+                    payment.token_identifier,
+                    managed_token_id!(FARMING_TOKEN_ID)
+                );
+                assert_eq!(payment.amount, managed_biguint!(expected_farming_token_out));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    b_mock.check_esdt_balance(
+        &farm_setup.user_address,
+        FARMING_TOKEN_ID,
+        expected_user_farming_token_balance,
+    );
+}
+
+fn check_unbond_schedule<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    expected_schedule: &[(u64, u64)],
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    farm_setup
+        .blockchain_wrapper
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            let schedule = sc.get_unbond_schedule().to_vec();
+            assert_eq!(schedule.len(), expected_schedule.len());
+
+            let mut expected_total = 0;
+            for (i, (unlock_epoch, amount)) in expected_schedule.iter().enumerate() {
+                assert_eq!(
+                    schedule.get(i),
+                    UnbondScheduleEntry {
+                        unlock_epoch: *unlock_epoch,
+                        amount: managed_biguint!(*amount),
+                    }
+                );
+                expected_total += amount;
+            }
+            assert_eq!(
+                sc.total_unbonding_supply().get(),
+                managed_biguint!(expected_total)
+            );
+        })
+        .assert_ok();
+}
+
 fn claim_rewards<FarmObjBuilder>(
     farm_setup: &mut FarmSetup<FarmObjBuilder>,
     farm_token_amount: u64,
@@ -370,6 +498,46 @@ fn claim_rewards<FarmObjBuilder>(
     let _ = TxContextStack::static_pop();
 }
 
+fn set_apr_cap_mode<FarmObjBuilder>(farm_setup: &mut FarmSetup<FarmObjBuilder>, mode: AprCapMode)
+where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_apr_cap_mode(mode);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+fn check_effective_apr_info<FarmObjBuilder>(
+    farm_setup: &mut FarmSetup<FarmObjBuilder>,
+    expected_apr: u64,
+    expected_clipped_per_block: u64,
+) where
+    FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
+{
+    farm_setup
+        .blockchain_wrapper
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            let (apr, clipped_per_block) = sc.get_effective_apr_info().into_tuple();
+            assert_eq!(apr, managed_biguint!(expected_apr));
+            assert_eq!(
+                clipped_per_block,
+                managed_biguint!(expected_clipped_per_block)
+            );
+        })
+        .assert_ok();
+}
+
 fn check_farm_token_supply<FarmObjBuilder>(
     farm_setup: &mut FarmSetup<FarmObjBuilder>,
     expected_farm_token_supply: u64,
@@ -513,6 +681,102 @@ fn test_claim_rewards() {
     check_farm_token_supply(&mut farm_setup, farm_in_amount);
 }
 
+#[test]
+fn test_apr_cap_modes() {
+    let mut farm_setup = setup_farm(farm_staking::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    stake_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+    );
+
+    let max_per_block_reward = farm_in_amount * MAX_APR / MAX_PERCENT / BLOCKS_IN_YEAR;
+    check_effective_apr_info(
+        &mut farm_setup,
+        MAX_APR,
+        PER_BLOCK_REWARD_AMOUNT - max_per_block_reward,
+    );
+
+    set_apr_cap_mode(&mut farm_setup, AprCapMode::UncappedWithinReserve);
+    let emission_apr = PER_BLOCK_REWARD_AMOUNT * BLOCKS_IN_YEAR * MAX_PERCENT / farm_in_amount;
+    check_effective_apr_info(&mut farm_setup, emission_apr, 0);
+
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 10);
+
+    // the whole emission is distributed, as it fits in the reward capacity
+    let expected_reward_token_out = 10 * PER_BLOCK_REWARD_AMOUNT;
+    let expected_farming_token_balance =
+        rust_biguint!(USER_TOTAL_RIDE_TOKENS - farm_in_amount + expected_reward_token_out);
+    let expected_reward_per_share = 500_000_000;
+    claim_rewards(
+        &mut farm_setup,
+        farm_in_amount,
+        expected_farm_token_nonce,
+        expected_reward_token_out,
+        &expected_farming_token_balance,
+        &expected_farming_token_balance,
+        expected_farm_token_nonce + 1,
+        expected_reward_per_share,
+        10,
+    );
+}
+
+#[test]
+fn test_apr_bounds() {
+    let mut farm_setup = setup_farm(farm_staking::contract_obj);
+    let rust_zero = rust_biguint!(0u64);
+
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_utilization_curve(managed_biguint!(MAX_APR / 2), managed_biguint!(1_000));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_max_apr(managed_biguint!(MAX_APR / 2 - 1));
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Max APR cannot be below min APR");
+
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &farm_setup.owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_zero,
+            |sc| {
+                sc.set_utilization_curve(managed_biguint!(MAX_APR + 1), managed_biguint!(1_000));
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Min APR cannot exceed max APR");
+}
+
 fn steps_enter_farm_twice<FarmObjBuilder>(farm_builder: FarmObjBuilder) -> FarmSetup<FarmObjBuilder>
 where
     FarmObjBuilder: 'static + Copy + Fn() -> farm_staking::ContractObj<DebugApi>,
@@ -683,3 +947,171 @@ fn test_unbond() {
         USER_TOTAL_RIDE_TOKENS + expected_rewards,
     );
 }
+
+#[test]
+fn test_unbond_farm_early() {
+    let mut farm_setup = setup_farm(farm_staking::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    stake_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+    );
+
+    let current_epoch = 5;
+    set_block_epoch(&mut farm_setup, current_epoch);
+    set_block_nonce(&mut farm_setup, 10);
+
+    let expected_rewards = 40;
+    let expected_ride_token_balance =
+        rust_biguint!(USER_TOTAL_RIDE_TOKENS) - farm_in_amount + expected_rewards;
+    unstake_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        expected_farm_token_nonce,
+        expected_rewards,
+        &expected_ride_token_balance,
+        &expected_ride_token_balance,
+        expected_farm_token_nonce + 1,
+        farm_in_amount,
+        &UnbondSftAttributes {
+            unlock_epoch: current_epoch + MIN_UNBOND_EPOCHS,
+        },
+    );
+
+    let fee_percent = 1_000; // 10%
+    enable_instant_unbond(&mut farm_setup, fee_percent);
+
+    // 3 of the 5 unbond epochs are left, so 3/5 of the fee is applied
+    set_block_epoch(&mut farm_setup, current_epoch + 2);
+    let expected_fee = farm_in_amount * fee_percent / MAX_PERCENT * 3 / MIN_UNBOND_EPOCHS;
+    let expected_farming_token_out = farm_in_amount - expected_fee;
+    unbond_farm_early(
+        &mut farm_setup,
+        expected_farm_token_nonce + 1,
+        farm_in_amount,
+        expected_farming_token_out,
+        &(expected_ride_token_balance + expected_farming_token_out),
+    );
+
+    farm_setup
+        .blockchain_wrapper
+        .execute_query(&farm_setup.farm_wrapper, |sc| {
+            assert_eq!(
+                sc.reward_capacity().get(),
+                managed_biguint!(TOTAL_REWARDS_AMOUNT + expected_fee)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_unbond_multiple() {
+    let mut farm_setup = setup_farm(farm_staking::contract_obj);
+
+    let farm_in_amount = 100_000_000;
+    let expected_farm_token_nonce = 1;
+    stake_farm(
+        &mut farm_setup,
+        farm_in_amount,
+        &[],
+        expected_farm_token_nonce,
+        0,
+        0,
+        0,
+    );
+
+    let first_unstake_amount = 40_000_000;
+    let first_unlock_epoch = 5 + MIN_UNBOND_EPOCHS;
+    set_block_epoch(&mut farm_setup, 5);
+    set_block_nonce(&mut farm_setup, 10);
+
+    // 40_000_000 * MAX_APR / MAX_PERCENT / BLOCKS_IN_YEAR * 10
+    let first_rewards = 10;
+    let mut expected_ride_token_balance =
+        rust_biguint!(USER_TOTAL_RIDE_TOKENS) - farm_in_amount + first_rewards;
+    unstake_farm(
+        &mut farm_setup,
+        first_unstake_amount,
+        expected_farm_token_nonce,
+        first_rewards,
+        &expected_ride_token_balance,
+        &expected_ride_token_balance,
+        expected_farm_token_nonce + 1,
+        first_unstake_amount,
+        &UnbondSftAttributes {
+            unlock_epoch: first_unlock_epoch,
+        },
+    );
+
+    let second_unstake_amount = farm_in_amount - first_unstake_amount;
+    let second_unlock_epoch = 7 + MIN_UNBOND_EPOCHS;
+    set_block_epoch(&mut farm_setup, 7);
+    set_block_nonce(&mut farm_setup, 20);
+
+    // 60_000_000 * MAX_APR / MAX_PERCENT / BLOCKS_IN_YEAR * 20
+    let second_rewards = 40;
+    expected_ride_token_balance += second_rewards;
+    unstake_farm(
+        &mut farm_setup,
+        second_unstake_amount,
+        expected_farm_token_nonce,
+        second_rewards,
+        &expected_ride_token_balance,
+        &expected_ride_token_balance,
+        expected_farm_token_nonce + 2,
+        second_unstake_amount,
+        &UnbondSftAttributes {
+            unlock_epoch: second_unlock_epoch,
+        },
+    );
+    check_unbond_schedule(
+        &mut farm_setup,
+        &[
+            (first_unlock_epoch, first_unstake_amount),
+            (second_unlock_epoch, second_unstake_amount),
+        ],
+    );
+
+    // only the first unbond token is matured, the second one is sent back
+    set_block_epoch(&mut farm_setup, first_unlock_epoch);
+    let unbond_tokens = [
+        TxInputESDT {
+            token_identifier: FARM_TOKEN_ID.to_vec(),
+            nonce: expected_farm_token_nonce + 1,
+            value: rust_biguint!(first_unstake_amount),
+        },
+        TxInputESDT {
+            token_identifier: FARM_TOKEN_ID.to_vec(),
+            nonce: expected_farm_token_nonce + 2,
+            value: rust_biguint!(second_unstake_amount),
+        },
+    ];
+    expected_ride_token_balance += first_unstake_amount;
+    unbond_farm_multiple(
+        &mut farm_setup,
+        &unbond_tokens,
+        first_unstake_amount,
+        &expected_ride_token_balance,
+    );
+
+    farm_setup.blockchain_wrapper.check_nft_balance(
+        &farm_setup.user_address,
+        FARM_TOKEN_ID,
+        expected_farm_token_nonce + 2,
+        &rust_biguint!(second_unstake_amount),
+        &UnbondSftAttributes {
+            unlock_epoch: second_unlock_epoch,
+        },
+    );
+    check_unbond_schedule(
+        &mut farm_setup,
+        &[(second_unlock_epoch, second_unstake_amount)],
+    );
+}
@@ -19,6 +19,7 @@ use config::*;
 use factory::locked_asset::LockedAssetModule;
 use factory::*;
 use farm_with_lock::custom_rewards::CustomRewardsModule;
+use farm_with_lock::lock_options::LockOptionsModule;
 use farm_with_lock::*;
 use rewards::*;
 
@@ -1072,3 +1073,160 @@ fn test_lock_rv_earn_twice() {
 
     let _ = TxContextStack::static_pop();
 }
+
+#[test]
+fn test_lock_option_with_reward_multiplier() {
+    let _ = DebugApi::dummy();
+
+    let per_block_reward_amount = rust_biguint!(100);
+    let mut farm_setup = setup_farm(
+        farm_with_lock::contract_obj,
+        factory::contract_obj,
+        per_block_reward_amount,
+    );
+    let alice = new_address_with_lp_tokens(&mut farm_setup, rust_biguint!(5_000));
+
+    let owner_address = farm_setup.owner_address.clone();
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let milestones =
+                    ManagedMultiResultVec::from(ManagedVec::from(vec![UnlockMilestone {
+                        unlock_epoch: 60,
+                        unlock_percent: 100,
+                    }]));
+                let lock_option_id = sc.add_lock_option(20_000, milestones);
+                assert_eq!(lock_option_id, 1);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(&alice, &farm_setup.farm_wrapper, &rust_biguint!(0), |sc| {
+            sc.select_lock_option(1);
+            assert_eq!(sc.get_user_lock_option_id(managed_address!(&alice)), 1);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    step(
+        &mut farm_setup,
+        3,
+        Action::EnterFarm(alice.clone(), rust_biguint!(1_000)),
+        Expected::new(rust_biguint!(400), rust_biguint!(0), rust_biguint!(1_000)),
+    );
+
+    // 700 rewards are doubled by the lock option multiplier
+    step(
+        &mut farm_setup,
+        10,
+        Action::ExitFarm(
+            alice,
+            1,
+            rust_biguint!(1_000),
+            rust_biguint!(1_400),
+            LockedAssetTokenAttributesEx {
+                unlock_schedule: UnlockScheduleEx {
+                    unlock_milestones: ManagedVec::from(vec![UnlockMilestoneEx {
+                        unlock_epoch: 60,
+                        unlock_percent: 100_000,
+                    }]),
+                },
+                is_merged: false,
+            },
+        ),
+        Expected::new(
+            rust_biguint!(400),
+            rust_biguint!(700_000_000_000),
+            rust_biguint!(0),
+        ),
+    );
+
+    let _ = TxContextStack::static_pop();
+}
+
+#[test]
+fn test_lock_option_locks_from_claim_epoch() {
+    let _ = DebugApi::dummy();
+
+    let per_block_reward_amount = rust_biguint!(100);
+    let mut farm_setup = setup_farm(
+        farm_with_lock::contract_obj,
+        factory::contract_obj,
+        per_block_reward_amount,
+    );
+    let alice = new_address_with_lp_tokens(&mut farm_setup, rust_biguint!(5_000));
+
+    let owner_address = farm_setup.owner_address.clone();
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner_address,
+            &farm_setup.farm_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let milestones =
+                    ManagedMultiResultVec::from(ManagedVec::from(vec![UnlockMilestone {
+                        unlock_epoch: 60,
+                        unlock_percent: 100,
+                    }]));
+                sc.add_lock_option(20_000, milestones);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    step(
+        &mut farm_setup,
+        3,
+        Action::EnterFarm(alice.clone(), rust_biguint!(1_000)),
+        Expected::new(rust_biguint!(400), rust_biguint!(0), rust_biguint!(1_000)),
+    );
+
+    // the option is selected long after entering, so the rewards are locked from now on
+    farm_setup.blockchain_wrapper.set_block_epoch(45);
+    farm_setup
+        .blockchain_wrapper
+        .execute_tx(&alice, &farm_setup.farm_wrapper, &rust_biguint!(0), |sc| {
+            sc.select_lock_option(1);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    step(
+        &mut farm_setup,
+        10,
+        Action::ExitFarm(
+            alice,
+            1,
+            rust_biguint!(1_000),
+            rust_biguint!(1_400),
+            LockedAssetTokenAttributesEx {
+                unlock_schedule: UnlockScheduleEx {
+                    unlock_milestones: ManagedVec::from(vec![UnlockMilestoneEx {
+                        unlock_epoch: 90,
+                        unlock_percent: 100_000,
+                    }]),
+                },
+                is_merged: false,
+            },
+        ),
+        Expected::new(
+            rust_biguint!(400),
+            rust_biguint!(700_000_000_000),
+            rust_biguint!(0),
+        ),
+    );
+
+    let _ = TxContextStack::static_pop();
+}
@@ -0,0 +1,254 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_structs::UnlockPeriod;
+
+use crate::global_op;
+
+pub type CampaignId = u64;
+
+pub mod factory_asset_proxy {
+    elrond_wasm::imports!();
+
+    #[elrond_wasm::proxy]
+    pub trait LockedAssetFactory {
+        #[view(getAssetTokenId)]
+        fn get_asset_token_id(&self) -> TokenIdentifier;
+    }
+}
+
+/// A distribution of its own token, next to the community distributions of the asset token.
+/// With a factory address, the allocations are locked through that factory, which needs
+/// to whitelist this contract and whose asset must be `token_id`. Without one, the allocations
+/// are sent as is from the amount funded through `fundCampaign`.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct Campaign<M: ManagedTypeApi> {
+    pub token_id: TokenIdentifier<M>,
+    pub locked_asset_factory_address: Option<ManagedAddress<M>>,
+    pub unlock_period: UnlockPeriod<M>,
+    pub budget: BigUint<M>,
+    pub allocated_amount: BigUint<M>,
+    pub funded_amount: BigUint<M>,
+    pub claimed_amount: BigUint<M>,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+}
+
+#[elrond_wasm::module]
+pub trait CampaignModule: global_op::GlobalOperationModule {
+    #[proxy]
+    fn campaign_factory_proxy(&self, to: ManagedAddress) -> factory::Proxy<Self::Api>;
+
+    #[proxy]
+    fn factory_asset_proxy(&self, to: ManagedAddress) -> factory_asset_proxy::Proxy<Self::Api>;
+
+    fn create_campaign(&self, campaign: Campaign<Self::Api>) -> CampaignId {
+        require!(campaign.token_id.is_esdt(), "Invalid token ID");
+        require!(campaign.budget > 0, "Zero amount");
+        require!(
+            campaign.start_epoch < campaign.end_epoch,
+            "Invalid campaign period"
+        );
+        require!(
+            campaign.end_epoch > self.blockchain().get_block_epoch(),
+            "Campaign end epoch in the past"
+        );
+
+        if let Some(factory_address) = &campaign.locked_asset_factory_address {
+            let factory_asset_token_id: TokenIdentifier = self
+                .factory_asset_proxy(factory_address.clone())
+                .get_asset_token_id()
+                .execute_on_dest_context();
+            require!(
+                campaign.token_id == factory_asset_token_id,
+                "Token is not the factory asset"
+            );
+        }
+
+        let campaign_id = self.last_campaign_id().get() + 1;
+        self.last_campaign_id().set(&campaign_id);
+        self.campaigns().insert(campaign_id, campaign);
+
+        campaign_id
+    }
+
+    #[only_owner]
+    #[endpoint(setCampaignUserAllocations)]
+    fn set_campaign_user_allocations(
+        &self,
+        campaign_id: CampaignId,
+        #[var_args] user_allocations: ManagedVarArgs<MultiArg2<ManagedAddress, BigUint>>,
+    ) {
+        require!(!user_allocations.is_empty(), "Empty assets vec");
+        let mut campaign = self.get_existing_campaign(campaign_id);
+        require!(
+            self.blockchain().get_block_epoch() < campaign.end_epoch,
+            "Campaign ended"
+        );
+
+        for user_allocation in user_allocations.into_iter() {
+            let (address, amount) = user_allocation.into_tuple();
+            require!(amount > 0, "Zero amount");
+
+            let allocation_mapper = self.campaign_user_allocation(campaign_id, &address);
+            require!(allocation_mapper.is_empty(), "Vector has duplicates");
+
+            campaign.allocated_amount += &amount;
+            allocation_mapper.set(&amount);
+        }
+        require!(
+            campaign.allocated_amount <= campaign.budget,
+            "User assets sums above campaign budget"
+        );
+
+        self.campaigns().insert(campaign_id, campaign);
+    }
+
+    /// Funds a campaign paying out its token as is. Anyone can fund it, up to its budget.
+    #[payable("*")]
+    #[endpoint(fundCampaign)]
+    fn fund_campaign(
+        &self,
+        campaign_id: CampaignId,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] token_nonce: u64,
+        #[payment_amount] amount: BigUint,
+    ) {
+        let mut campaign = self.get_existing_campaign(campaign_id);
+        require!(
+            campaign.locked_asset_factory_address.is_none(),
+            "Campaign is locked through a factory"
+        );
+        require!(
+            token_id == campaign.token_id && token_nonce == 0,
+            "Bad payment token"
+        );
+
+        campaign.funded_amount += &amount;
+        require!(
+            campaign.funded_amount <= campaign.budget,
+            "Funding above campaign budget"
+        );
+
+        self.campaigns().insert(campaign_id, campaign);
+    }
+
+    #[endpoint(claimCampaign)]
+    fn claim_campaign(&self, campaign_id: CampaignId) -> EsdtTokenPayment<Self::Api> {
+        self.require_global_op_not_ongoing();
+        let mut campaign = self.get_existing_campaign(campaign_id);
+        let current_epoch = self.blockchain().get_block_epoch();
+        require!(
+            campaign.start_epoch <= current_epoch && current_epoch < campaign.end_epoch,
+            "Campaign not active"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let allocation_mapper = self.campaign_user_allocation(campaign_id, &caller);
+        require!(!allocation_mapper.is_empty(), "Nothing to claim");
+        let amount = allocation_mapper.get();
+        allocation_mapper.clear();
+
+        campaign.claimed_amount += &amount;
+        let payment = match &campaign.locked_asset_factory_address {
+            Some(factory_address) => self
+                .campaign_factory_proxy(factory_address.clone())
+                .create_and_forward_custom_period(
+                    amount,
+                    caller,
+                    campaign.start_epoch,
+                    campaign.unlock_period.clone(),
+                )
+                .execute_on_dest_context_custom_range(|_, after| (after - 1, after)),
+            None => {
+                require!(
+                    campaign.claimed_amount <= campaign.funded_amount,
+                    "Campaign not funded"
+                );
+                self.send()
+                    .direct(&caller, &campaign.token_id, 0, &amount, &[]);
+                EsdtTokenPayment::new(campaign.token_id.clone(), 0, amount)
+            }
+        };
+
+        self.campaigns().insert(campaign_id, campaign);
+        payment
+    }
+
+    /// Sends back to the owner the part of the funds of an ended campaign that was not claimed.
+    #[only_owner]
+    #[endpoint(withdrawCampaignFunds)]
+    fn withdraw_campaign_funds(&self, campaign_id: CampaignId) -> EsdtTokenPayment<Self::Api> {
+        let mut campaign = self.get_existing_campaign(campaign_id);
+        require!(
+            campaign.locked_asset_factory_address.is_none(),
+            "Campaign is locked through a factory"
+        );
+        require!(
+            self.blockchain().get_block_epoch() >= campaign.end_epoch,
+            "Campaign not ended"
+        );
+
+        let amount = &campaign.funded_amount - &campaign.claimed_amount;
+        require!(amount > 0, "Nothing to withdraw");
+        campaign.funded_amount = campaign.claimed_amount.clone();
+
+        let caller = self.blockchain().get_caller();
+        self.send()
+            .direct(&caller, &campaign.token_id, 0, &amount, &[]);
+
+        let payment = EsdtTokenPayment::new(campaign.token_id.clone(), 0, amount);
+        self.campaigns().insert(campaign_id, campaign);
+        payment
+    }
+
+    fn get_existing_campaign(&self, campaign_id: CampaignId) -> Campaign<Self::Api> {
+        let opt_campaign = self.campaigns().get(&campaign_id);
+        require!(opt_campaign.is_some(), "Unknown campaign");
+        opt_campaign.unwrap()
+    }
+
+    #[view(getCampaign)]
+    fn get_campaign(&self, campaign_id: CampaignId) -> Campaign<Self::Api> {
+        self.get_existing_campaign(campaign_id)
+    }
+
+    #[view(getCampaignIds)]
+    fn get_campaign_ids(&self) -> ManagedMultiResultVec<CampaignId> {
+        let mut result = ManagedMultiResultVec::new();
+        for campaign_id in self.campaigns().keys() {
+            result.push(campaign_id);
+        }
+        result
+    }
+
+    /// Returns the amount the address can claim from the campaign, zero while it's not active.
+    #[view(getCampaignClaimableAmount)]
+    fn get_campaign_claimable_amount(
+        &self,
+        campaign_id: CampaignId,
+        address: ManagedAddress,
+    ) -> BigUint {
+        let campaign = self.get_existing_campaign(campaign_id);
+        let current_epoch = self.blockchain().get_block_epoch();
+        if current_epoch < campaign.start_epoch || current_epoch >= campaign.end_epoch {
+            return BigUint::zero();
+        }
+
+        self.campaign_user_allocation(campaign_id, &address).get()
+    }
+
+    #[storage_mapper("last_campaign_id")]
+    fn last_campaign_id(&self) -> SingleValueMapper<CampaignId>;
+
+    #[storage_mapper("campaigns")]
+    fn campaigns(&self) -> MapMapper<CampaignId, Campaign<Self::Api>>;
+
+    #[view(getCampaignUserAllocation)]
+    #[storage_mapper("campaign_user_allocation")]
+    fn campaign_user_allocation(
+        &self,
+        campaign_id: CampaignId,
+        address: &ManagedAddress,
+    ) -> SingleValueMapper<BigUint>;
+}
@@ -0,0 +1,22 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+#[elrond_wasm::module]
+pub trait EventsModule {
+    #[event("expired_distribution_swept")]
+    fn expired_distribution_swept_event(
+        &self,
+        #[indexed] spread_epoch: u64,
+        #[indexed] treasury: &ManagedAddress,
+        unclaimed_amount: &BigUint,
+    );
+
+    #[event("expired_distribution_rolled_over")]
+    fn expired_distribution_rolled_over_event(
+        &self,
+        #[indexed] spread_epoch: u64,
+        #[indexed] target_spread_epoch: u64,
+        unclaimed_amount: &BigUint,
+        target_after_planning_amount: &BigUint,
+    );
+}
@@ -7,7 +7,13 @@ use common_structs::{UnlockMilestone, UnlockPeriod};
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
-mod global_op;
+pub mod campaign;
+mod events;
+pub mod global_op;
+pub mod merkle_claim;
+
+use campaign::{Campaign, CampaignId};
+use merkle_claim::{MerkleHash, MerkleLeaf};
 
 const GAS_THRESHOLD: u64 = 100_000;
 const MAX_CLAIMABLE_DISTRIBUTION_ROUNDS: usize = 4;
@@ -32,7 +38,12 @@ pub struct CommunityDistribution<M: ManagedTypeApi> {
 }
 
 #[elrond_wasm::contract]
-pub trait Distribution: global_op::GlobalOperationModule {
+pub trait Distribution:
+    global_op::GlobalOperationModule
+    + merkle_claim::MerkleClaimModule
+    + campaign::CampaignModule
+    + events::EventsModule
+{
     #[proxy]
     fn locked_asset_factory_proxy(&self, to: ManagedAddress) -> factory::Proxy<Self::Api>;
 
@@ -72,6 +83,7 @@ pub trait Distribution: global_op::GlobalOperationModule {
             after_planning_amount: total_amount,
         };
         self.community_distribution_list().push_front(distrib);
+        self.distribution_claims_tracked(spread_epoch).set(&true);
     }
 
     #[only_owner]
@@ -88,6 +100,117 @@ pub trait Distribution: global_op::GlobalOperationModule {
         self.add_all_user_assets_to_map(spread_epoch, user_locked_assets)
     }
 
+    /// Commits the Merkle root of the user allocations for the last community distribution,
+    /// instead of pushing each user. `total_amount` is the sum of the allocations in the tree.
+    #[only_owner]
+    #[endpoint(setMerkleRoot)]
+    fn set_merkle_root(
+        &self,
+        spread_epoch: u64,
+        root: MerkleHash<Self::Api>,
+        total_amount: BigUint,
+    ) {
+        self.require_global_op_ongoing();
+        self.require_community_distribution_list_not_empty();
+        require!(total_amount > 0, "Zero amount");
+        require!(
+            self.merkle_root(spread_epoch).is_empty(),
+            "Merkle root already set"
+        );
+
+        let mut last_community_distrib = self
+            .community_distribution_list()
+            .front()
+            .unwrap()
+            .get_value_cloned();
+        require!(
+            spread_epoch == last_community_distrib.spread_epoch,
+            "Bad spread epoch"
+        );
+        require!(
+            last_community_distrib.after_planning_amount >= total_amount,
+            "User assets sums above community total assets"
+        );
+        last_community_distrib.after_planning_amount -= &total_amount;
+
+        self.community_distribution_list().pop_front();
+        self.community_distribution_list()
+            .push_front(last_community_distrib);
+
+        self.set_merkle_root_for_distribution(spread_epoch, &root, &total_amount);
+    }
+
+    /// Claims the allocation of the caller from the Merkle tree of the given distribution.
+    /// Like the pushed allocations, only the last few distributions can be claimed.
+    #[endpoint(claimLockedAssetsWithProof)]
+    fn claim_locked_assets_with_proof(
+        &self,
+        spread_epoch: u64,
+        index: u64,
+        amount: BigUint,
+        #[var_args] proof: ManagedVarArgs<MerkleHash<Self::Api>>,
+    ) -> BigUint {
+        self.require_global_op_not_ongoing();
+        self.require_unlock_period_not_empty();
+        require!(
+            spread_epoch <= self.blockchain().get_block_epoch(),
+            "Distribution not started"
+        );
+        require!(
+            self.is_community_distribution_claimable(spread_epoch),
+            "Distribution not claimable"
+        );
+        require!(
+            !self.is_claim_deadline_passed(spread_epoch, self.blockchain().get_block_epoch()),
+            "Claim deadline passed"
+        );
+        require!(
+            !self.merkle_root(spread_epoch).is_empty(),
+            "No Merkle root for spread epoch"
+        );
+        require!(
+            !self.is_merkle_leaf_claimed(spread_epoch, index),
+            "Already claimed"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let leaf = MerkleLeaf {
+            index,
+            address: caller.clone(),
+            amount: amount.clone(),
+        };
+        require!(
+            self.verify_merkle_proof(
+                &self.merkle_root(spread_epoch).get(),
+                &leaf,
+                &proof.to_vec()
+            ),
+            "Invalid proof"
+        );
+
+        let claimed_amount = self.get_merkle_claimed_amount(spread_epoch) + &amount;
+        require!(
+            claimed_amount <= self.merkle_total_amount(spread_epoch).get(),
+            "Claims above Merkle total amount"
+        );
+        self.add_merkle_claimed_amount(spread_epoch, &amount);
+        self.set_merkle_leaf_claimed(spread_epoch, index);
+        self.distribution_claimed_amount(spread_epoch)
+            .update(|x| *x += &amount);
+
+        let to = self.locked_asset_factory_address().get();
+        self.locked_asset_factory_proxy(to)
+            .create_and_forward_custom_period(
+                amount.clone(),
+                caller,
+                spread_epoch,
+                self.unlock_period().get(),
+            )
+            .execute_on_dest_context_ignore_result();
+
+        amount
+    }
+
     #[endpoint(claimLockedAssets)]
     fn claim_locked_assets(&self) -> BigUint {
         self.require_global_op_not_ongoing();
@@ -119,12 +242,148 @@ pub trait Distribution: global_op::GlobalOperationModule {
                 )
                 .with_gas_limit(gas_limit_per_execute)
                 .execute_on_dest_context_ignore_result();
+            self.distribution_claimed_amount(spread_epoch)
+                .update(|x| *x += &amount);
             cummulated_amount += amount;
         }
 
         cummulated_amount
     }
 
+    /// After the deadline, the allocations of the distribution can't be claimed anymore
+    /// and its unclaimed amount can be swept or rolled over. Only the distributions whose
+    /// claims are tracked, those set after the deadlines were introduced, can expire.
+    #[only_owner]
+    #[endpoint(setClaimDeadline)]
+    fn set_claim_deadline(&self, spread_epoch: u64, deadline_epoch: u64) {
+        require!(
+            self.get_community_distribution(spread_epoch).is_some(),
+            "Unknown community distribution"
+        );
+        self.require_distribution_claims_tracked(spread_epoch);
+        require!(
+            !self.distribution_expired(spread_epoch).get(),
+            "Distribution already expired"
+        );
+        require!(
+            deadline_epoch >= spread_epoch,
+            "Deadline before spread epoch"
+        );
+
+        self.claim_deadline(spread_epoch).set(&deadline_epoch);
+    }
+
+    #[only_owner]
+    #[endpoint(setUnclaimedTreasury)]
+    fn set_unclaimed_treasury(&self, treasury: ManagedAddress) {
+        self.unclaimed_treasury().set(&treasury);
+    }
+
+    /// Sends the unclaimed amount of an expired distribution to the treasury,
+    /// as locked assets following the unlock period from the spread epoch.
+    #[only_owner]
+    #[endpoint(sweepExpiredDistribution)]
+    fn sweep_expired_distribution(&self, spread_epoch: u64) -> BigUint {
+        self.require_unlock_period_not_empty();
+        require!(
+            !self.unclaimed_treasury().is_empty(),
+            "No treasury for unclaimed assets"
+        );
+
+        let unclaimed_amount = self.expire_community_distribution(spread_epoch);
+        let treasury = self.unclaimed_treasury().get();
+        if unclaimed_amount > 0 {
+            let to = self.locked_asset_factory_address().get();
+            self.locked_asset_factory_proxy(to)
+                .create_and_forward_custom_period(
+                    unclaimed_amount.clone(),
+                    treasury.clone(),
+                    spread_epoch,
+                    self.unlock_period().get(),
+                )
+                .execute_on_dest_context_ignore_result();
+        }
+
+        self.expired_distribution_swept_event(spread_epoch, &treasury, &unclaimed_amount);
+        unclaimed_amount
+    }
+
+    /// Adds the unclaimed amount of an expired distribution to the last community
+    /// distribution, which is still being planned, so it can be allocated again.
+    #[only_owner]
+    #[endpoint(rollOverExpiredDistribution)]
+    fn roll_over_expired_distribution(&self, spread_epoch: u64) -> BigUint {
+        self.require_global_op_ongoing();
+        self.require_community_distribution_list_not_empty();
+
+        let mut last_community_distrib = self
+            .community_distribution_list()
+            .front()
+            .unwrap()
+            .get_value_cloned();
+        require!(
+            last_community_distrib.spread_epoch > spread_epoch,
+            "No later community distribution"
+        );
+
+        let unclaimed_amount = self.expire_community_distribution(spread_epoch);
+        last_community_distrib.total_amount += &unclaimed_amount;
+        last_community_distrib.after_planning_amount += &unclaimed_amount;
+
+        self.expired_distribution_rolled_over_event(
+            spread_epoch,
+            last_community_distrib.spread_epoch,
+            &unclaimed_amount,
+            &last_community_distrib.after_planning_amount,
+        );
+
+        self.community_distribution_list().pop_front();
+        self.community_distribution_list()
+            .push_front(last_community_distrib);
+
+        unclaimed_amount
+    }
+
+    /// Returns the amount of the distribution that was not claimed yet, including
+    /// the part that was never allocated to users.
+    #[view(getUnclaimedAmount)]
+    fn get_unclaimed_amount(&self, spread_epoch: u64) -> BigUint {
+        let opt_community_distrib = self.get_community_distribution(spread_epoch);
+        require!(
+            opt_community_distrib.is_some(),
+            "Unknown community distribution"
+        );
+        self.require_distribution_claims_tracked(spread_epoch);
+
+        opt_community_distrib.unwrap().total_amount
+            - self.distribution_claimed_amount(spread_epoch).get()
+    }
+
+    fn expire_community_distribution(&self, spread_epoch: u64) -> BigUint {
+        require!(
+            !self.distribution_expired(spread_epoch).get(),
+            "Distribution already expired"
+        );
+        require!(
+            self.is_claim_deadline_passed(spread_epoch, self.blockchain().get_block_epoch())
+                || !self.is_community_distribution_claimable(spread_epoch),
+            "Claim deadline not passed"
+        );
+
+        let unclaimed_amount = self.get_unclaimed_amount(spread_epoch);
+        self.distribution_expired(spread_epoch).set(&true);
+        self.expired_unclaimed_amount(spread_epoch)
+            .set(&unclaimed_amount);
+        unclaimed_amount
+    }
+
+    fn require_distribution_claims_tracked(&self, spread_epoch: u64) {
+        require!(
+            self.distribution_claims_tracked(spread_epoch).get(),
+            "Claims not tracked for distribution"
+        );
+    }
+
     #[endpoint(clearUnclaimableAssets)]
     fn clear_unclaimable_assets(&self) -> usize {
         let biggest_unclaimable_asset_epoch = self.get_biggest_unclaimable_asset_epoch();
@@ -136,7 +395,19 @@ pub trait Distribution: global_op::GlobalOperationModule {
     fn undo_last_community_distrib(&self) {
         self.require_global_op_ongoing();
         self.require_community_distribution_list_not_empty();
+        let spread_epoch = self
+            .community_distribution_list()
+            .front()
+            .unwrap()
+            .get_value_as_ref()
+            .spread_epoch;
         self.community_distribution_list().pop_front();
+        self.clear_merkle_root_for_distribution(spread_epoch);
+        self.claim_deadline(spread_epoch).clear();
+        self.distribution_claims_tracked(spread_epoch).clear();
+        self.distribution_claimed_amount(spread_epoch).clear();
+        self.distribution_expired(spread_epoch).clear();
+        self.expired_unclaimed_amount(spread_epoch).clear();
     }
 
     #[only_owner]
@@ -157,6 +428,66 @@ pub trait Distribution: global_op::GlobalOperationModule {
             .set(&UnlockPeriod { unlock_milestones });
     }
 
+    /// Creates a campaign distributing the given token as is, from the amount funded for it.
+    #[only_owner]
+    #[endpoint(createCampaign)]
+    fn create_plain_campaign(
+        &self,
+        token_id: TokenIdentifier,
+        budget: BigUint,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> CampaignId {
+        self.create_campaign(Campaign {
+            token_id,
+            locked_asset_factory_address: None,
+            unlock_period: UnlockPeriod {
+                unlock_milestones: ManagedVec::new(),
+            },
+            budget,
+            allocated_amount: BigUint::zero(),
+            funded_amount: BigUint::zero(),
+            claimed_amount: BigUint::zero(),
+            start_epoch,
+            end_epoch,
+        })
+    }
+
+    /// Creates a campaign whose allocations are locked through the given factory,
+    /// following the unlock period from the campaign start epoch.
+    /// The token must be the asset token of the factory.
+    #[only_owner]
+    #[endpoint(createLockedCampaign)]
+    fn create_locked_campaign(
+        &self,
+        token_id: TokenIdentifier,
+        budget: BigUint,
+        start_epoch: u64,
+        end_epoch: u64,
+        locked_asset_factory_address: ManagedAddress,
+        #[var_args] milestones: ManagedVarArgs<UnlockMilestone>,
+    ) -> CampaignId {
+        require!(
+            self.blockchain()
+                .is_smart_contract(&locked_asset_factory_address),
+            "Invalid factory address"
+        );
+        let unlock_milestones = milestones.to_vec();
+        self.validate_unlock_milestones(&unlock_milestones);
+
+        self.create_campaign(Campaign {
+            token_id,
+            locked_asset_factory_address: Some(locked_asset_factory_address),
+            unlock_period: UnlockPeriod { unlock_milestones },
+            budget,
+            allocated_amount: BigUint::zero(),
+            funded_amount: BigUint::zero(),
+            claimed_amount: BigUint::zero(),
+            start_epoch,
+            end_epoch,
+        })
+    }
+
     #[view(calculateLockedAssets)]
     fn calculate_locked_assets_view(&self, address: ManagedAddress) -> BigUint {
         self.require_global_op_not_ongoing();
@@ -253,6 +584,9 @@ pub trait Distribution: global_op::GlobalOperationModule {
             .iter()
             .take(MAX_CLAIMABLE_DISTRIBUTION_ROUNDS)
             .filter(|x| x.get_value_as_ref().spread_epoch <= current_epoch)
+            .filter(|x| {
+                !self.is_claim_deadline_passed(x.get_value_as_ref().spread_epoch, current_epoch)
+            })
         {
             let user_asset_key = UserLockedAssetKey {
                 caller: address.clone(),
@@ -313,6 +647,30 @@ pub trait Distribution: global_op::GlobalOperationModule {
         map_len_before - self.user_locked_asset_map().len()
     }
 
+    fn get_community_distribution(
+        &self,
+        spread_epoch: u64,
+    ) -> Option<CommunityDistribution<Self::Api>> {
+        self.community_distribution_list()
+            .iter()
+            .map(|community_distrib| community_distrib.get_value_cloned())
+            .find(|community_distrib| community_distrib.spread_epoch == spread_epoch)
+    }
+
+    fn is_community_distribution_claimable(&self, spread_epoch: u64) -> bool {
+        self.community_distribution_list()
+            .iter()
+            .take(MAX_CLAIMABLE_DISTRIBUTION_ROUNDS)
+            .any(|community_distrib| {
+                community_distrib.get_value_as_ref().spread_epoch == spread_epoch
+            })
+    }
+
+    fn is_claim_deadline_passed(&self, spread_epoch: u64, current_epoch: u64) -> bool {
+        let deadline_mapper = self.claim_deadline(spread_epoch);
+        !deadline_mapper.is_empty() && current_epoch > deadline_mapper.get()
+    }
+
     fn require_community_distribution_list_not_empty(&self) {
         require!(
             !self.community_distribution_list().is_empty(),
@@ -350,6 +708,30 @@ pub trait Distribution: global_op::GlobalOperationModule {
     #[storage_mapper("user_locked_asset_map")]
     fn user_locked_asset_map(&self) -> MapMapper<UserLockedAssetKey<Self::Api>, BigUint>;
 
+    #[view(getClaimDeadline)]
+    #[storage_mapper("claim_deadline")]
+    fn claim_deadline(&self, spread_epoch: u64) -> SingleValueMapper<u64>;
+
+    #[view(getDistributionClaimedAmount)]
+    #[storage_mapper("distribution_claimed_amount")]
+    fn distribution_claimed_amount(&self, spread_epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[view(areDistributionClaimsTracked)]
+    #[storage_mapper("distribution_claims_tracked")]
+    fn distribution_claims_tracked(&self, spread_epoch: u64) -> SingleValueMapper<bool>;
+
+    #[view(isDistributionExpired)]
+    #[storage_mapper("distribution_expired")]
+    fn distribution_expired(&self, spread_epoch: u64) -> SingleValueMapper<bool>;
+
+    #[view(getExpiredUnclaimedAmount)]
+    #[storage_mapper("expired_unclaimed_amount")]
+    fn expired_unclaimed_amount(&self, spread_epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[view(getUnclaimedTreasury)]
+    #[storage_mapper("unclaimed_treasury")]
+    fn unclaimed_treasury(&self) -> SingleValueMapper<ManagedAddress>;
+
     #[storage_mapper("locked_asset_factory_address")]
     fn locked_asset_factory_address(&self) -> SingleValueMapper<ManagedAddress>;
 
@@ -0,0 +1,131 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+pub const HASH_LENGTH: usize = 32;
+const BITMAP_WORD_BITS: u64 = 64;
+
+pub type MerkleHash<M> = ManagedByteArray<M, HASH_LENGTH>;
+
+#[derive(TopEncode, NestedEncode)]
+pub struct MerkleLeaf<M: ManagedTypeApi> {
+    pub index: u64,
+    pub address: ManagedAddress<M>,
+    pub amount: BigUint<M>,
+}
+
+/// Leaves are the double keccak256 of the top encoded `MerkleLeaf`, so they can't be
+/// mistaken for inner nodes. Each inner node hashes its left child followed by its right one,
+/// the bits of the leaf index telling on which side the proof nodes go, from the bottom up.
+///
+/// The claims are tracked per root id rather than per spread epoch,
+/// so a root set again after an undone distribution starts with no claims.
+#[elrond_wasm::module]
+pub trait MerkleClaimModule {
+    fn verify_merkle_proof(
+        &self,
+        root: &MerkleHash<Self::Api>,
+        leaf: &MerkleLeaf<Self::Api>,
+        proof: &ManagedVec<MerkleHash<Self::Api>>,
+    ) -> bool {
+        let mut node = self.merkle_leaf_hash(leaf);
+
+        let mut path = leaf.index;
+        for sibling in proof.iter() {
+            node = if path % 2 == 0 {
+                self.merkle_node_hash(&node, &sibling)
+            } else {
+                self.merkle_node_hash(&sibling, &node)
+            };
+            path /= 2;
+        }
+
+        require!(path == 0, "Leaf index out of tree");
+        &node == root
+    }
+
+    fn merkle_leaf_hash(&self, leaf: &MerkleLeaf<Self::Api>) -> MerkleHash<Self::Api> {
+        let encoded_leaf = self.serializer().top_encode_to_managed_buffer(leaf);
+        let leaf_hash = self.crypto().keccak256(&encoded_leaf);
+        self.crypto().keccak256(leaf_hash.as_managed_buffer())
+    }
+
+    fn merkle_node_hash(
+        &self,
+        left: &MerkleHash<Self::Api>,
+        right: &MerkleHash<Self::Api>,
+    ) -> MerkleHash<Self::Api> {
+        let mut concatenated = ManagedBuffer::new();
+        concatenated.append(left.as_managed_buffer());
+        concatenated.append(right.as_managed_buffer());
+        self.crypto().keccak256(&concatenated)
+    }
+
+    fn set_merkle_root_for_distribution(
+        &self,
+        spread_epoch: u64,
+        root: &MerkleHash<Self::Api>,
+        total_amount: &BigUint,
+    ) {
+        let root_id = self.last_merkle_root_id().get() + 1;
+        self.last_merkle_root_id().set(&root_id);
+
+        self.merkle_root_id(spread_epoch).set(&root_id);
+        self.merkle_root(spread_epoch).set(root);
+        self.merkle_total_amount(spread_epoch).set(total_amount);
+    }
+
+    fn clear_merkle_root_for_distribution(&self, spread_epoch: u64) {
+        let root_id = self.merkle_root_id(spread_epoch).get();
+        self.merkle_claimed_amount(root_id).clear();
+
+        self.merkle_root_id(spread_epoch).clear();
+        self.merkle_root(spread_epoch).clear();
+        self.merkle_total_amount(spread_epoch).clear();
+    }
+
+    #[view(isMerkleLeafClaimed)]
+    fn is_merkle_leaf_claimed(&self, spread_epoch: u64, index: u64) -> bool {
+        let root_id = self.merkle_root_id(spread_epoch).get();
+        let word = self
+            .merkle_claimed_bitmap(root_id, index / BITMAP_WORD_BITS)
+            .get();
+        word & (1u64 << (index % BITMAP_WORD_BITS)) != 0
+    }
+
+    fn set_merkle_leaf_claimed(&self, spread_epoch: u64, index: u64) {
+        let root_id = self.merkle_root_id(spread_epoch).get();
+        self.merkle_claimed_bitmap(root_id, index / BITMAP_WORD_BITS)
+            .update(|word| *word |= 1u64 << (index % BITMAP_WORD_BITS));
+    }
+
+    #[view(getMerkleClaimedAmount)]
+    fn get_merkle_claimed_amount(&self, spread_epoch: u64) -> BigUint {
+        let root_id = self.merkle_root_id(spread_epoch).get();
+        self.merkle_claimed_amount(root_id).get()
+    }
+
+    fn add_merkle_claimed_amount(&self, spread_epoch: u64, amount: &BigUint) {
+        let root_id = self.merkle_root_id(spread_epoch).get();
+        self.merkle_claimed_amount(root_id).update(|x| *x += amount);
+    }
+
+    #[view(getMerkleRoot)]
+    #[storage_mapper("merkle_root")]
+    fn merkle_root(&self, spread_epoch: u64) -> SingleValueMapper<MerkleHash<Self::Api>>;
+
+    #[view(getMerkleTotalAmount)]
+    #[storage_mapper("merkle_total_amount")]
+    fn merkle_total_amount(&self, spread_epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("last_merkle_root_id")]
+    fn last_merkle_root_id(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("merkle_root_id")]
+    fn merkle_root_id(&self, spread_epoch: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("merkle_claimed_amount")]
+    fn merkle_claimed_amount(&self, root_id: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("merkle_claimed_bitmap")]
+    fn merkle_claimed_bitmap(&self, root_id: u64, word_index: u64) -> SingleValueMapper<u64>;
+}
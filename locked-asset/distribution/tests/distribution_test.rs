@@ -0,0 +1,987 @@
+use common_structs::UnlockMilestone;
+use elrond_wasm::types::{Address, EsdtLocalRole, ManagedMultiResultVec, ManagedVec, MultiArg2};
+use elrond_wasm_debug::tx_mock::TxResult;
+use elrond_wasm_debug::{
+    managed_address, managed_biguint, managed_token_id, rust_biguint, testing_framework::*,
+    DebugApi,
+};
+
+use distribution::campaign::CampaignModule;
+use distribution::global_op::GlobalOperationModule;
+use distribution::merkle_claim::{MerkleClaimModule, MerkleHash, MerkleLeaf};
+use distribution::Distribution;
+use factory::locked_asset::LockedAssetModule;
+use factory::LockedAssetFactory;
+
+const DISTRIBUTION_WASM_PATH: &'static str = "output/distribution.wasm";
+const FACTORY_WASM_PATH: &'static str = "../factory/output/factory.wasm";
+
+const MEX_TOKEN_ID: &[u8] = b"MEX-abcdef";
+const USDC_TOKEN_ID: &[u8] = b"USDC-abcdef";
+const LOCKED_MEX_TOKEN_ID: &[u8] = b"LKMEX-abcdef";
+
+const FIRST_SPREAD_EPOCH: u64 = 10;
+const SECOND_SPREAD_EPOCH: u64 = 30;
+const DISTRIBUTION_AMOUNT: u64 = 1_000;
+const FIRST_USER_AMOUNT: u64 = 300;
+const SECOND_USER_AMOUNT: u64 = 200;
+const CLAIM_DEADLINE: u64 = 20;
+
+const MERKLE_THIRD_LEAF_AMOUNT: u64 = 100;
+const MERKLE_FOURTH_LEAF_AMOUNT: u64 = 50;
+const MERKLE_TOTAL_AMOUNT: u64 =
+    FIRST_USER_AMOUNT + SECOND_USER_AMOUNT + MERKLE_THIRD_LEAF_AMOUNT + MERKLE_FOURTH_LEAF_AMOUNT;
+
+const CAMPAIGN_BUDGET: u64 = 1_000;
+const CAMPAIGN_START_EPOCH: u64 = 5;
+const CAMPAIGN_END_EPOCH: u64 = 15;
+
+struct DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>
+where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    pub blockchain_wrapper: BlockchainStateWrapper,
+    pub owner_address: Address,
+    pub first_user: Address,
+    pub second_user: Address,
+    pub treasury: Address,
+    pub factory_wrapper: ContractObjWrapper<factory::ContractObj<DebugApi>, FactoryObjBuilder>,
+    pub distribution_wrapper:
+        ContractObjWrapper<distribution::ContractObj<DebugApi>, DistributionObjBuilder>,
+}
+
+fn setup_distribution<DistributionObjBuilder, FactoryObjBuilder>(
+    distribution_builder: DistributionObjBuilder,
+    factory_builder: FactoryObjBuilder,
+) -> DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>
+where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let owner_address = blockchain_wrapper.create_user_account(&rust_zero);
+    let first_user = blockchain_wrapper.create_user_account(&rust_zero);
+    let second_user = blockchain_wrapper.create_user_account(&rust_zero);
+    let treasury = blockchain_wrapper.create_user_account(&rust_zero);
+
+    let factory_wrapper = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner_address),
+        factory_builder,
+        FACTORY_WASM_PATH,
+    );
+    blockchain_wrapper
+        .execute_tx(&owner_address, &factory_wrapper, &rust_zero, |sc| {
+            let default_unlock_period = ManagedMultiResultVec::from(ManagedVec::from(vec![
+                UnlockMilestone {
+                    unlock_epoch: 0,
+                    unlock_percent: 50,
+                },
+                UnlockMilestone {
+                    unlock_epoch: 30,
+                    unlock_percent: 50,
+                },
+            ]));
+            sc.init(managed_token_id!(MEX_TOKEN_ID), default_unlock_period);
+            sc.locked_asset_token_id()
+                .set(&managed_token_id!(LOCKED_MEX_TOKEN_ID));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    let locked_asset_token_roles = [
+        EsdtLocalRole::NftCreate,
+        EsdtLocalRole::NftAddQuantity,
+        EsdtLocalRole::NftBurn,
+    ];
+    blockchain_wrapper.set_esdt_local_roles(
+        factory_wrapper.address_ref(),
+        LOCKED_MEX_TOKEN_ID,
+        &locked_asset_token_roles[..],
+    );
+
+    let distribution_wrapper = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner_address),
+        distribution_builder,
+        DISTRIBUTION_WASM_PATH,
+    );
+    let factory_address = factory_wrapper.address_ref().clone();
+    blockchain_wrapper
+        .execute_tx(&owner_address, &distribution_wrapper, &rust_zero, |sc| {
+            sc.init(
+                managed_token_id!(MEX_TOKEN_ID),
+                managed_address!(&factory_address),
+            );
+
+            let unlock_period = ManagedMultiResultVec::from(ManagedVec::from(vec![
+                UnlockMilestone {
+                    unlock_epoch: 0,
+                    unlock_percent: 50,
+                },
+                UnlockMilestone {
+                    unlock_epoch: 30,
+                    unlock_percent: 50,
+                },
+            ]));
+            sc.set_unlock_period(unlock_period);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    let distribution_address = distribution_wrapper.address_ref().clone();
+    blockchain_wrapper
+        .execute_tx(&owner_address, &factory_wrapper, &rust_zero, |sc| {
+            sc.whitelist(managed_address!(&distribution_address));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    DistributionSetup {
+        blockchain_wrapper,
+        owner_address,
+        first_user,
+        second_user,
+        treasury,
+        factory_wrapper,
+        distribution_wrapper,
+    }
+}
+
+fn set_community_distribution<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    total_amount: u64,
+    spread_epoch: u64,
+    user_amounts: &[(&Address, u64)],
+) where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.global_op_start();
+                sc.set_community_distribution(managed_biguint!(total_amount), spread_epoch);
+
+                let mut user_locked_assets = ManagedMultiResultVec::new();
+                for (address, amount) in user_amounts.iter() {
+                    user_locked_assets.push(MultiArg2::from((
+                        managed_address!(*address),
+                        managed_biguint!(*amount),
+                    )));
+                }
+                sc.set_per_user_distributed_locked_assets(spread_epoch, user_locked_assets);
+                sc.global_op_stop();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+fn claim_locked_assets<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    user: &Address,
+    expected_amount: u64,
+) where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    setup
+        .blockchain_wrapper
+        .execute_tx(user, &setup.distribution_wrapper, &rust_biguint!(0), |sc| {
+            let amount = sc.claim_locked_assets();
+            assert_eq!(amount, managed_biguint!(expected_amount));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+}
+
+fn set_claim_deadline<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    spread_epoch: u64,
+    deadline_epoch: u64,
+) -> TxResult
+where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    setup.blockchain_wrapper.execute_tx(
+        &owner,
+        &setup.distribution_wrapper,
+        &rust_biguint!(0),
+        |sc| {
+            sc.set_claim_deadline(spread_epoch, deadline_epoch);
+
+            StateChange::Commit
+        },
+    )
+}
+
+fn sweep_expired_distribution<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    spread_epoch: u64,
+    expected_amount: u64,
+) -> TxResult
+where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    let treasury = setup.treasury.clone();
+    setup.blockchain_wrapper.execute_tx(
+        &owner,
+        &setup.distribution_wrapper,
+        &rust_biguint!(0),
+        |sc| {
+            sc.set_unclaimed_treasury(managed_address!(&treasury));
+            let amount = sc.sweep_expired_distribution(spread_epoch);
+            assert_eq!(amount, managed_biguint!(expected_amount));
+
+            StateChange::Commit
+        },
+    )
+}
+
+/// Sets the first distribution, claims the allocation of the first user and sets the deadline.
+fn set_claimed_distribution_with_deadline<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+) where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    set_community_distribution(
+        setup,
+        DISTRIBUTION_AMOUNT,
+        FIRST_SPREAD_EPOCH,
+        &[
+            (&first_user, FIRST_USER_AMOUNT),
+            (&second_user, SECOND_USER_AMOUNT),
+        ],
+    );
+
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+    claim_locked_assets(setup, &first_user, FIRST_USER_AMOUNT);
+
+    set_claim_deadline(setup, FIRST_SPREAD_EPOCH, CLAIM_DEADLINE).assert_ok();
+}
+
+#[test]
+fn test_distribution_setup() {
+    let _ = setup_distribution(distribution::contract_obj, factory::contract_obj);
+}
+
+#[test]
+fn test_claim_deadline() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_claimed_distribution_with_deadline(&mut setup);
+
+    set_claim_deadline(&mut setup, FIRST_SPREAD_EPOCH, FIRST_SPREAD_EPOCH - 1)
+        .assert_user_error("Deadline before spread epoch");
+    set_claim_deadline(&mut setup, SECOND_SPREAD_EPOCH, CLAIM_DEADLINE)
+        .assert_user_error("Unknown community distribution");
+
+    // the allocations can still be claimed on the deadline epoch
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE);
+    let second_user = setup.second_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            let amount = sc.calculate_locked_assets_view(managed_address!(&second_user));
+            assert_eq!(amount, managed_biguint!(SECOND_USER_AMOUNT));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE + 1);
+    claim_locked_assets(&mut setup, &second_user, 0);
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert_eq!(
+                sc.get_unclaimed_amount(FIRST_SPREAD_EPOCH),
+                managed_biguint!(DISTRIBUTION_AMOUNT - FIRST_USER_AMOUNT)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_sweep_expired_distribution() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_claimed_distribution_with_deadline(&mut setup);
+
+    let unclaimed_amount = DISTRIBUTION_AMOUNT - FIRST_USER_AMOUNT;
+    sweep_expired_distribution(&mut setup, FIRST_SPREAD_EPOCH, unclaimed_amount)
+        .assert_user_error("Claim deadline not passed");
+
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE + 1);
+    sweep_expired_distribution(&mut setup, FIRST_SPREAD_EPOCH, unclaimed_amount).assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert!(sc.distribution_expired(FIRST_SPREAD_EPOCH).get());
+            assert_eq!(
+                sc.expired_unclaimed_amount(FIRST_SPREAD_EPOCH).get(),
+                managed_biguint!(unclaimed_amount)
+            );
+        })
+        .assert_ok();
+
+    sweep_expired_distribution(&mut setup, FIRST_SPREAD_EPOCH, unclaimed_amount)
+        .assert_user_error("Distribution already expired");
+    set_claim_deadline(&mut setup, FIRST_SPREAD_EPOCH, CLAIM_DEADLINE + 10)
+        .assert_user_error("Distribution already expired");
+}
+
+#[test]
+fn test_sweep_fully_claimed_distribution() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    set_community_distribution(
+        &mut setup,
+        FIRST_USER_AMOUNT + SECOND_USER_AMOUNT,
+        FIRST_SPREAD_EPOCH,
+        &[
+            (&first_user, FIRST_USER_AMOUNT),
+            (&second_user, SECOND_USER_AMOUNT),
+        ],
+    );
+
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+    claim_locked_assets(&mut setup, &first_user, FIRST_USER_AMOUNT);
+    claim_locked_assets(&mut setup, &second_user, SECOND_USER_AMOUNT);
+    set_claim_deadline(&mut setup, FIRST_SPREAD_EPOCH, CLAIM_DEADLINE).assert_ok();
+
+    // nothing is left to sweep, but the distribution still expires only once
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE + 1);
+    sweep_expired_distribution(&mut setup, FIRST_SPREAD_EPOCH, 0).assert_ok();
+    sweep_expired_distribution(&mut setup, FIRST_SPREAD_EPOCH, 0)
+        .assert_user_error("Distribution already expired");
+}
+
+#[test]
+fn test_roll_over_expired_distribution() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_claimed_distribution_with_deadline(&mut setup);
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE + 1);
+
+    let owner = setup.owner_address.clone();
+    let rust_zero = rust_biguint!(0u64);
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            sc.global_op_start();
+            let _ = sc.roll_over_expired_distribution(FIRST_SPREAD_EPOCH);
+
+            StateChange::Commit
+        })
+        .assert_user_error("No later community distribution");
+
+    let unclaimed_amount = DISTRIBUTION_AMOUNT - FIRST_USER_AMOUNT;
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            sc.global_op_start();
+            sc.set_community_distribution(
+                managed_biguint!(DISTRIBUTION_AMOUNT),
+                SECOND_SPREAD_EPOCH,
+            );
+            let amount = sc.roll_over_expired_distribution(FIRST_SPREAD_EPOCH);
+            assert_eq!(amount, managed_biguint!(unclaimed_amount));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            let last_community_distrib = sc
+                .community_distribution_list()
+                .front()
+                .unwrap()
+                .get_value_cloned();
+            assert_eq!(last_community_distrib.spread_epoch, SECOND_SPREAD_EPOCH);
+            assert_eq!(
+                last_community_distrib.total_amount,
+                managed_biguint!(DISTRIBUTION_AMOUNT + unclaimed_amount)
+            );
+            assert_eq!(
+                last_community_distrib.after_planning_amount,
+                managed_biguint!(DISTRIBUTION_AMOUNT + unclaimed_amount)
+            );
+            assert!(sc.distribution_expired(FIRST_SPREAD_EPOCH).get());
+        })
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let _ = sc.roll_over_expired_distribution(FIRST_SPREAD_EPOCH);
+
+            StateChange::Commit
+        })
+        .assert_user_error("Distribution already expired");
+}
+
+#[test]
+fn test_untracked_distribution_cannot_expire() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    let first_user = setup.first_user.clone();
+    set_community_distribution(
+        &mut setup,
+        DISTRIBUTION_AMOUNT,
+        FIRST_SPREAD_EPOCH,
+        &[(&first_user, FIRST_USER_AMOUNT)],
+    );
+
+    // distributions set before the claims were tracked have no flag
+    let owner = setup.owner_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.distribution_claims_tracked(FIRST_SPREAD_EPOCH).clear();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    set_claim_deadline(&mut setup, FIRST_SPREAD_EPOCH, CLAIM_DEADLINE)
+        .assert_user_error("Claims not tracked for distribution");
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            let _ = sc.get_unclaimed_amount(FIRST_SPREAD_EPOCH);
+        })
+        .assert_user_error("Claims not tracked for distribution");
+}
+
+#[test]
+fn test_undo_clears_expired_distribution() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_claimed_distribution_with_deadline(&mut setup);
+
+    setup.blockchain_wrapper.set_block_epoch(CLAIM_DEADLINE + 1);
+    sweep_expired_distribution(
+        &mut setup,
+        FIRST_SPREAD_EPOCH,
+        DISTRIBUTION_AMOUNT - FIRST_USER_AMOUNT,
+    )
+    .assert_ok();
+
+    let owner = setup.owner_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.global_op_start();
+                sc.undo_last_community_distrib();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert!(sc.community_distribution_list().is_empty());
+            assert!(sc.claim_deadline(FIRST_SPREAD_EPOCH).is_empty());
+            assert!(sc
+                .distribution_claimed_amount(FIRST_SPREAD_EPOCH)
+                .is_empty());
+            assert!(!sc.distribution_claims_tracked(FIRST_SPREAD_EPOCH).get());
+            assert!(!sc.distribution_expired(FIRST_SPREAD_EPOCH).get());
+            assert!(sc.expired_unclaimed_amount(FIRST_SPREAD_EPOCH).is_empty());
+        })
+        .assert_ok();
+}
+
+/// Builds a Merkle tree with four leaves, two for each user, and returns its nodes:
+/// the leaves first, then the two inner nodes and the root.
+fn merkle_tree_nodes(
+    sc: &distribution::ContractObj<DebugApi>,
+    first_user: &Address,
+    second_user: &Address,
+) -> Vec<MerkleHash<DebugApi>> {
+    let leaves = [
+        (first_user, FIRST_USER_AMOUNT),
+        (second_user, SECOND_USER_AMOUNT),
+        (first_user, MERKLE_THIRD_LEAF_AMOUNT),
+        (second_user, MERKLE_FOURTH_LEAF_AMOUNT),
+    ];
+
+    let mut nodes = Vec::new();
+    for (index, (address, amount)) in leaves.iter().enumerate() {
+        nodes.push(sc.merkle_leaf_hash(&MerkleLeaf {
+            index: index as u64,
+            address: managed_address!(*address),
+            amount: managed_biguint!(*amount),
+        }));
+    }
+    nodes.push(sc.merkle_node_hash(&nodes[0], &nodes[1]));
+    nodes.push(sc.merkle_node_hash(&nodes[2], &nodes[3]));
+    nodes.push(sc.merkle_node_hash(&nodes[4], &nodes[5]));
+
+    nodes
+}
+
+/// Sets the first distribution along with the Merkle root of its allocations.
+fn set_merkle_distribution<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    merkle_total_amount: u64,
+) where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let nodes = merkle_tree_nodes(sc, &first_user, &second_user);
+
+                sc.global_op_start();
+                sc.set_community_distribution(
+                    managed_biguint!(DISTRIBUTION_AMOUNT),
+                    FIRST_SPREAD_EPOCH,
+                );
+                sc.set_merkle_root(
+                    FIRST_SPREAD_EPOCH,
+                    nodes[6].clone(),
+                    managed_biguint!(merkle_total_amount),
+                );
+                sc.global_op_stop();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+/// Claims a leaf of the Merkle tree, the proof being given by the indexes of its nodes.
+fn claim_with_proof<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    user: &Address,
+    index: u64,
+    amount: u64,
+    proof_node_indexes: &[usize],
+) -> TxResult
+where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    setup.blockchain_wrapper.execute_tx(
+        user,
+        &setup.distribution_wrapper,
+        &rust_biguint!(0),
+        |sc| {
+            let nodes = merkle_tree_nodes(sc, &first_user, &second_user);
+            let mut proof = ManagedMultiResultVec::new();
+            for node_index in proof_node_indexes.iter() {
+                proof.push(nodes[*node_index].clone());
+            }
+
+            let claimed_amount = sc.claim_locked_assets_with_proof(
+                FIRST_SPREAD_EPOCH,
+                index,
+                managed_biguint!(amount),
+                proof,
+            );
+            assert_eq!(claimed_amount, managed_biguint!(amount));
+
+            StateChange::Commit
+        },
+    )
+}
+
+#[test]
+fn test_claim_with_merkle_proof() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_merkle_distribution(&mut setup, MERKLE_TOTAL_AMOUNT);
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5]).assert_ok();
+    claim_with_proof(
+        &mut setup,
+        &second_user,
+        3,
+        MERKLE_FOURTH_LEAF_AMOUNT,
+        &[2, 4],
+    )
+    .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert!(sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 0));
+            assert!(!sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 1));
+            assert!(!sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 2));
+            assert!(sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 3));
+            assert_eq!(
+                sc.get_merkle_claimed_amount(FIRST_SPREAD_EPOCH),
+                managed_biguint!(FIRST_USER_AMOUNT + MERKLE_FOURTH_LEAF_AMOUNT)
+            );
+        })
+        .assert_ok();
+}
+
+fn set_campaign_user_allocations<DistributionObjBuilder, FactoryObjBuilder>(
+    setup: &mut DistributionSetup<DistributionObjBuilder, FactoryObjBuilder>,
+    campaign_id: u64,
+) where
+    DistributionObjBuilder: 'static + Copy + Fn() -> distribution::ContractObj<DebugApi>,
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let mut user_allocations = ManagedMultiResultVec::new();
+                user_allocations.push(MultiArg2::from((
+                    managed_address!(&first_user),
+                    managed_biguint!(FIRST_USER_AMOUNT),
+                )));
+                user_allocations.push(MultiArg2::from((
+                    managed_address!(&second_user),
+                    managed_biguint!(SECOND_USER_AMOUNT),
+                )));
+                sc.set_campaign_user_allocations(campaign_id, user_allocations);
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn test_locked_campaign_requires_factory_asset() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let factory_address = setup.factory_wrapper.address_ref().clone();
+    let rust_zero = rust_biguint!(0u64);
+
+    for (token_id, opt_expected_error) in [
+        (USDC_TOKEN_ID, Some("Token is not the factory asset")),
+        (MEX_TOKEN_ID, None),
+    ] {
+        let result = setup.blockchain_wrapper.execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_zero,
+            |sc| {
+                let milestones =
+                    ManagedMultiResultVec::from(ManagedVec::from(vec![UnlockMilestone {
+                        unlock_epoch: 0,
+                        unlock_percent: 100,
+                    }]));
+                let campaign_id = sc.create_locked_campaign(
+                    managed_token_id!(token_id),
+                    managed_biguint!(CAMPAIGN_BUDGET),
+                    CAMPAIGN_START_EPOCH,
+                    CAMPAIGN_END_EPOCH,
+                    managed_address!(&factory_address),
+                    milestones,
+                );
+                assert_eq!(campaign_id, 1);
+
+                StateChange::Commit
+            },
+        );
+        match opt_expected_error {
+            Some(expected_error) => result.assert_user_error(expected_error),
+            None => result.assert_ok(),
+        }
+    }
+
+    set_campaign_user_allocations(&mut setup, 1);
+
+    setup
+        .blockchain_wrapper
+        .set_block_epoch(CAMPAIGN_START_EPOCH);
+    let first_user = setup.first_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(&first_user, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let payment = sc.claim_campaign(1);
+            assert_eq!(
+                payment.token_identifier,
+                managed_token_id!(LOCKED_MEX_TOKEN_ID)
+            );
+            assert_eq!(payment.amount, managed_biguint!(FIRST_USER_AMOUNT));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    // the locked assets are minted by the factory, there is nothing to withdraw
+    setup.blockchain_wrapper.set_block_epoch(CAMPAIGN_END_EPOCH);
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let _ = sc.withdraw_campaign_funds(1);
+
+            StateChange::Commit
+        })
+        .assert_user_error("Campaign is locked through a factory");
+}
+
+#[test]
+fn test_plain_campaign_withdraw_funds() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let rust_zero = rust_biguint!(0u64);
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let campaign_id = sc.create_plain_campaign(
+                managed_token_id!(USDC_TOKEN_ID),
+                managed_biguint!(CAMPAIGN_BUDGET),
+                CAMPAIGN_START_EPOCH,
+                CAMPAIGN_END_EPOCH,
+            );
+            assert_eq!(campaign_id, 1);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+    set_campaign_user_allocations(&mut setup, 1);
+
+    setup.blockchain_wrapper.set_esdt_balance(
+        &owner,
+        USDC_TOKEN_ID,
+        &rust_biguint!(CAMPAIGN_BUDGET),
+    );
+    setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &owner,
+            &setup.distribution_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(CAMPAIGN_BUDGET),
+            |sc| {
+                sc.fund_campaign(
+                    1,
+                    managed_token_id!(USDC_TOKEN_ID),
+                    0,
+                    managed_biguint!(CAMPAIGN_BUDGET),
+                );
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .set_block_epoch(CAMPAIGN_START_EPOCH);
+    let first_user = setup.first_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(&first_user, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let payment = sc.claim_campaign(1);
+            assert_eq!(payment.amount, managed_biguint!(FIRST_USER_AMOUNT));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_esdt_balance(
+        &first_user,
+        USDC_TOKEN_ID,
+        &rust_biguint!(FIRST_USER_AMOUNT),
+    );
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let _ = sc.withdraw_campaign_funds(1);
+
+            StateChange::Commit
+        })
+        .assert_user_error("Campaign not ended");
+
+    // the allocation of the second user was not claimed before the end
+    setup.blockchain_wrapper.set_block_epoch(CAMPAIGN_END_EPOCH);
+    let second_user = setup.second_user.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &second_user,
+            &setup.distribution_wrapper,
+            &rust_zero,
+            |sc| {
+                let _ = sc.claim_campaign(1);
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Campaign not active");
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let payment = sc.withdraw_campaign_funds(1);
+            assert_eq!(
+                payment.amount,
+                managed_biguint!(CAMPAIGN_BUDGET - FIRST_USER_AMOUNT)
+            );
+
+            StateChange::Commit
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_esdt_balance(
+        &owner,
+        USDC_TOKEN_ID,
+        &rust_biguint!(CAMPAIGN_BUDGET - FIRST_USER_AMOUNT),
+    );
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.distribution_wrapper, &rust_zero, |sc| {
+            let _ = sc.withdraw_campaign_funds(1);
+
+            StateChange::Commit
+        })
+        .assert_user_error("Nothing to withdraw");
+}
+
+#[test]
+fn test_claim_with_invalid_merkle_proof() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_merkle_distribution(&mut setup, MERKLE_TOTAL_AMOUNT);
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+
+    // siblings given in the wrong order
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[5, 1])
+        .assert_user_error("Invalid proof");
+    // leaf of another user
+    claim_with_proof(&mut setup, &second_user, 0, FIRST_USER_AMOUNT, &[1, 5])
+        .assert_user_error("Invalid proof");
+    // amount above the allocation
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT + 1, &[1, 5])
+        .assert_user_error("Invalid proof");
+    // index beyond the leaves covered by the proof
+    claim_with_proof(&mut setup, &first_user, 4, FIRST_USER_AMOUNT, &[1, 5])
+        .assert_user_error("Leaf index out of tree");
+
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5]).assert_ok();
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5])
+        .assert_user_error("Already claimed");
+}
+
+#[test]
+fn test_claim_with_merkle_proof_above_total_amount() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_merkle_distribution(&mut setup, FIRST_USER_AMOUNT + MERKLE_FOURTH_LEAF_AMOUNT);
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+
+    let first_user = setup.first_user.clone();
+    let second_user = setup.second_user.clone();
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5]).assert_ok();
+    claim_with_proof(&mut setup, &second_user, 1, SECOND_USER_AMOUNT, &[0, 5])
+        .assert_user_error("Claims above Merkle total amount");
+    claim_with_proof(
+        &mut setup,
+        &second_user,
+        3,
+        MERKLE_FOURTH_LEAF_AMOUNT,
+        &[2, 4],
+    )
+    .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert!(!sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 1));
+            assert_eq!(
+                sc.get_merkle_claimed_amount(FIRST_SPREAD_EPOCH),
+                managed_biguint!(FIRST_USER_AMOUNT + MERKLE_FOURTH_LEAF_AMOUNT)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_undo_clears_merkle_claims() {
+    let mut setup = setup_distribution(distribution::contract_obj, factory::contract_obj);
+    set_merkle_distribution(&mut setup, MERKLE_TOTAL_AMOUNT);
+    setup.blockchain_wrapper.set_block_epoch(FIRST_SPREAD_EPOCH);
+
+    let first_user = setup.first_user.clone();
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5]).assert_ok();
+
+    let owner = setup.owner_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(
+            &owner,
+            &setup.distribution_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.global_op_start();
+                sc.undo_last_community_distrib();
+                sc.global_op_stop();
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.distribution_wrapper, |sc| {
+            assert!(sc.merkle_root(FIRST_SPREAD_EPOCH).is_empty());
+            assert!(!sc.is_merkle_leaf_claimed(FIRST_SPREAD_EPOCH, 0));
+            assert_eq!(sc.get_merkle_claimed_amount(FIRST_SPREAD_EPOCH), 0u64);
+        })
+        .assert_ok();
+
+    // the same root set again starts with no claims
+    set_merkle_distribution(&mut setup, MERKLE_TOTAL_AMOUNT);
+    claim_with_proof(&mut setup, &first_user, 0, FIRST_USER_AMOUNT, &[1, 5]).assert_ok();
+}
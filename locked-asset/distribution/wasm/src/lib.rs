@@ -8,20 +8,47 @@ elrond_wasm_node::wasm_endpoints! {
     distribution
     (
         calculateLockedAssets
+        areDistributionClaimsTracked
+        claimCampaign
         claimLockedAssets
+        claimLockedAssetsWithProof
         clearUnclaimableAssets
+        createCampaign
+        createLockedCampaign
         deleteUserDistributedLockedAssets
         endGlobalOperation
+        fundCampaign
         getAssetTokenId
+        getCampaign
+        getCampaignClaimableAmount
+        getCampaignIds
+        getCampaignUserAllocation
+        getClaimDeadline
         getCommunityDistributionList
+        getDistributionClaimedAmount
+        getExpiredUnclaimedAmount
+        getMerkleClaimedAmount
+        getMerkleRoot
+        getMerkleTotalAmount
+        getUnclaimedAmount
+        getUnclaimedTreasury
         getUnlockPeriod
         getUsersDistributedLockedAssetsLength
+        isDistributionExpired
+        isMerkleLeafClaimed
+        rollOverExpiredDistribution
+        setCampaignUserAllocations
+        setClaimDeadline
         setCommunityDistribution
+        setMerkleRoot
         setPerUserDistributedLockedAssets
+        setUnclaimedTreasury
         setUnlockPeriod
         startGlobalOperation
+        sweepExpiredDistribution
         undoLastCommunityDistribution
         undoUserDistributedAssetsBetweenEpochs
+        withdrawCampaignFunds
     )
 }
 
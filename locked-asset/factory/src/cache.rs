@@ -1,11 +1,12 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
-use common_structs::{Nonce, UnlockScheduleEx};
+use common_structs::{Epoch, LinearVestingSchedule, Nonce, UnlockMilestoneEx, UnlockScheduleEx};
 
 use crate::attr_ex_helper;
 
 use super::locked_asset;
+use crate::ADDITIONAL_AMOUNT_TO_CREATE;
 
 #[elrond_wasm::module]
 pub trait CacheModule:
@@ -38,6 +39,121 @@ pub trait CacheModule:
             .insert(nonce, unlock_schedule.clone());
     }
 
+    #[inline(always)]
+    fn get_sft_nonce_for_linear_vesting(
+        &self,
+        vesting_schedule: &LinearVestingSchedule,
+    ) -> Option<Nonce> {
+        self.nonce_cache_linear().get(vesting_schedule)
+    }
+
+    #[view(getLinearVestingForSFTNonce)]
+    fn get_linear_vesting_for_sft_nonce(&self, nonce: Nonce) -> Option<LinearVestingSchedule> {
+        self.linear_vesting_cache().get(&nonce)
+    }
+
+    #[inline(always)]
+    fn cache_linear_vesting_and_nonce(
+        &self,
+        vesting_schedule: &LinearVestingSchedule,
+        nonce: Nonce,
+    ) {
+        self.nonce_cache_linear().insert(*vesting_schedule, nonce);
+        self.linear_vesting_cache().insert(nonce, *vesting_schedule);
+    }
+
+    /// Milestones that are already unlockable are merged into one at the current month start,
+    /// so that schedules differing only in their past milestones share the same nonce.
+    fn normalize_unlock_schedule(
+        &self,
+        unlock_schedule: &UnlockScheduleEx<Self::Api>,
+        month_start_epoch: Epoch,
+    ) -> UnlockScheduleEx<Self::Api> {
+        let mut elapsed_unlock_percent = 0u64;
+        for milestone in unlock_schedule.unlock_milestones.iter() {
+            if milestone.unlock_epoch <= month_start_epoch {
+                elapsed_unlock_percent += milestone.unlock_percent;
+            }
+        }
+
+        let mut unlock_milestones = ManagedVec::new();
+        if elapsed_unlock_percent > 0 {
+            unlock_milestones.push(UnlockMilestoneEx {
+                unlock_epoch: month_start_epoch,
+                unlock_percent: elapsed_unlock_percent,
+            });
+        }
+        for milestone in unlock_schedule.unlock_milestones.iter() {
+            if milestone.unlock_epoch > month_start_epoch {
+                unlock_milestones.push(milestone);
+            }
+        }
+
+        UnlockScheduleEx { unlock_milestones }
+    }
+
+    /// Lookups use normalized schedules, so a cached schedule with milestones before
+    /// the current month start can't be matched anymore.
+    fn is_stale_unlock_schedule(
+        &self,
+        unlock_schedule: &UnlockScheduleEx<Self::Api>,
+        month_start_epoch: Epoch,
+    ) -> bool {
+        unlock_schedule
+            .unlock_milestones
+            .iter()
+            .any(|milestone| milestone.unlock_epoch < month_start_epoch)
+    }
+
+    /// Checks at most `max_entries` nonces, starting after the last one checked by the
+    /// previous call, and drops the stale cache entries. Once the last created nonce is
+    /// reached, the next call starts over. The extra unit minted for each cached nonce
+    /// is burned along. Returns the pruned count.
+    fn prune_stale_cache_entries(&self, month_start_epoch: Epoch, max_entries: usize) -> usize {
+        let token_id = self.locked_asset_token_id().get();
+        let last_nonce = self
+            .blockchain()
+            .get_current_esdt_nft_nonce(&self.blockchain().get_sc_address(), &token_id);
+        let extra_amount = BigUint::from(ADDITIONAL_AMOUNT_TO_CREATE);
+
+        let mut nonce = self.prune_cache_cursor().get();
+        let mut pruned_entries = 0;
+        for _ in 0..max_entries {
+            if nonce >= last_nonce {
+                nonce = 0;
+                break;
+            }
+            nonce += 1;
+
+            let unlock_schedule = match self.unlock_schedule_cache_ex().get(&nonce) {
+                Some(unlock_schedule) => unlock_schedule,
+                None => continue,
+            };
+            if !self.is_stale_unlock_schedule(&unlock_schedule, month_start_epoch) {
+                continue;
+            }
+
+            self.unlock_schedule_cache_ex().remove(&nonce);
+            self.nonce_cache_ex().remove(&unlock_schedule);
+            if self.blockchain().get_sc_balance(&token_id, nonce) >= extra_amount {
+                self.send().esdt_local_burn(&token_id, nonce, &extra_amount);
+            }
+            pruned_entries += 1;
+        }
+        self.prune_cache_cursor().set(&nonce);
+
+        pruned_entries
+    }
+
+    fn count_stale_cache_entries(&self, month_start_epoch: Epoch) -> usize {
+        self.nonce_cache_ex()
+            .keys()
+            .filter(|unlock_schedule| {
+                self.is_stale_unlock_schedule(unlock_schedule, month_start_epoch)
+            })
+            .count()
+    }
+
     #[view(getCacheSize)]
     fn get_cache_size(&self) -> usize {
         self.nonce_cache_ex().len()
@@ -48,4 +164,14 @@ pub trait CacheModule:
 
     #[storage_mapper("unlock_schedule_cache_ex")]
     fn unlock_schedule_cache_ex(&self) -> MapMapper<Nonce, UnlockScheduleEx<Self::Api>>;
+
+    #[view(getPruneCacheCursor)]
+    #[storage_mapper("prune_cache_cursor")]
+    fn prune_cache_cursor(&self) -> SingleValueMapper<Nonce>;
+
+    #[storage_mapper("nonce_cache_linear")]
+    fn nonce_cache_linear(&self) -> MapMapper<LinearVestingSchedule, Nonce>;
+
+    #[storage_mapper("linear_vesting_cache")]
+    fn linear_vesting_cache(&self) -> MapMapper<Nonce, LinearVestingSchedule>;
 }
@@ -0,0 +1,231 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_structs::{Epoch, Nonce, UnlockMilestoneEx};
+
+use crate::attr_ex_helper;
+use crate::cache;
+use crate::events;
+use crate::locked_asset::{self, PERCENTAGE_TOTAL_EX};
+
+pub const MAX_EARLY_UNLOCK_PENALTY_PERCENT: u64 = 10_000;
+
+/// Penalty applied to the milestones that become unlockable in at least `min_epochs_remaining` epochs.
+#[derive(
+    ManagedVecItem,
+    TopEncode,
+    TopDecode,
+    NestedEncode,
+    NestedDecode,
+    TypeAbi,
+    Clone,
+    Copy,
+    PartialEq,
+    Debug,
+)]
+pub struct EarlyUnlockPenaltyTier {
+    pub min_epochs_remaining: u64,
+    pub penalty_percent: u64,
+}
+
+#[elrond_wasm::module]
+pub trait EarlyUnlockModule:
+    locked_asset::LockedAssetModule
+    + token_send::TokenSendModule
+    + attr_ex_helper::AttrExHelper
+    + cache::CacheModule
+    + events::EventsModule
+{
+    /// Sets the penalty curve, as tiers ordered by the epochs remaining until a milestone unlocks.
+    /// Each milestone is penalized by the last tier it reaches, milestones closer than the
+    /// first tier are not penalized. Penalties are in MAX_EARLY_UNLOCK_PENALTY_PERCENT units
+    /// and can't decrease for further away milestones.
+    #[only_owner]
+    #[endpoint(setEarlyUnlockPenaltyCurve)]
+    fn set_early_unlock_penalty_curve(
+        &self,
+        #[var_args] tiers: ManagedVarArgs<EarlyUnlockPenaltyTier>,
+    ) {
+        let penalty_curve = tiers.to_vec();
+        require!(!penalty_curve.is_empty(), "Empty param");
+
+        let mut last_tier: Option<EarlyUnlockPenaltyTier> = None;
+        for tier in penalty_curve.iter() {
+            require!(
+                tier.penalty_percent <= MAX_EARLY_UNLOCK_PENALTY_PERCENT,
+                "Penalty percent more than max"
+            );
+            if let Some(last) = last_tier {
+                require!(
+                    tier.min_epochs_remaining > last.min_epochs_remaining,
+                    "Tier epochs not in order"
+                );
+                require!(
+                    tier.penalty_percent >= last.penalty_percent,
+                    "Penalty decreases for further milestones"
+                );
+            }
+            last_tier = Some(tier);
+        }
+
+        self.early_unlock_penalty_curve().set(&penalty_curve);
+    }
+
+    #[only_owner]
+    #[endpoint(disableEarlyUnlock)]
+    fn disable_early_unlock(&self) {
+        self.early_unlock_penalty_curve().clear();
+    }
+
+    /// Penalties are sent to the given treasury. Without one, they are not minted at all,
+    /// which amounts to burning them.
+    #[only_owner]
+    #[endpoint(setEarlyUnlockPenaltyTreasury)]
+    fn set_early_unlock_penalty_treasury(
+        &self,
+        #[var_args] opt_treasury: OptionalArg<ManagedAddress>,
+    ) {
+        match opt_treasury {
+            OptionalArg::Some(treasury) => self.early_unlock_penalty_treasury().set(&treasury),
+            OptionalArg::None => self.early_unlock_penalty_treasury().clear(),
+        }
+    }
+
+    /// Unlocks all the remaining milestones of the locked assets right away.
+    /// The milestones that are not unlockable yet are penalized according to the curve.
+    #[payable("*")]
+    #[endpoint(unlockEarly)]
+    fn unlock_early(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] token_nonce: Nonce,
+        #[payment_amount] amount: BigUint,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let locked_token_id = self.locked_asset_token_id().get();
+        require!(token_id == locked_token_id, "Bad payment token");
+        require!(amount > 0u64, "Zero input amount");
+        require!(
+            !self.early_unlock_penalty_curve().is_empty(),
+            "Early unlock not enabled"
+        );
+
+        let attributes = self.get_attributes_ex(&token_id, token_nonce);
+        let penalty_amount = self.compute_early_unlock_penalty(
+            &amount,
+            self.blockchain().get_block_epoch(),
+            &attributes.unlock_schedule.unlock_milestones,
+            &self.early_unlock_penalty_curve().get(),
+        );
+        let unlocked_amount = &amount - &penalty_amount;
+
+        self.send()
+            .esdt_local_burn(&locked_token_id, token_nonce, &amount);
+
+        let caller = self.blockchain().get_caller();
+        self.mint_and_send_assets(&caller, &unlocked_amount);
+
+        let treasury_mapper = self.early_unlock_penalty_treasury();
+        if !treasury_mapper.is_empty() {
+            self.mint_and_send_assets(&treasury_mapper.get(), &penalty_amount);
+        }
+
+        let asset_token_id = self.asset_token_id().get();
+        self.emit_early_unlock_event(
+            &caller,
+            &token_id,
+            token_nonce,
+            &amount,
+            &asset_token_id,
+            &unlocked_amount,
+            &penalty_amount,
+            &attributes,
+        );
+
+        self.create_payment(&asset_token_id, 0, &unlocked_amount)
+    }
+
+    /// Returns the asset amount received and the penalty paid
+    /// when unlocking early the given amount of locked assets.
+    #[view(getEarlyUnlockQuote)]
+    fn get_early_unlock_quote(
+        &self,
+        token_nonce: Nonce,
+        amount: BigUint,
+    ) -> MultiResult2<BigUint, BigUint> {
+        require!(
+            !self.early_unlock_penalty_curve().is_empty(),
+            "Early unlock not enabled"
+        );
+
+        let token_id = self.locked_asset_token_id().get();
+        let attributes = self.get_attributes_ex(&token_id, token_nonce);
+        let penalty_amount = self.compute_early_unlock_penalty(
+            &amount,
+            self.blockchain().get_block_epoch(),
+            &attributes.unlock_schedule.unlock_milestones,
+            &self.early_unlock_penalty_curve().get(),
+        );
+
+        (&amount - &penalty_amount, penalty_amount).into()
+    }
+
+    fn compute_early_unlock_penalty(
+        &self,
+        amount: &BigUint,
+        current_epoch: Epoch,
+        unlock_milestones: &ManagedVec<UnlockMilestoneEx>,
+        penalty_curve: &ManagedVec<EarlyUnlockPenaltyTier>,
+    ) -> BigUint {
+        let month_start_epoch = self.get_month_start_epoch(current_epoch);
+        let mut penalty_amount = BigUint::zero();
+        let mut remaining_amount = amount.clone();
+        let last_index = unlock_milestones.len() - 1;
+
+        for (index, milestone) in unlock_milestones.iter().enumerate() {
+            // The last milestone gets the rounding leftovers.
+            let milestone_amount = if index == last_index {
+                remaining_amount.clone()
+            } else {
+                amount * &BigUint::from(milestone.unlock_percent) / PERCENTAGE_TOTAL_EX
+            };
+            remaining_amount -= &milestone_amount;
+
+            // Milestones become unlockable at the start of a month, as in unlockAssets.
+            if milestone.unlock_epoch > month_start_epoch {
+                let unlockable_epoch = self.get_next_month_start_epoch(milestone.unlock_epoch);
+                let penalty_percent = self.get_early_unlock_penalty_percent(
+                    unlockable_epoch - current_epoch,
+                    penalty_curve,
+                );
+                penalty_amount += milestone_amount * &BigUint::from(penalty_percent)
+                    / MAX_EARLY_UNLOCK_PENALTY_PERCENT;
+            }
+        }
+
+        penalty_amount
+    }
+
+    fn get_early_unlock_penalty_percent(
+        &self,
+        epochs_remaining: u64,
+        penalty_curve: &ManagedVec<EarlyUnlockPenaltyTier>,
+    ) -> u64 {
+        let mut penalty_percent = 0u64;
+        for tier in penalty_curve.iter() {
+            if tier.min_epochs_remaining > epochs_remaining {
+                break;
+            }
+            penalty_percent = tier.penalty_percent;
+        }
+
+        penalty_percent
+    }
+
+    #[view(getEarlyUnlockPenaltyCurve)]
+    #[storage_mapper("early_unlock_penalty_curve")]
+    fn early_unlock_penalty_curve(&self) -> SingleValueMapper<ManagedVec<EarlyUnlockPenaltyTier>>;
+
+    #[view(getEarlyUnlockPenaltyTreasury)]
+    #[storage_mapper("early_unlock_penalty_treasury")]
+    fn early_unlock_penalty_treasury(&self) -> SingleValueMapper<ManagedAddress>;
+}
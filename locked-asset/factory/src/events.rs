@@ -1,7 +1,7 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
-use common_structs::LockedAssetTokenAttributesEx;
+use common_structs::{LinearVestingTokenAttributes, LockedAssetTokenAttributesEx};
 
 #[derive(TopEncode)]
 pub struct CreateAndForwardEvent<M: ManagedTypeApi> {
@@ -35,6 +35,67 @@ pub struct UnlockAssetsEvent<M: ManagedTypeApi> {
     timestamp: u64,
 }
 
+#[derive(TopEncode)]
+pub struct RelockAssetsEvent<M: ManagedTypeApi> {
+    caller: ManagedAddress<M>,
+    input_locked_assets_token_id: TokenIdentifier<M>,
+    input_locked_assets_token_nonce: u64,
+    input_locked_assets_token_amount: BigUint<M>,
+    output_locked_assets_token_id: TokenIdentifier<M>,
+    output_locked_assets_token_nonce: u64,
+    output_locked_assets_token_amount: BigUint<M>,
+    input_assets_attributes: LockedAssetTokenAttributesEx<M>,
+    output_assets_attributes: LockedAssetTokenAttributesEx<M>,
+    block: u64,
+    epoch: u64,
+    timestamp: u64,
+}
+
+#[derive(TopEncode)]
+pub struct EarlyUnlockEvent<M: ManagedTypeApi> {
+    caller: ManagedAddress<M>,
+    input_locked_assets_token_id: TokenIdentifier<M>,
+    input_locked_assets_token_nonce: u64,
+    input_locked_assets_token_amount: BigUint<M>,
+    asset_token_id: TokenIdentifier<M>,
+    asset_token_amount: BigUint<M>,
+    penalty_amount: BigUint<M>,
+    input_assets_attributes: LockedAssetTokenAttributesEx<M>,
+    block: u64,
+    epoch: u64,
+    timestamp: u64,
+}
+
+#[derive(TopEncode)]
+pub struct CreateAndForwardLinearVestingEvent<M: ManagedTypeApi> {
+    caller: ManagedAddress<M>,
+    destination: ManagedAddress<M>,
+    locked_asset_token_id: TokenIdentifier<M>,
+    locked_asset_token_nonce: u64,
+    locked_asset_token_amount: BigUint<M>,
+    locked_assets_attributes: LinearVestingTokenAttributes,
+    block: u64,
+    epoch: u64,
+    timestamp: u64,
+}
+
+#[derive(TopEncode)]
+pub struct UnlockLinearVestingAssetsEvent<M: ManagedTypeApi> {
+    caller: ManagedAddress<M>,
+    locked_assets_token_id: TokenIdentifier<M>,
+    input_locked_assets_token_nonce: u64,
+    input_locked_assets_token_amount: BigUint<M>,
+    output_locked_assets_token_nonce: u64,
+    output_locked_assets_token_amount: BigUint<M>,
+    asset_token_id: TokenIdentifier<M>,
+    asset_token_amount: BigUint<M>,
+    input_assets_attributes: LinearVestingTokenAttributes,
+    output_assets_attributes: LinearVestingTokenAttributes,
+    block: u64,
+    epoch: u64,
+    timestamp: u64,
+}
+
 #[elrond_wasm::module]
 pub trait EventsModule {
     fn emit_create_and_forward_event(
@@ -104,6 +165,133 @@ pub trait EventsModule {
         )
     }
 
+    fn emit_early_unlock_event(
+        self,
+        caller: &ManagedAddress,
+        input_locked_assets_token_id: &TokenIdentifier,
+        input_locked_assets_token_nonce: u64,
+        input_locked_assets_token_amount: &BigUint,
+        asset_token_id: &TokenIdentifier,
+        asset_token_amount: &BigUint,
+        penalty_amount: &BigUint,
+        input_assets_attributes: &LockedAssetTokenAttributesEx<Self::Api>,
+    ) {
+        let epoch = self.blockchain().get_block_epoch();
+        self.early_unlock_event(
+            caller,
+            epoch,
+            &EarlyUnlockEvent {
+                caller: caller.clone(),
+                input_locked_assets_token_id: input_locked_assets_token_id.clone(),
+                input_locked_assets_token_nonce,
+                input_locked_assets_token_amount: input_locked_assets_token_amount.clone(),
+                asset_token_id: asset_token_id.clone(),
+                asset_token_amount: asset_token_amount.clone(),
+                penalty_amount: penalty_amount.clone(),
+                input_assets_attributes: input_assets_attributes.clone(),
+                block: self.blockchain().get_block_nonce(),
+                epoch,
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        )
+    }
+
+    fn emit_relock_assets_event(
+        self,
+        caller: &ManagedAddress,
+        input_locked_assets_token_id: &TokenIdentifier,
+        input_locked_assets_token_nonce: u64,
+        input_locked_assets_token_amount: &BigUint,
+        output_locked_assets_token_id: &TokenIdentifier,
+        output_locked_assets_token_nonce: u64,
+        output_locked_assets_token_amount: &BigUint,
+        input_assets_attributes: &LockedAssetTokenAttributesEx<Self::Api>,
+        output_assets_attributes: &LockedAssetTokenAttributesEx<Self::Api>,
+    ) {
+        let epoch = self.blockchain().get_block_epoch();
+        self.relock_assets_event(
+            caller,
+            epoch,
+            &RelockAssetsEvent {
+                caller: caller.clone(),
+                input_locked_assets_token_id: input_locked_assets_token_id.clone(),
+                input_locked_assets_token_nonce,
+                input_locked_assets_token_amount: input_locked_assets_token_amount.clone(),
+                output_locked_assets_token_id: output_locked_assets_token_id.clone(),
+                output_locked_assets_token_nonce,
+                output_locked_assets_token_amount: output_locked_assets_token_amount.clone(),
+                input_assets_attributes: input_assets_attributes.clone(),
+                output_assets_attributes: output_assets_attributes.clone(),
+                block: self.blockchain().get_block_nonce(),
+                epoch,
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        )
+    }
+
+    fn emit_create_and_forward_linear_vesting_event(
+        self,
+        caller: &ManagedAddress,
+        destination: &ManagedAddress,
+        locked_asset_token_id: &TokenIdentifier,
+        locked_asset_token_nonce: u64,
+        locked_asset_token_amount: &BigUint,
+        locked_assets_attributes: &LinearVestingTokenAttributes,
+    ) {
+        let epoch = self.blockchain().get_block_epoch();
+        self.create_and_forward_linear_vesting_event(
+            caller,
+            destination,
+            epoch,
+            &CreateAndForwardLinearVestingEvent {
+                caller: caller.clone(),
+                destination: destination.clone(),
+                locked_asset_token_id: locked_asset_token_id.clone(),
+                locked_asset_token_nonce,
+                locked_asset_token_amount: locked_asset_token_amount.clone(),
+                locked_assets_attributes: *locked_assets_attributes,
+                block: self.blockchain().get_block_nonce(),
+                epoch,
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        )
+    }
+
+    fn emit_unlock_linear_vesting_assets_event(
+        self,
+        caller: &ManagedAddress,
+        locked_assets_token_id: &TokenIdentifier,
+        input_locked_assets_token_nonce: u64,
+        input_locked_assets_token_amount: &BigUint,
+        output_locked_assets_token_nonce: u64,
+        output_locked_assets_token_amount: &BigUint,
+        asset_token_id: &TokenIdentifier,
+        asset_token_amount: &BigUint,
+        input_assets_attributes: &LinearVestingTokenAttributes,
+        output_assets_attributes: &LinearVestingTokenAttributes,
+    ) {
+        let epoch = self.blockchain().get_block_epoch();
+        self.unlock_linear_vesting_assets_event(
+            caller,
+            epoch,
+            &UnlockLinearVestingAssetsEvent {
+                caller: caller.clone(),
+                locked_assets_token_id: locked_assets_token_id.clone(),
+                input_locked_assets_token_nonce,
+                input_locked_assets_token_amount: input_locked_assets_token_amount.clone(),
+                output_locked_assets_token_nonce,
+                output_locked_assets_token_amount: output_locked_assets_token_amount.clone(),
+                asset_token_id: asset_token_id.clone(),
+                asset_token_amount: asset_token_amount.clone(),
+                input_assets_attributes: *input_assets_attributes,
+                output_assets_attributes: *output_assets_attributes,
+                block: self.blockchain().get_block_nonce(),
+                epoch,
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        )
+    }
+
     #[event("create_and_forward")]
     fn create_and_forward_event(
         self,
@@ -120,4 +308,37 @@ pub trait EventsModule {
         #[indexed] epoch: u64,
         swap_event: &UnlockAssetsEvent<Self::Api>,
     );
+
+    #[event("early_unlock")]
+    fn early_unlock_event(
+        self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        early_unlock_event: &EarlyUnlockEvent<Self::Api>,
+    );
+
+    #[event("relock_assets")]
+    fn relock_assets_event(
+        self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        relock_event: &RelockAssetsEvent<Self::Api>,
+    );
+
+    #[event("create_and_forward_linear_vesting")]
+    fn create_and_forward_linear_vesting_event(
+        self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] destination: &ManagedAddress,
+        #[indexed] epoch: u64,
+        create_event: &CreateAndForwardLinearVestingEvent<Self::Api>,
+    );
+
+    #[event("unlock_linear_vesting_assets")]
+    fn unlock_linear_vesting_assets_event(
+        self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        unlock_event: &UnlockLinearVestingAssetsEvent<Self::Api>,
+    );
 }
@@ -3,8 +3,10 @@
 #![feature(exact_size_is_empty)]
 
 mod attr_ex_helper;
-mod cache;
+pub mod cache;
+pub mod early_unlock;
 mod events;
+pub mod linear_vesting;
 pub mod locked_asset;
 pub mod locked_asset_token_merge;
 
@@ -13,13 +15,13 @@ elrond_wasm::derive_imports!();
 
 const DEFAULT_TRANSFER_EXEC_GAS_LIMIT: u64 = 35000000;
 const ADDITIONAL_AMOUNT_TO_CREATE: u64 = 1;
-const EPOCHS_IN_MONTH: u64 = 30;
 
 use attr_ex_helper::PRECISION_EX_INCREASE;
 use common_structs::{
-    Epoch, LockedAssetTokenAttributesEx, Nonce, UnlockMilestone, UnlockMilestoneEx, UnlockPeriod,
-    UnlockScheduleEx,
+    Epoch, LinearVestingTokenAttributes, LockedAssetTokenAttributesEx, Nonce, UnlockMilestone,
+    UnlockMilestoneEx, UnlockPeriod, UnlockScheduleEx,
 };
+use locked_asset::LockedAssetUnlockInfo;
 
 #[elrond_wasm::contract]
 pub trait LockedAssetFactory:
@@ -30,6 +32,9 @@ pub trait LockedAssetFactory:
     + locked_asset_token_merge::LockedAssetTokenMergeModule
     + events::EventsModule
     + attr_ex_helper::AttrExHelper
+    + early_unlock::EarlyUnlockModule
+    + linear_vesting::LinearVestingModule
+    + token_metadata::TokenMetadataModule
 {
     #[init]
     fn init(
@@ -113,7 +118,8 @@ pub trait LockedAssetFactory:
             is_merged: false,
         };
 
-        let new_token = self.produce_tokens_and_send(&amount, &attr, &address, &OptionalArg::None);
+        let (new_token, new_attr) =
+            self.produce_tokens_and_send(&amount, &attr, &address, &OptionalArg::None);
 
         self.emit_create_and_forward_event(
             &caller,
@@ -121,12 +127,61 @@ pub trait LockedAssetFactory:
             &new_token.token_identifier,
             new_token.token_nonce,
             &new_token.amount,
-            &attr,
+            &new_attr,
             month_start_epoch,
         );
         new_token
     }
 
+    /// Same as `createAndForwardCustomPeriod`, but the assets unlock linearly between
+    /// the start and end epochs. Nothing unlocks before the cliff epoch, if one is given.
+    #[endpoint(createAndForwardLinearVesting)]
+    fn create_and_forward_linear_vesting(
+        &self,
+        amount: BigUint,
+        address: ManagedAddress,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+        #[var_args] opt_cliff_epoch: OptionalArg<Epoch>,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let caller = self.blockchain().get_caller();
+        require!(
+            self.whitelisted_contracts().contains(&caller),
+            "Permission denied"
+        );
+        require!(
+            !self.linear_vesting_token_id().is_empty(),
+            "Linear Vesting Token not registered"
+        );
+        require!(amount > 0, "Zero input amount");
+
+        let attr = LinearVestingTokenAttributes {
+            vesting_schedule: self.create_linear_vesting_schedule(
+                start_epoch,
+                end_epoch,
+                opt_cliff_epoch,
+            ),
+            is_merged: false,
+        };
+
+        let new_token = self.produce_linear_vesting_tokens_and_send(
+            &amount,
+            &attr,
+            &address,
+            &OptionalArg::None,
+        );
+
+        self.emit_create_and_forward_linear_vesting_event(
+            &caller,
+            &address,
+            &new_token.token_identifier,
+            new_token.token_nonce,
+            &new_token.amount,
+            &attr,
+        );
+        new_token
+    }
+
     #[endpoint(createAndForward)]
     fn create_and_forward(
         &self,
@@ -153,7 +208,7 @@ pub trait LockedAssetFactory:
             is_merged: false,
         };
 
-        let new_token =
+        let (new_token, new_attr) =
             self.produce_tokens_and_send(&amount, &attr, &address, &opt_accept_funds_func);
 
         self.emit_create_and_forward_event(
@@ -162,7 +217,7 @@ pub trait LockedAssetFactory:
             &new_token.token_identifier,
             new_token.token_nonce,
             &new_token.amount,
-            &attr,
+            &new_attr,
             start_epoch,
         );
         new_token
@@ -176,6 +231,11 @@ pub trait LockedAssetFactory:
         #[payment_amount] amount: BigUint,
         #[payment_nonce] token_nonce: Nonce,
     ) {
+        if self.is_linear_vesting_token(&token_id) {
+            self.unlock_linear_vesting_assets(&token_id, token_nonce, &amount);
+            return;
+        }
+
         let locked_token_id = self.locked_asset_token_id().get();
         require!(token_id == locked_token_id, "Bad payment token");
 
@@ -213,18 +273,20 @@ pub trait LockedAssetFactory:
                 month_start_epoch,
                 &unlock_schedule.unlock_milestones,
             );
-            output_locked_asset_attributes = LockedAssetTokenAttributesEx {
+            let new_attributes = LockedAssetTokenAttributesEx {
                 unlock_schedule: UnlockScheduleEx {
                     unlock_milestones: new_unlock_milestones,
                 },
                 is_merged: attributes.is_merged,
             };
-            output_locked_assets_token_amount = self.produce_tokens_and_send(
+            let (new_token, normalized_attributes) = self.produce_tokens_and_send(
                 &locked_remaining,
-                &output_locked_asset_attributes,
+                &new_attributes,
                 &caller,
                 &OptionalArg::None,
             );
+            output_locked_assets_token_amount = new_token;
+            output_locked_asset_attributes = normalized_attributes;
         }
 
         self.send()
@@ -245,6 +307,168 @@ pub trait LockedAssetFactory:
         );
     }
 
+    /// Burns the locked assets and sends back new ones following the given unlock period,
+    /// which starts in the current month. The new schedule can't unlock anything sooner
+    /// than the old one did, so the lock can only be extended.
+    #[payable("*")]
+    #[endpoint(relockAssets)]
+    fn relock_assets(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] token_nonce: Nonce,
+        #[payment_amount] amount: BigUint,
+        #[var_args] target_unlock_period: ManagedVarArgs<UnlockMilestone>,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let locked_token_id = self.locked_asset_token_id().get();
+        require!(token_id == locked_token_id, "Bad payment token");
+        require!(amount > 0u64, "Zero input amount");
+
+        let unlock_milestones = target_unlock_period.to_vec();
+        self.validate_unlock_milestones(&unlock_milestones);
+
+        let attributes = self.get_attributes_ex(&token_id, token_nonce);
+        let month_start_epoch = self.get_month_start_epoch(self.blockchain().get_block_epoch());
+        let new_unlock_schedule =
+            self.create_unlock_schedule(month_start_epoch, UnlockPeriod { unlock_milestones });
+        self.require_unlock_schedule_extended(
+            &attributes.unlock_schedule.unlock_milestones,
+            &new_unlock_schedule.unlock_milestones,
+        );
+
+        self.send()
+            .esdt_local_burn(&locked_token_id, token_nonce, &amount);
+
+        let new_attributes = LockedAssetTokenAttributesEx {
+            unlock_schedule: new_unlock_schedule,
+            is_merged: false,
+        };
+        let caller = self.blockchain().get_caller();
+        let (new_token, new_attributes) =
+            self.produce_tokens_and_send(&amount, &new_attributes, &caller, &OptionalArg::None);
+
+        self.emit_relock_assets_event(
+            &caller,
+            &token_id,
+            token_nonce,
+            &amount,
+            &new_token.token_identifier,
+            new_token.token_nonce,
+            &new_token.amount,
+            &attributes,
+            &new_attributes,
+        );
+        new_token
+    }
+
+    /// Unlock schedules are step functions, so comparing the unlocked percents
+    /// at each milestone epoch of both schedules is enough.
+    fn require_unlock_schedule_extended(
+        &self,
+        old_unlock_milestones: &ManagedVec<UnlockMilestoneEx>,
+        new_unlock_milestones: &ManagedVec<UnlockMilestoneEx>,
+    ) {
+        for milestone in old_unlock_milestones
+            .iter()
+            .chain(new_unlock_milestones.iter())
+        {
+            require!(
+                self.get_unlock_percent(milestone.unlock_epoch, new_unlock_milestones)
+                    <= self.get_unlock_percent(milestone.unlock_epoch, old_unlock_milestones),
+                "New schedule unlocks sooner"
+            );
+        }
+    }
+
+    /// Returns how much of the given locked assets can be unlocked right away,
+    /// when the next tranche becomes unlockable and the release table of their schedule.
+    #[view(getUnlockInfo)]
+    fn get_unlock_info(
+        &self,
+        token_nonce: Nonce,
+        amount: BigUint,
+    ) -> LockedAssetUnlockInfo<Self::Api> {
+        let current_epoch = self.blockchain().get_block_epoch();
+        let token_id = self.locked_asset_token_id().get();
+        let attributes = self.get_attributes_ex(&token_id, token_nonce);
+        let unlock_milestones = &attributes.unlock_schedule.unlock_milestones;
+
+        let month_start_epoch = self.get_month_start_epoch(current_epoch);
+        let unlockable_amount =
+            self.get_unlock_amount(&amount, month_start_epoch, unlock_milestones);
+        let release_table = self.get_release_table(&amount, unlock_milestones);
+
+        // Milestones become unlockable at the start of the month they fall into or after.
+        let mut next_unlock_epoch = None;
+        let mut next_unlock_amount = BigUint::zero();
+        for entry in release_table.iter() {
+            if entry.unlock_epoch <= month_start_epoch {
+                continue;
+            }
+
+            let unlockable_epoch = self.get_next_month_start_epoch(entry.unlock_epoch);
+            match next_unlock_epoch {
+                None => next_unlock_epoch = Some(unlockable_epoch),
+                Some(epoch) if epoch != unlockable_epoch => break,
+                Some(_) => {}
+            }
+            next_unlock_amount += &entry.amount;
+        }
+
+        LockedAssetUnlockInfo {
+            token_nonce,
+            amount,
+            unlockable_amount,
+            next_unlock_epoch,
+            next_unlock_amount,
+            release_table,
+        }
+    }
+
+    /// Same as `getUnlockInfo`, for linear vesting tokens.
+    #[view(getLinearVestingUnlockInfo)]
+    fn get_linear_vesting_unlock_info_view(
+        &self,
+        token_nonce: Nonce,
+        amount: BigUint,
+    ) -> LockedAssetUnlockInfo<Self::Api> {
+        let current_epoch = self.blockchain().get_block_epoch();
+        self.get_linear_vesting_unlock_info(token_nonce, amount, current_epoch)
+    }
+
+    #[view(getUnlockInfoBatch)]
+    fn get_unlock_info_batch(
+        &self,
+        #[var_args] locked_assets: ManagedVarArgs<MultiArg2<Nonce, BigUint>>,
+    ) -> ManagedMultiResultVec<LockedAssetUnlockInfo<Self::Api>> {
+        let mut result = ManagedMultiResultVec::new();
+        for locked_asset in locked_assets.into_iter() {
+            let (token_nonce, amount) = locked_asset.into_tuple();
+            result.push(self.get_unlock_info(token_nonce, amount));
+        }
+        result
+    }
+
+    /// Drops the cached schedules that can't be matched anymore, see `CacheModule`.
+    #[only_owner]
+    #[endpoint(pruneUnlockScheduleCache)]
+    fn prune_unlock_schedule_cache(&self, max_entries: usize) -> usize {
+        let month_start_epoch = self.get_month_start_epoch(self.blockchain().get_block_epoch());
+        self.prune_stale_cache_entries(month_start_epoch, max_entries)
+    }
+
+    /// Returns the number of cached unlock schedules, how many of them are stale
+    /// and can be pruned, and the number of cached linear vesting schedules.
+    #[view(getCacheStats)]
+    fn get_cache_stats(&self) -> MultiResult3<usize, usize, usize> {
+        let month_start_epoch = self.get_month_start_epoch(self.blockchain().get_block_epoch());
+        (
+            self.nonce_cache_ex().len(),
+            self.count_stale_cache_entries(month_start_epoch),
+            self.nonce_cache_linear().len(),
+        )
+            .into()
+    }
+
     #[only_owner]
     #[endpoint(setUnlockPeriod)]
     fn set_unlock_period(&self, #[var_args] milestones: ManagedVarArgs<UnlockMilestone>) {
@@ -254,17 +478,25 @@ pub trait LockedAssetFactory:
             .set(&UnlockPeriod { unlock_milestones });
     }
 
-    fn get_month_start_epoch(&self, epoch: Epoch) -> Epoch {
-        epoch - (epoch - self.init_epoch().get()) % EPOCHS_IN_MONTH
-    }
-
+    /// The unlock schedule is normalized before the lookup, so the attributes
+    /// of the sent tokens are returned along with them.
     fn produce_tokens_and_send(
         &self,
         amount: &BigUint,
         attributes: &LockedAssetTokenAttributesEx<Self::Api>,
         address: &ManagedAddress,
         opt_accept_funds_func: &OptionalArg<ManagedBuffer>,
-    ) -> EsdtTokenPayment<Self::Api> {
+    ) -> (
+        EsdtTokenPayment<Self::Api>,
+        LockedAssetTokenAttributesEx<Self::Api>,
+    ) {
+        let month_start_epoch = self.get_month_start_epoch(self.blockchain().get_block_epoch());
+        let attributes = LockedAssetTokenAttributesEx {
+            unlock_schedule: self
+                .normalize_unlock_schedule(&attributes.unlock_schedule, month_start_epoch),
+            is_merged: attributes.is_merged,
+        };
+
         let result = self.get_sft_nonce_for_unlock_schedule(&attributes.unlock_schedule);
         let sent_nonce = match result {
             Option::Some(cached_nonce) => {
@@ -289,7 +521,7 @@ pub trait LockedAssetFactory:
                     amount,
                     &additional_amount_to_create,
                     address,
-                    attributes,
+                    &attributes,
                     opt_accept_funds_func,
                 );
 
@@ -301,7 +533,10 @@ pub trait LockedAssetFactory:
         };
 
         let token_id = self.locked_asset_token_id().get();
-        self.create_payment(&token_id, sent_nonce, amount)
+        (
+            self.create_payment(&token_id, sent_nonce, amount),
+            attributes,
+        )
     }
 
     #[only_owner]
@@ -364,6 +599,69 @@ pub trait LockedAssetFactory:
         };
     }
 
+    #[only_owner]
+    #[payable("EGLD")]
+    #[endpoint(registerLinearVestingToken)]
+    fn register_linear_vesting_token(
+        &self,
+        #[payment_amount] register_cost: BigUint,
+        token_display_name: ManagedBuffer,
+        token_ticker: ManagedBuffer,
+        num_decimals: usize,
+    ) -> AsyncCall {
+        require!(
+            self.linear_vesting_token_id().is_empty(),
+            "Token exists already"
+        );
+
+        self.send()
+            .esdt_system_sc_proxy()
+            .register_meta_esdt(
+                register_cost,
+                &token_display_name,
+                &token_ticker,
+                MetaTokenProperties {
+                    num_decimals,
+                    can_add_special_roles: true,
+                    can_change_owner: false,
+                    can_freeze: false,
+                    can_pause: false,
+                    can_upgrade: true,
+                    can_wipe: false,
+                },
+            )
+            .async_call()
+            .with_callback(self.callbacks().register_linear_vesting_callback())
+    }
+
+    #[callback]
+    fn register_linear_vesting_callback(
+        &self,
+        #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(token_id) => {
+                self.last_error_message().clear();
+
+                if self.linear_vesting_token_id().is_empty() {
+                    self.linear_vesting_token_id().set(&token_id);
+                }
+            }
+            ManagedAsyncCallResult::Err(message) => {
+                self.last_error_message().set(&message.err_msg);
+
+                let (payment, token_id) = self.call_value().payment_token_pair();
+                self.send().direct(
+                    &self.blockchain().get_owner_address(),
+                    &token_id,
+                    0,
+                    &payment,
+                    &[],
+                );
+            }
+        };
+    }
+
     #[only_owner]
     #[endpoint(setLocalRolesLockedAssetToken)]
     fn set_local_roles_locked_asset_token(
@@ -387,6 +685,29 @@ pub trait LockedAssetFactory:
             .with_callback(self.callbacks().change_roles_callback())
     }
 
+    #[only_owner]
+    #[endpoint(setLocalRolesLinearVestingToken)]
+    fn set_local_roles_linear_vesting_token(
+        &self,
+        address: ManagedAddress,
+        #[var_args] roles: ManagedVarArgs<EsdtLocalRole>,
+    ) -> AsyncCall {
+        require!(
+            !self.linear_vesting_token_id().is_empty(),
+            "Linear Vesting Token not registered"
+        );
+
+        self.send()
+            .esdt_system_sc_proxy()
+            .set_special_roles(
+                &address,
+                &self.linear_vesting_token_id().get(),
+                roles.into_iter(),
+            )
+            .async_call()
+            .with_callback(self.callbacks().change_roles_callback())
+    }
+
     #[callback]
     fn change_roles_callback(&self, #[call_result] result: ManagedAsyncCallResult<()>) {
         match result {
@@ -399,6 +720,88 @@ pub trait LockedAssetFactory:
         }
     }
 
+    /// Once any address holds the transfer role, the locked asset token can only be
+    /// transferred from or to role holders. The factory and the contracts handling
+    /// locked assets (proxy_dex, farms, distribution) are meant to hold it, so the token
+    /// can't be moved between users anymore.
+    /// The factory must be the first holder, so it can always send the tokens it creates.
+    #[only_owner]
+    #[endpoint(setTransferRoleLockedAssetToken)]
+    fn set_transfer_role_locked_asset_token(&self, address: ManagedAddress) -> AsyncCall {
+        require!(
+            !self.locked_asset_token_id().is_empty(),
+            "Locked Asset Token not registered"
+        );
+        require!(
+            self.blockchain().is_smart_contract(&address),
+            "Only contracts can hold the transfer role"
+        );
+        require!(
+            !self.transfer_role_holders().is_empty()
+                || address == self.blockchain().get_sc_address(),
+            "The factory must hold the transfer role first"
+        );
+
+        let roles = [EsdtLocalRole::Transfer];
+        self.send()
+            .esdt_system_sc_proxy()
+            .set_special_roles(
+                &address,
+                &self.locked_asset_token_id().get(),
+                roles.iter().cloned(),
+            )
+            .async_call()
+            .with_callback(self.callbacks().transfer_role_callback(address, true))
+    }
+
+    #[only_owner]
+    #[endpoint(unsetTransferRoleLockedAssetToken)]
+    fn unset_transfer_role_locked_asset_token(&self, address: ManagedAddress) -> AsyncCall {
+        require!(
+            self.transfer_role_holders().contains(&address),
+            "Address does not hold the transfer role"
+        );
+        require!(
+            address != self.blockchain().get_sc_address()
+                || self.transfer_role_holders().len() == 1,
+            "The factory must hold the transfer role while other contracts do"
+        );
+
+        let roles = [EsdtLocalRole::Transfer];
+        self.send()
+            .esdt_system_sc_proxy()
+            .unset_special_roles(
+                &address,
+                &self.locked_asset_token_id().get(),
+                roles.iter().cloned(),
+            )
+            .async_call()
+            .with_callback(self.callbacks().transfer_role_callback(address, false))
+    }
+
+    #[callback]
+    fn transfer_role_callback(
+        &self,
+        address: ManagedAddress,
+        is_set: bool,
+        #[call_result] result: ManagedAsyncCallResult<()>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.last_error_message().clear();
+
+                if is_set {
+                    self.transfer_role_holders().insert(address);
+                } else {
+                    self.transfer_role_holders().remove(&address);
+                }
+            }
+            ManagedAsyncCallResult::Err(message) => {
+                self.last_error_message().set(&message.err_msg);
+            }
+        }
+    }
+
     fn create_unlock_schedule(
         &self,
         start_epoch: Epoch,
@@ -426,10 +829,6 @@ pub trait LockedAssetFactory:
     #[storage_mapper("last_error_message")]
     fn last_error_message(&self) -> SingleValueMapper<ManagedBuffer>;
 
-    #[view(getInitEpoch)]
-    #[storage_mapper("init_epoch")]
-    fn init_epoch(&self) -> SingleValueMapper<Epoch>;
-
     #[storage_mapper("whitelist")]
     fn whitelisted_contracts(&self) -> SetMapper<ManagedAddress>;
 
@@ -442,6 +841,20 @@ pub trait LockedAssetFactory:
         result
     }
 
+    #[storage_mapper("transfer_role_holders")]
+    fn transfer_role_holders(&self) -> SetMapper<ManagedAddress>;
+
+    /// The addresses holding the transfer role. If the list is not empty, every transfer
+    /// of the locked asset token must have one of them as sender or receiver.
+    #[view(getTransferRoleHolders)]
+    fn get_transfer_role_holders(&self) -> ManagedMultiResultVec<ManagedAddress> {
+        let mut result = ManagedMultiResultVec::new();
+        for address in self.transfer_role_holders().iter() {
+            result.push(address);
+        }
+        result
+    }
+
     #[view(getDefaultUnlockPeriod)]
     #[storage_mapper("default_unlock_period")]
     fn default_unlock_period(&self) -> SingleValueMapper<UnlockPeriod<Self::Api>>;
@@ -0,0 +1,303 @@
+elrond_wasm::imports!();
+
+use common_structs::{Epoch, LinearVestingSchedule, LinearVestingTokenAttributes, Nonce};
+
+use crate::attr_ex_helper;
+use crate::cache;
+use crate::events;
+use crate::locked_asset::{self, LockedAssetUnlockInfo, ReleaseTableEntry};
+use crate::ADDITIONAL_AMOUNT_TO_CREATE;
+
+#[elrond_wasm::module]
+pub trait LinearVestingModule:
+    locked_asset::LockedAssetModule
+    + cache::CacheModule
+    + token_send::TokenSendModule
+    + attr_ex_helper::AttrExHelper
+    + events::EventsModule
+    + token_metadata::TokenMetadataModule
+{
+    fn create_linear_vesting_schedule(
+        &self,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+        opt_cliff_epoch: OptionalArg<Epoch>,
+    ) -> LinearVestingSchedule {
+        require!(start_epoch < end_epoch, "Invalid vesting period");
+
+        let cliff_epoch = match opt_cliff_epoch {
+            OptionalArg::Some(cliff_epoch) => cliff_epoch,
+            OptionalArg::None => start_epoch,
+        };
+        require!(
+            start_epoch <= cliff_epoch && cliff_epoch <= end_epoch,
+            "Invalid cliff epoch"
+        );
+
+        LinearVestingSchedule {
+            start_epoch,
+            cliff_epoch,
+            end_epoch,
+        }
+    }
+
+    fn is_linear_vesting_token(&self, token_id: &TokenIdentifier) -> bool {
+        !self.linear_vesting_token_id().is_empty()
+            && token_id == &self.linear_vesting_token_id().get()
+    }
+
+    fn get_linear_vesting_attributes(
+        &self,
+        token_id: &TokenIdentifier,
+        token_nonce: Nonce,
+    ) -> LinearVestingTokenAttributes {
+        let token_info = self.blockchain().get_esdt_token_data(
+            &self.blockchain().get_sc_address(),
+            token_id,
+            token_nonce,
+        );
+
+        self.serializer()
+            .top_decode_from_managed_buffer::<LinearVestingTokenAttributes>(&token_info.attributes)
+    }
+
+    fn get_linear_vesting_unlock_amount(
+        &self,
+        amount: &BigUint,
+        current_epoch: Epoch,
+        vesting_schedule: &LinearVestingSchedule,
+    ) -> BigUint {
+        if current_epoch < vesting_schedule.cliff_epoch {
+            return BigUint::zero();
+        }
+        if current_epoch >= vesting_schedule.end_epoch {
+            return amount.clone();
+        }
+
+        amount * &BigUint::from(current_epoch - vesting_schedule.start_epoch)
+            / (vesting_schedule.end_epoch - vesting_schedule.start_epoch)
+    }
+
+    /// Linear vesting releases a tranche every epoch, so the release table only holds
+    /// the amount vested at the cliff and the remaining amount, fully vested at the end.
+    fn get_linear_vesting_unlock_info(
+        &self,
+        token_nonce: Nonce,
+        amount: BigUint,
+        current_epoch: Epoch,
+    ) -> LockedAssetUnlockInfo<Self::Api> {
+        let token_id = self.linear_vesting_token_id().get();
+        let vesting_schedule = self
+            .get_linear_vesting_attributes(&token_id, token_nonce)
+            .vesting_schedule;
+
+        let unlockable_amount =
+            self.get_linear_vesting_unlock_amount(&amount, current_epoch, &vesting_schedule);
+        let amount_at_cliff = self.get_linear_vesting_unlock_amount(
+            &amount,
+            vesting_schedule.cliff_epoch,
+            &vesting_schedule,
+        );
+
+        let mut release_table = ManagedVec::new();
+        if amount_at_cliff > 0u64 {
+            release_table.push(ReleaseTableEntry {
+                unlock_epoch: vesting_schedule.cliff_epoch,
+                amount: amount_at_cliff.clone(),
+            });
+        }
+        if amount > amount_at_cliff {
+            release_table.push(ReleaseTableEntry {
+                unlock_epoch: vesting_schedule.end_epoch,
+                amount: &amount - &amount_at_cliff,
+            });
+        }
+
+        let (next_unlock_epoch, next_unlock_amount) =
+            if current_epoch < vesting_schedule.cliff_epoch {
+                (Some(vesting_schedule.cliff_epoch), amount_at_cliff)
+            } else if current_epoch < vesting_schedule.end_epoch {
+                let next_epoch = current_epoch + 1;
+                let next_unlockable_amount =
+                    self.get_linear_vesting_unlock_amount(&amount, next_epoch, &vesting_schedule);
+                (
+                    Some(next_epoch),
+                    next_unlockable_amount - &unlockable_amount,
+                )
+            } else {
+                (None, BigUint::zero())
+            };
+
+        LockedAssetUnlockInfo {
+            token_nonce,
+            amount,
+            unlockable_amount,
+            next_unlock_epoch,
+            next_unlock_amount,
+            release_table,
+        }
+    }
+
+    fn unlock_linear_vesting_assets(
+        &self,
+        token_id: &TokenIdentifier,
+        token_nonce: Nonce,
+        amount: &BigUint,
+    ) {
+        let attributes = self.get_linear_vesting_attributes(token_id, token_nonce);
+        let vesting_schedule = &attributes.vesting_schedule;
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let unlock_amount =
+            self.get_linear_vesting_unlock_amount(amount, current_epoch, vesting_schedule);
+        require!(unlock_amount > 0u64, "Method called too soon");
+
+        let caller = self.blockchain().get_caller();
+        self.mint_and_send_assets(&caller, &unlock_amount);
+
+        // The remaining amount keeps vesting at the same pace, starting from now.
+        let mut output_attributes = attributes;
+        let mut output_locked_assets_token_amount =
+            self.create_payment(token_id, 0, &BigUint::zero());
+        let locked_remaining = amount - &unlock_amount;
+        if locked_remaining > 0u64 {
+            output_attributes.vesting_schedule = LinearVestingSchedule {
+                start_epoch: current_epoch,
+                cliff_epoch: current_epoch,
+                end_epoch: vesting_schedule.end_epoch,
+            };
+            output_locked_assets_token_amount = self.produce_linear_vesting_tokens_and_send(
+                &locked_remaining,
+                &output_attributes,
+                &caller,
+                &OptionalArg::None,
+            );
+        }
+
+        self.send().esdt_local_burn(token_id, token_nonce, amount);
+
+        self.emit_unlock_linear_vesting_assets_event(
+            &caller,
+            token_id,
+            token_nonce,
+            amount,
+            output_locked_assets_token_amount.token_nonce,
+            &output_locked_assets_token_amount.amount,
+            &self.asset_token_id().get(),
+            &unlock_amount,
+            &attributes,
+            &output_attributes,
+        );
+    }
+
+    fn produce_linear_vesting_tokens_and_send(
+        &self,
+        amount: &BigUint,
+        attributes: &LinearVestingTokenAttributes,
+        address: &ManagedAddress,
+        opt_accept_funds_func: &OptionalArg<ManagedBuffer>,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let token_id = self.linear_vesting_token_id().get();
+        let vesting_schedule = &attributes.vesting_schedule;
+
+        let sent_nonce = match self.get_sft_nonce_for_linear_vesting(vesting_schedule) {
+            Option::Some(cached_nonce) => {
+                self.send().esdt_local_mint(&token_id, cached_nonce, amount);
+                self.transfer_execute_custom(
+                    address,
+                    &token_id,
+                    cached_nonce,
+                    amount,
+                    opt_accept_funds_func,
+                );
+                cached_nonce
+            }
+            Option::None => {
+                let additional_amount_to_create = if attributes.is_merged {
+                    BigUint::zero()
+                } else {
+                    BigUint::from(ADDITIONAL_AMOUNT_TO_CREATE)
+                };
+
+                let new_nonce = self.create_position_tokens(
+                    &token_id,
+                    &(amount + &additional_amount_to_create),
+                    attributes,
+                    Some(vesting_schedule.start_epoch),
+                );
+                self.transfer_execute_custom(
+                    address,
+                    &token_id,
+                    new_nonce,
+                    amount,
+                    opt_accept_funds_func,
+                );
+
+                if !attributes.is_merged {
+                    self.cache_linear_vesting_and_nonce(vesting_schedule, new_nonce);
+                }
+                new_nonce
+            }
+        };
+
+        self.create_payment(&token_id, sent_nonce, amount)
+    }
+
+    /// Merged tokens vest between the latest start, cliff and end epochs of all schedules.
+    /// Each of them unlocks a smaller share at any epoch than the merged schedules did,
+    /// so merging never unlocks anything sooner.
+    fn get_merged_linear_vesting_amount_and_attributes(
+        &self,
+        payments: ManagedVecRefIterator<Self::Api, EsdtTokenPayment<Self::Api>>,
+    ) -> (BigUint, LinearVestingTokenAttributes) {
+        let linear_vesting_token_id = self.linear_vesting_token_id().get();
+        let mut sum_amount = BigUint::zero();
+        let mut merged_schedule = LinearVestingSchedule {
+            start_epoch: 0,
+            cliff_epoch: 0,
+            end_epoch: 0,
+        };
+        let mut last_attributes = None;
+        let mut tokens_count = 0usize;
+
+        for entry in payments {
+            require!(
+                entry.token_identifier == linear_vesting_token_id,
+                "Bad token id"
+            );
+
+            let attributes =
+                self.get_linear_vesting_attributes(&entry.token_identifier, entry.token_nonce);
+            let vesting_schedule = &attributes.vesting_schedule;
+            merged_schedule.start_epoch = merged_schedule
+                .start_epoch
+                .max(vesting_schedule.start_epoch);
+            merged_schedule.cliff_epoch = merged_schedule
+                .cliff_epoch
+                .max(vesting_schedule.cliff_epoch);
+            merged_schedule.end_epoch = merged_schedule.end_epoch.max(vesting_schedule.end_epoch);
+            sum_amount += &entry.amount;
+
+            last_attributes = Some(attributes);
+            tokens_count += 1;
+        }
+        require!(sum_amount != 0u64, "Sum cannot be zero");
+
+        if tokens_count == 1 {
+            return (sum_amount, last_attributes.unwrap());
+        }
+
+        let attributes = LinearVestingTokenAttributes {
+            vesting_schedule: merged_schedule,
+            is_merged: true,
+        };
+
+        (sum_amount, attributes)
+    }
+
+    /// Linear vesting tokens have their own token id, so the contracts accepting
+    /// locked asset tokens never get attributes they can't decode.
+    #[view(getLinearVestingTokenId)]
+    #[storage_mapper("linear_vesting_token_id")]
+    fn linear_vesting_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+}
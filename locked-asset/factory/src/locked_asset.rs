@@ -10,6 +10,7 @@ pub const TEN_THOUSAND: u64 = 10_000u64;
 pub const PERCENTAGE_TOTAL_EX: u64 = 100_000u64;
 pub const MAX_MILESTONES_IN_SCHEDULE: usize = 64;
 pub const DOUBLE_MAX_MILESTONES_IN_SCHEDULE: usize = 2 * MAX_MILESTONES_IN_SCHEDULE;
+pub const EPOCHS_IN_MONTH: u64 = 30;
 
 use core::fmt::Debug;
 
@@ -25,6 +26,22 @@ pub struct EpochAmountPair<M: ManagedTypeApi> {
     pub amount: BigUint<M>,
 }
 
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, ManagedVecItem, Clone)]
+pub struct ReleaseTableEntry<M: ManagedTypeApi> {
+    pub unlock_epoch: Epoch,
+    pub amount: BigUint<M>,
+}
+
+#[derive(TopEncode, TopDecode, TypeAbi)]
+pub struct LockedAssetUnlockInfo<M: ManagedTypeApi> {
+    pub token_nonce: Nonce,
+    pub amount: BigUint<M>,
+    pub unlockable_amount: BigUint<M>,
+    pub next_unlock_epoch: Option<Epoch>,
+    pub next_unlock_amount: BigUint<M>,
+    pub release_table: ManagedVec<M, ReleaseTableEntry<M>>,
+}
+
 impl<M> Debug for EpochAmountPair<M>
 where
     M: ManagedTypeApi,
@@ -107,6 +124,30 @@ pub trait LockedAssetModule: token_send::TokenSendModule + attr_ex_helper::AttrE
         unlock_percent
     }
 
+    /// Splits the amount between the milestones the same way successive unlocks do,
+    /// so the last milestone gets the rounding leftovers.
+    fn get_release_table(
+        &self,
+        amount: &BigUint,
+        unlock_milestones: &ManagedVec<UnlockMilestoneEx>,
+    ) -> ManagedVec<ReleaseTableEntry<Self::Api>> {
+        let mut release_table = ManagedVec::new();
+        let mut unlock_percent = 0u64;
+        let mut released_amount = BigUint::zero();
+
+        for milestone in unlock_milestones.iter() {
+            unlock_percent += milestone.unlock_percent;
+            let total_released = amount * &BigUint::from(unlock_percent) / PERCENTAGE_TOTAL_EX;
+            release_table.push(ReleaseTableEntry {
+                unlock_epoch: milestone.unlock_epoch,
+                amount: &total_released - &released_amount,
+            });
+            released_amount = total_released;
+        }
+
+        release_table
+    }
+
     fn create_new_unlock_milestones(
         &self,
         current_epoch: Epoch,
@@ -222,6 +263,23 @@ pub trait LockedAssetModule: token_send::TokenSendModule + attr_ex_helper::AttrE
         }
     }
 
+    fn get_month_start_epoch(&self, epoch: Epoch) -> Epoch {
+        epoch - (epoch - self.init_epoch().get()) % EPOCHS_IN_MONTH
+    }
+
+    fn get_next_month_start_epoch(&self, epoch: Epoch) -> Epoch {
+        let month_start_epoch = self.get_month_start_epoch(epoch);
+        if month_start_epoch == epoch {
+            epoch
+        } else {
+            month_start_epoch + EPOCHS_IN_MONTH
+        }
+    }
+
+    #[view(getInitEpoch)]
+    #[storage_mapper("init_epoch")]
+    fn init_epoch(&self) -> SingleValueMapper<Epoch>;
+
     #[view(getLockedAssetTokenId)]
     #[storage_mapper("locked_asset_token_id")]
     fn locked_asset_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
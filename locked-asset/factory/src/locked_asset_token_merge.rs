@@ -4,6 +4,7 @@ elrond_wasm::derive_imports!();
 use common_structs::*;
 
 use crate::attr_ex_helper::{self, PRECISION_EX_INCREASE};
+use crate::{cache, linear_vesting};
 
 use super::locked_asset;
 use super::locked_asset::{
@@ -17,6 +18,8 @@ pub trait LockedAssetTokenMergeModule:
     + token_send::TokenSendModule
     + token_merge::TokenMergeModule
     + attr_ex_helper::AttrExHelper
+    + cache::CacheModule
+    + linear_vesting::LinearVestingModule
 {
     #[payable("*")]
     #[endpoint(mergeLockedAssetTokens)]
@@ -29,6 +32,20 @@ pub trait LockedAssetTokenMergeModule:
         require!(!payments_vec.is_empty(), "Empty payment vec");
         let payments_iter = payments_vec.iter();
 
+        // Merging works on tokens of the same schedule kind only.
+        if self.is_linear_vesting_token(&payments_vec.get(0).token_identifier) {
+            let (amount, attrs) =
+                self.get_merged_linear_vesting_amount_and_attributes(payments_iter.clone());
+            self.burn_tokens_from_payments(payments_iter);
+
+            return self.produce_linear_vesting_tokens_and_send(
+                &amount,
+                &attrs,
+                &caller,
+                &opt_accept_funds_func,
+            );
+        }
+
         let (amount, attrs) =
             self.get_merged_locked_asset_token_amount_and_attributes(payments_iter.clone());
         let locked_asset_token = self.locked_asset_token_id().get();
@@ -1,12 +1,27 @@
-use common_structs::{LockedAssetTokenAttributesEx, UnlockMilestoneEx, UnlockScheduleEx};
-use elrond_wasm::types::{EsdtTokenPayment, EsdtTokenType, ManagedVec};
+use common_structs::{
+    LinearVestingSchedule, LinearVestingTokenAttributes, LockedAssetTokenAttributesEx,
+    UnlockMilestone, UnlockMilestoneEx, UnlockScheduleEx,
+};
+use elrond_wasm::contract_base::ContractBase;
+use elrond_wasm::types::{
+    Address, EsdtLocalRole, EsdtTokenPayment, EsdtTokenType, ManagedAsyncCallResult,
+    ManagedMultiResultVec, ManagedVec, OptionalArg,
+};
 use elrond_wasm_debug::testing_framework::BigUint;
-use elrond_wasm_debug::{managed_biguint, rust_biguint, testing_framework::*, DebugApi};
+use elrond_wasm_debug::tx_mock::{TxInputESDT, TxResult};
+use elrond_wasm_debug::{
+    managed_address, managed_biguint, managed_token_id, rust_biguint, testing_framework::*,
+    DebugApi,
+};
 
 const SC_WASM_PATH: &'static str = "output/factory.wasm";
 
+use factory::cache::*;
+use factory::early_unlock::*;
+use factory::linear_vesting::*;
 use factory::locked_asset::*;
 use factory::locked_asset_token_merge::*;
+use factory::LockedAssetFactory;
 
 #[test]
 fn test_unlock_100mil_1mil() {
@@ -776,3 +791,959 @@ fn test_aggregated_unlock_schedule_with_1_offset() {
         })
         .assert_ok();
 }
+
+#[test]
+fn test_early_unlock_penalty() {
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        None,
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 0,
+                    unlock_percent: 20_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 40_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 90,
+                    unlock_percent: 40_000,
+                },
+            ]);
+            let penalty_curve = ManagedVec::from(vec![
+                EarlyUnlockPenaltyTier {
+                    min_epochs_remaining: 1,
+                    penalty_percent: 1_000,
+                },
+                EarlyUnlockPenaltyTier {
+                    min_epochs_remaining: 60,
+                    penalty_percent: 5_000,
+                },
+            ]);
+
+            // 10% of the milestone unlocking in 20 epochs and 50% of the one in 80 epochs
+            let penalty = sc.compute_early_unlock_penalty(
+                &managed_biguint!(1_000_000),
+                10,
+                &unlock_milestones,
+                &penalty_curve,
+            );
+            assert_eq!(penalty, managed_biguint!(240_000));
+
+            // all milestones are unlockable, nothing is penalized
+            let penalty = sc.compute_early_unlock_penalty(
+                &managed_biguint!(1_000_000),
+                90,
+                &unlock_milestones,
+                &penalty_curve,
+            );
+            assert_eq!(penalty, managed_biguint!(0));
+
+            // the milestone at epoch 35 is past, but only unlockable from the month start at 60
+            let unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 50_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 35,
+                    unlock_percent: 50_000,
+                },
+            ]);
+            let penalty = sc.compute_early_unlock_penalty(
+                &managed_biguint!(1_000_000),
+                40,
+                &unlock_milestones,
+                &penalty_curve,
+            );
+            assert_eq!(penalty, managed_biguint!(50_000));
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_relock_schedule_extension() {
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        None,
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let old_unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 50_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 60,
+                    unlock_percent: 50_000,
+                },
+            ]);
+            let new_unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 60,
+                    unlock_percent: 50_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 90,
+                    unlock_percent: 50_000,
+                },
+            ]);
+
+            sc.require_unlock_schedule_extended(&old_unlock_milestones, &new_unlock_milestones);
+        })
+        .assert_ok();
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let old_unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 50_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 60,
+                    unlock_percent: 50_000,
+                },
+            ]);
+            let new_unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 60_000,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 90,
+                    unlock_percent: 40_000,
+                },
+            ]);
+
+            sc.require_unlock_schedule_extended(&old_unlock_milestones, &new_unlock_milestones);
+        })
+        .assert_user_error("New schedule unlocks sooner");
+}
+
+#[test]
+fn test_linear_vesting_unlock_amount() {
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        None,
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let vesting_schedule =
+                sc.create_linear_vesting_schedule(100, 200, OptionalArg::Some(125));
+            assert_eq!(
+                vesting_schedule,
+                LinearVestingSchedule {
+                    start_epoch: 100,
+                    cliff_epoch: 125,
+                    end_epoch: 200,
+                }
+            );
+
+            // nothing before the cliff, then everything vested since the start epoch
+            let amount = managed_biguint!(1_000_000);
+            assert_eq!(
+                sc.get_linear_vesting_unlock_amount(&amount, 120, &vesting_schedule),
+                managed_biguint!(0)
+            );
+            assert_eq!(
+                sc.get_linear_vesting_unlock_amount(&amount, 125, &vesting_schedule),
+                managed_biguint!(250_000)
+            );
+            assert_eq!(
+                sc.get_linear_vesting_unlock_amount(&amount, 160, &vesting_schedule),
+                managed_biguint!(600_000)
+            );
+            assert_eq!(
+                sc.get_linear_vesting_unlock_amount(&amount, 250, &vesting_schedule),
+                amount
+            );
+        })
+        .assert_ok();
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            sc.create_linear_vesting_schedule(100, 200, OptionalArg::Some(250));
+        })
+        .assert_user_error("Invalid cliff epoch");
+}
+
+#[test]
+fn test_release_table() {
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        None,
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let unlock_milestones = ManagedVec::from(vec![
+                UnlockMilestoneEx {
+                    unlock_epoch: 30,
+                    unlock_percent: 33_333,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 60,
+                    unlock_percent: 33_333,
+                },
+                UnlockMilestoneEx {
+                    unlock_epoch: 90,
+                    unlock_percent: 33_334,
+                },
+            ]);
+
+            let release_table = sc.get_release_table(&managed_biguint!(1_000), &unlock_milestones);
+            let expected = [(30, 333u64), (60, 333u64), (90, 334u64)];
+            assert_eq!(release_table.len(), expected.len());
+            for (entry, (unlock_epoch, amount)) in release_table.iter().zip(expected.iter()) {
+                assert_eq!(entry.unlock_epoch, *unlock_epoch);
+                assert_eq!(entry.amount, managed_biguint!(*amount));
+            }
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_normalize_unlock_schedule() {
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        None,
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let unlock_schedule = UnlockScheduleEx {
+                unlock_milestones: ManagedVec::from(vec![
+                    UnlockMilestoneEx {
+                        unlock_epoch: 0,
+                        unlock_percent: 20_000,
+                    },
+                    UnlockMilestoneEx {
+                        unlock_epoch: 30,
+                        unlock_percent: 30_000,
+                    },
+                    UnlockMilestoneEx {
+                        unlock_epoch: 90,
+                        unlock_percent: 50_000,
+                    },
+                ]),
+            };
+
+            // the elapsed milestones are merged at the month start
+            let normalized_schedule = sc.normalize_unlock_schedule(&unlock_schedule, 60);
+            assert_eq!(
+                normalized_schedule,
+                UnlockScheduleEx {
+                    unlock_milestones: ManagedVec::from(vec![
+                        UnlockMilestoneEx {
+                            unlock_epoch: 60,
+                            unlock_percent: 50_000,
+                        },
+                        UnlockMilestoneEx {
+                            unlock_epoch: 90,
+                            unlock_percent: 50_000,
+                        },
+                    ]),
+                }
+            );
+
+            assert!(sc.is_stale_unlock_schedule(&unlock_schedule, 60));
+            assert!(!sc.is_stale_unlock_schedule(&normalized_schedule, 60));
+            assert!(sc.is_stale_unlock_schedule(&normalized_schedule, 90));
+        })
+        .assert_ok();
+}
+
+fn set_transfer_role<FactoryObjBuilder>(
+    blockchain_wrapper: &mut BlockchainStateWrapper,
+    owner: &Address,
+    factory: &ContractObjWrapper<factory::ContractObj<DebugApi>, FactoryObjBuilder>,
+    address: &Address,
+    is_set: bool,
+) -> TxResult
+where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    blockchain_wrapper.execute_tx(owner, factory, &rust_biguint!(0), |sc| {
+        let _ = if is_set {
+            sc.set_transfer_role_locked_asset_token(managed_address!(address))
+        } else {
+            sc.unset_transfer_role_locked_asset_token(managed_address!(address))
+        };
+
+        StateChange::Commit
+    })
+}
+
+/// Simulates the successful answer of the system smart contract.
+fn transfer_role_callback<FactoryObjBuilder>(
+    blockchain_wrapper: &mut BlockchainStateWrapper,
+    owner: &Address,
+    factory: &ContractObjWrapper<factory::ContractObj<DebugApi>, FactoryObjBuilder>,
+    address: &Address,
+    is_set: bool,
+) where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    blockchain_wrapper
+        .execute_tx(owner, factory, &rust_biguint!(0), |sc| {
+            sc.transfer_role_callback(
+                managed_address!(address),
+                is_set,
+                ManagedAsyncCallResult::Ok(()),
+            );
+
+            StateChange::Commit
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_transfer_role_holders() {
+    let rust_zero = rust_biguint!(0u64);
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let owner = blockchain_wrapper.create_user_account(&rust_zero);
+    let factory = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner),
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+    let other_contract = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner),
+        factory::contract_obj,
+        SC_WASM_PATH,
+    );
+    let factory_address = factory.address_ref().clone();
+    let other_address = other_contract.address_ref().clone();
+
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &factory_address,
+        true,
+    )
+    .assert_user_error("Locked Asset Token not registered");
+
+    blockchain_wrapper
+        .execute_tx(&owner, &factory, &rust_zero, |sc| {
+            sc.locked_asset_token_id()
+                .set(&managed_token_id!(b"LKMEX-abcdef"));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    set_transfer_role(&mut blockchain_wrapper, &owner, &factory, &owner, true)
+        .assert_user_error("Only contracts can hold the transfer role");
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &other_address,
+        true,
+    )
+    .assert_user_error("The factory must hold the transfer role first");
+
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &factory_address,
+        true,
+    )
+    .assert_ok();
+    transfer_role_callback(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &factory_address,
+        true,
+    );
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &other_address,
+        true,
+    )
+    .assert_ok();
+    transfer_role_callback(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &other_address,
+        true,
+    );
+
+    blockchain_wrapper
+        .execute_query(&factory, |sc| {
+            let holders = sc.get_transfer_role_holders().to_vec();
+            assert_eq!(holders.len(), 2);
+            assert_eq!(holders.get(0), managed_address!(&factory_address));
+            assert_eq!(holders.get(1), managed_address!(&other_address));
+        })
+        .assert_ok();
+
+    set_transfer_role(&mut blockchain_wrapper, &owner, &factory, &owner, false)
+        .assert_user_error("Address does not hold the transfer role");
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &factory_address,
+        false,
+    )
+    .assert_user_error("The factory must hold the transfer role while other contracts do");
+
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &other_address,
+        false,
+    )
+    .assert_ok();
+    transfer_role_callback(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &other_address,
+        false,
+    );
+    set_transfer_role(
+        &mut blockchain_wrapper,
+        &owner,
+        &factory,
+        &factory_address,
+        false,
+    )
+    .assert_ok();
+}
+
+const MEX_TOKEN_ID: &[u8] = b"MEX-abcdef";
+const LOCKED_MEX_TOKEN_ID: &[u8] = b"LKMEX-abcdef";
+const LINEAR_VESTING_TOKEN_ID: &[u8] = b"LVMEX-abcdef";
+
+struct FactorySetup<FactoryObjBuilder>
+where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    pub blockchain_wrapper: BlockchainStateWrapper,
+    pub owner_address: Address,
+    pub user_address: Address,
+    pub factory_wrapper: ContractObjWrapper<factory::ContractObj<DebugApi>, FactoryObjBuilder>,
+}
+
+/// The owner is whitelisted, so it can create locked assets for the user.
+fn setup_factory<FactoryObjBuilder>(
+    factory_builder: FactoryObjBuilder,
+) -> FactorySetup<FactoryObjBuilder>
+where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let owner_address = blockchain_wrapper.create_user_account(&rust_zero);
+    let user_address = blockchain_wrapper.create_user_account(&rust_zero);
+    let factory_wrapper = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner_address),
+        factory_builder,
+        SC_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_tx(&owner_address, &factory_wrapper, &rust_zero, |sc| {
+            let default_unlock_period = ManagedMultiResultVec::from(ManagedVec::from(vec![
+                UnlockMilestone {
+                    unlock_epoch: 0,
+                    unlock_percent: 50,
+                },
+                UnlockMilestone {
+                    unlock_epoch: 30,
+                    unlock_percent: 50,
+                },
+            ]));
+            sc.init(managed_token_id!(MEX_TOKEN_ID), default_unlock_period);
+            sc.locked_asset_token_id()
+                .set(&managed_token_id!(LOCKED_MEX_TOKEN_ID));
+            sc.linear_vesting_token_id()
+                .set(&managed_token_id!(LINEAR_VESTING_TOKEN_ID));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    let owner = owner_address.clone();
+    blockchain_wrapper
+        .execute_tx(&owner_address, &factory_wrapper, &rust_zero, |sc| {
+            sc.whitelist(managed_address!(&owner));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    let locked_token_roles = [
+        EsdtLocalRole::NftCreate,
+        EsdtLocalRole::NftAddQuantity,
+        EsdtLocalRole::NftBurn,
+    ];
+    blockchain_wrapper.set_esdt_local_roles(
+        factory_wrapper.address_ref(),
+        LOCKED_MEX_TOKEN_ID,
+        &locked_token_roles[..],
+    );
+    blockchain_wrapper.set_esdt_local_roles(
+        factory_wrapper.address_ref(),
+        LINEAR_VESTING_TOKEN_ID,
+        &locked_token_roles[..],
+    );
+    blockchain_wrapper.set_esdt_local_roles(
+        factory_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &[EsdtLocalRole::Mint][..],
+    );
+
+    FactorySetup {
+        blockchain_wrapper,
+        owner_address,
+        user_address,
+        factory_wrapper,
+    }
+}
+
+#[test]
+fn test_linear_vesting_token() {
+    let rust_zero = rust_biguint!(0u64);
+    let mut setup = setup_factory(factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let user = setup.user_address.clone();
+
+    setup.blockchain_wrapper.set_block_epoch(10);
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.factory_wrapper, &rust_zero, |sc| {
+            let payment = sc.create_and_forward_linear_vesting(
+                managed_biguint!(1_000),
+                managed_address!(&user),
+                10,
+                110,
+                OptionalArg::None,
+            );
+            assert_eq!(
+                payment.token_identifier,
+                managed_token_id!(LINEAR_VESTING_TOKEN_ID)
+            );
+            assert_eq!(payment.token_nonce, 1);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_nft_balance(
+        &user,
+        LINEAR_VESTING_TOKEN_ID,
+        1,
+        &rust_biguint!(1_000),
+        &LinearVestingTokenAttributes {
+            vesting_schedule: LinearVestingSchedule {
+                start_epoch: 10,
+                cliff_epoch: 10,
+                end_epoch: 110,
+            },
+            is_merged: false,
+        },
+    );
+
+    // Linear vesting tokens are only accepted where their schedule kind is handled.
+    setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &user,
+            &setup.factory_wrapper,
+            LINEAR_VESTING_TOKEN_ID,
+            1,
+            &rust_biguint!(1_000),
+            |sc| {
+                let _ = sc.relock_assets(
+                    managed_token_id!(LINEAR_VESTING_TOKEN_ID),
+                    1,
+                    managed_biguint!(1_000),
+                    ManagedMultiResultVec::new(),
+                );
+
+                StateChange::Commit
+            },
+        )
+        .assert_user_error("Bad payment token");
+
+    setup.blockchain_wrapper.set_block_epoch(60);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let unlock_info = sc.get_linear_vesting_unlock_info_view(1, managed_biguint!(1_000));
+            assert_eq!(unlock_info.unlockable_amount, managed_biguint!(500));
+            assert_eq!(unlock_info.next_unlock_epoch, Some(61));
+            assert_eq!(unlock_info.next_unlock_amount, managed_biguint!(10));
+        })
+        .assert_ok();
+
+    setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &user,
+            &setup.factory_wrapper,
+            LINEAR_VESTING_TOKEN_ID,
+            1,
+            &rust_biguint!(1_000),
+            |sc| {
+                sc.unlock_assets(
+                    managed_token_id!(LINEAR_VESTING_TOKEN_ID),
+                    managed_biguint!(1_000),
+                    1,
+                );
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+    setup
+        .blockchain_wrapper
+        .check_esdt_balance(&user, MEX_TOKEN_ID, &rust_biguint!(500));
+    setup.blockchain_wrapper.check_nft_balance(
+        &user,
+        LINEAR_VESTING_TOKEN_ID,
+        2,
+        &rust_biguint!(500),
+        &LinearVestingTokenAttributes {
+            vesting_schedule: LinearVestingSchedule {
+                start_epoch: 60,
+                cliff_epoch: 60,
+                end_epoch: 110,
+            },
+            is_merged: false,
+        },
+    );
+}
+
+#[test]
+fn test_merge_linear_vesting_tokens_with_different_schedules() {
+    let rust_zero = rust_biguint!(0u64);
+    let mut setup = setup_factory(factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let user = setup.user_address.clone();
+
+    for &(start_epoch, end_epoch) in [(0u64, 10u64), (100, 200)].iter() {
+        setup
+            .blockchain_wrapper
+            .execute_tx(&owner, &setup.factory_wrapper, &rust_zero, |sc| {
+                let _ = sc.create_and_forward_linear_vesting(
+                    managed_biguint!(100),
+                    managed_address!(&user),
+                    start_epoch,
+                    end_epoch,
+                    OptionalArg::None,
+                );
+
+                StateChange::Commit
+            })
+            .assert_ok();
+    }
+
+    let transfers = vec![
+        TxInputESDT {
+            token_identifier: LINEAR_VESTING_TOKEN_ID.to_vec(),
+            nonce: 1,
+            value: rust_biguint!(100),
+        },
+        TxInputESDT {
+            token_identifier: LINEAR_VESTING_TOKEN_ID.to_vec(),
+            nonce: 2,
+            value: rust_biguint!(100),
+        },
+    ];
+    setup
+        .blockchain_wrapper
+        .execute_esdt_multi_transfer(&user, &setup.factory_wrapper, &transfers, |sc| {
+            let payment = sc.merge_locked_asset_tokens(OptionalArg::None);
+            assert_eq!(payment.token_nonce, 3);
+            assert_eq!(payment.amount, managed_biguint!(200));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    // The merged token vests no sooner than the later of the two schedules.
+    setup.blockchain_wrapper.check_nft_balance(
+        &user,
+        LINEAR_VESTING_TOKEN_ID,
+        3,
+        &rust_biguint!(200),
+        &LinearVestingTokenAttributes {
+            vesting_schedule: LinearVestingSchedule {
+                start_epoch: 100,
+                cliff_epoch: 100,
+                end_epoch: 200,
+            },
+            is_merged: true,
+        },
+    );
+
+    setup.blockchain_wrapper.set_block_epoch(100);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let unlock_info = sc.get_linear_vesting_unlock_info_view(3, managed_biguint!(200));
+            assert_eq!(unlock_info.unlockable_amount, managed_biguint!(0));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_epoch(150);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let unlock_info = sc.get_linear_vesting_unlock_info_view(3, managed_biguint!(200));
+            assert_eq!(unlock_info.unlockable_amount, managed_biguint!(100));
+        })
+        .assert_ok();
+}
+
+fn prune_unlock_schedule_cache<FactoryObjBuilder>(
+    setup: &mut FactorySetup<FactoryObjBuilder>,
+    max_entries: usize,
+    expected_pruned_entries: usize,
+    expected_cursor: u64,
+) where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let owner = setup.owner_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.factory_wrapper, &rust_biguint!(0), |sc| {
+            let pruned_entries = sc.prune_unlock_schedule_cache(max_entries);
+            assert_eq!(pruned_entries, expected_pruned_entries);
+            assert_eq!(sc.prune_cache_cursor().get(), expected_cursor);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_prune_unlock_schedule_cache() {
+    let rust_zero = rust_biguint!(0u64);
+    let mut setup = setup_factory(factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let user = setup.user_address.clone();
+
+    for &(epoch, expected_nonce) in [(0u64, 1u64), (30, 2)].iter() {
+        setup.blockchain_wrapper.set_block_epoch(epoch);
+        setup
+            .blockchain_wrapper
+            .execute_tx(&owner, &setup.factory_wrapper, &rust_zero, |sc| {
+                let payment = sc.create_and_forward(
+                    managed_biguint!(1_000),
+                    managed_address!(&user),
+                    epoch,
+                    OptionalArg::None,
+                );
+                assert_eq!(payment.token_nonce, expected_nonce);
+
+                StateChange::Commit
+            })
+            .assert_ok();
+    }
+
+    // Only the first schedule has a milestone before the current month start.
+    setup.blockchain_wrapper.set_block_epoch(45);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let (cache_size, stale_entries, _) = sc.get_cache_stats().into_tuple();
+            assert_eq!(cache_size, 2);
+            assert_eq!(stale_entries, 1);
+        })
+        .assert_ok();
+
+    prune_unlock_schedule_cache(&mut setup, 1, 1, 1);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let token_id = managed_token_id!(LOCKED_MEX_TOKEN_ID);
+            assert_eq!(
+                sc.blockchain().get_sc_balance(&token_id, 1),
+                managed_biguint!(0)
+            );
+            assert_eq!(
+                sc.blockchain().get_sc_balance(&token_id, 2),
+                managed_biguint!(1)
+            );
+        })
+        .assert_ok();
+
+    prune_unlock_schedule_cache(&mut setup, 1, 0, 2);
+    prune_unlock_schedule_cache(&mut setup, 1, 0, 0);
+
+    setup.blockchain_wrapper.set_block_epoch(60);
+    prune_unlock_schedule_cache(&mut setup, 5, 1, 0);
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            assert_eq!(sc.get_cache_size(), 0);
+        })
+        .assert_ok();
+}
+
+fn relock_assets<FactoryObjBuilder>(
+    setup: &mut FactorySetup<FactoryObjBuilder>,
+    token_nonce: u64,
+    amount: u64,
+    unlock_period: &[(u64, u8)],
+    expected_nonce: u64,
+) -> TxResult
+where
+    FactoryObjBuilder: 'static + Copy + Fn() -> factory::ContractObj<DebugApi>,
+{
+    let user = setup.user_address.clone();
+    setup.blockchain_wrapper.execute_esdt_transfer(
+        &user,
+        &setup.factory_wrapper,
+        LOCKED_MEX_TOKEN_ID,
+        token_nonce,
+        &rust_biguint!(amount),
+        |sc| {
+            let milestones = unlock_period
+                .iter()
+                .map(|&(unlock_epoch, unlock_percent)| UnlockMilestone {
+                    unlock_epoch,
+                    unlock_percent,
+                })
+                .collect::<Vec<_>>();
+            let new_token = sc.relock_assets(
+                managed_token_id!(LOCKED_MEX_TOKEN_ID),
+                token_nonce,
+                managed_biguint!(amount),
+                ManagedMultiResultVec::from(ManagedVec::from(milestones)),
+            );
+            assert_eq!(new_token.token_nonce, expected_nonce);
+            assert_eq!(new_token.amount, managed_biguint!(amount));
+
+            StateChange::Commit
+        },
+    )
+}
+
+#[test]
+fn test_relock_assets() {
+    let rust_zero = rust_biguint!(0u64);
+    let mut setup = setup_factory(factory::contract_obj);
+    let owner = setup.owner_address.clone();
+    let user = setup.user_address.clone();
+
+    setup
+        .blockchain_wrapper
+        .execute_tx(&owner, &setup.factory_wrapper, &rust_zero, |sc| {
+            let payment = sc.create_and_forward(
+                managed_biguint!(1_000),
+                managed_address!(&user),
+                0,
+                OptionalArg::None,
+            );
+            assert_eq!(payment.token_nonce, 1);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    relock_assets(&mut setup, 1, 1_000, &[(60, 50), (90, 50)], 2).assert_ok();
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let token_id = managed_token_id!(LOCKED_MEX_TOKEN_ID);
+            let user_address = managed_address!(&user);
+            assert_eq!(
+                sc.blockchain()
+                    .get_esdt_balance(&user_address, &token_id, 1),
+                managed_biguint!(0)
+            );
+            assert_eq!(
+                sc.blockchain()
+                    .get_esdt_balance(&user_address, &token_id, 2),
+                managed_biguint!(1_000)
+            );
+            // Only the extra unit minted for the cached nonce is left.
+            assert_eq!(
+                sc.blockchain().get_sc_balance(&token_id, 1),
+                managed_biguint!(1)
+            );
+
+            let unlock_schedule = sc.get_unlock_schedule_for_sft_nonce(2).unwrap();
+            let milestones = unlock_schedule.unlock_milestones;
+            assert_eq!(milestones.len(), 2);
+            assert_eq!(
+                milestones.get(0),
+                UnlockMilestoneEx {
+                    unlock_epoch: 60,
+                    unlock_percent: 50_000,
+                }
+            );
+            assert_eq!(
+                milestones.get(1),
+                UnlockMilestoneEx {
+                    unlock_epoch: 90,
+                    unlock_percent: 50_000,
+                }
+            );
+
+            let unlock_info = sc.get_unlock_info(2, managed_biguint!(1_000));
+            assert_eq!(unlock_info.unlockable_amount, managed_biguint!(0));
+            assert_eq!(unlock_info.next_unlock_epoch, Some(60));
+        })
+        .assert_ok();
+
+    relock_assets(&mut setup, 2, 1_000, &[(30, 100)], 3)
+        .assert_user_error("New schedule unlocks sooner");
+    relock_assets(&mut setup, 2, 400, &[(90, 100)], 3).assert_ok();
+    setup
+        .blockchain_wrapper
+        .execute_query(&setup.factory_wrapper, |sc| {
+            let token_id = managed_token_id!(LOCKED_MEX_TOKEN_ID);
+            let user_address = managed_address!(&user);
+            assert_eq!(
+                sc.blockchain()
+                    .get_esdt_balance(&user_address, &token_id, 2),
+                managed_biguint!(600)
+            );
+            assert_eq!(
+                sc.blockchain()
+                    .get_esdt_balance(&user_address, &token_id, 3),
+                managed_biguint!(400)
+            );
+        })
+        .assert_ok();
+}
@@ -10,24 +10,50 @@ elrond_wasm_node::wasm_endpoints! {
         callBack
         createAndForward
         createAndForwardCustomPeriod
+        createAndForwardLinearVesting
+        disableEarlyUnlock
         getAssetTokenId
         getCacheSize
+        getCacheStats
         getDefaultUnlockPeriod
+        getEarlyUnlockPenaltyCurve
+        getEarlyUnlockPenaltyTreasury
+        getEarlyUnlockQuote
         getExtendedAttributesActivationNonce
         getInitEpoch
         getLastErrorMessage
+        getLinearVestingForSFTNonce
+        getLinearVestingTokenId
+        getLinearVestingUnlockInfo
         getLockedAssetTokenId
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
+        getPruneCacheCursor
         getTransferExecGasLimit
+        getTransferRoleHolders
+        getUnlockInfo
+        getUnlockInfoBatch
         getUnlockScheduleForSFTNonce
         getWhitelistedContracts
         mergeLockedAssetTokens
+        pruneUnlockScheduleCache
+        registerLinearVestingToken
         registerLockedAssetToken
+        relockAssets
         removeWhitelist
+        setEarlyUnlockPenaltyCurve
+        setEarlyUnlockPenaltyTreasury
         setInitEpoch
+        setLocalRolesLinearVestingToken
         setLocalRolesLockedAssetToken
+        setPositionTokenMetadata
+        setTransferRoleLockedAssetToken
         setUnlockPeriod
         set_transfer_exec_gas_limit
         unlockAssets
+        unlockEarly
+        unsetTransferRoleLockedAssetToken
         whitelist
     )
 }
@@ -117,6 +117,22 @@ pub struct CompoundRewardsProxyEvent<M: ManagedTypeApi> {
     timestamp: u64,
 }
 
+#[derive(TopEncode)]
+pub struct SwapLockedAssetProxyEvent<M: ManagedTypeApi> {
+    caller: ManagedAddress<M>,
+    input_token_id: TokenIdentifier<M>,
+    input_token_nonce: u64,
+    input_token_amount: BigUint<M>,
+    output_token_id: TokenIdentifier<M>,
+    output_token_nonce: u64,
+    output_token_amount: BigUint<M>,
+    locked_assets_nonce: u64,
+    locked_assets_invested: BigUint<M>,
+    block: u64,
+    epoch: u64,
+    timestamp: u64,
+}
+
 #[elrond_wasm::module]
 pub trait EventsModule {
     fn emit_add_liquidity_proxy_event(
@@ -367,6 +383,41 @@ pub trait EventsModule {
         )
     }
 
+    fn emit_swap_locked_asset_proxy_event(
+        self,
+        caller: &ManagedAddress,
+        input_token_id: &TokenIdentifier,
+        input_token_nonce: u64,
+        input_token_amount: &BigUint,
+        output_token_id: &TokenIdentifier,
+        output_token_nonce: u64,
+        output_token_amount: &BigUint,
+        locked_assets_nonce: u64,
+        locked_assets_invested: &BigUint,
+    ) {
+        let epoch = self.blockchain().get_block_epoch();
+        self.swap_locked_asset_proxy_event(
+            input_token_id,
+            output_token_id,
+            caller,
+            epoch,
+            &SwapLockedAssetProxyEvent {
+                caller: caller.clone(),
+                input_token_id: input_token_id.clone(),
+                input_token_nonce,
+                input_token_amount: input_token_amount.clone(),
+                output_token_id: output_token_id.clone(),
+                output_token_nonce,
+                output_token_amount: output_token_amount.clone(),
+                locked_assets_nonce,
+                locked_assets_invested: locked_assets_invested.clone(),
+                block: self.blockchain().get_block_nonce(),
+                epoch,
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        )
+    }
+
     #[event("add_liquidity_proxy")]
     fn add_liquidity_proxy_event(
         self,
@@ -428,4 +479,14 @@ pub trait EventsModule {
         #[indexed] epoch: u64,
         compound_rewards_farm_proxy_event: &CompoundRewardsProxyEvent<Self::Api>,
     );
+
+    #[event("swap_locked_asset_proxy")]
+    fn swap_locked_asset_proxy_event(
+        self,
+        #[indexed] input_token: &TokenIdentifier,
+        #[indexed] output_token: &TokenIdentifier,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        swap_locked_asset_proxy_event: &SwapLockedAssetProxyEvent<Self::Api>,
+    );
 }
@@ -11,6 +11,7 @@ pub mod migration_from_v1_2;
 pub mod proxy_common;
 pub mod proxy_farm;
 mod proxy_pair;
+pub mod proxy_swap;
 mod wrapped_farm_token_merge;
 mod wrapped_lp_token_merge;
 
@@ -18,6 +19,7 @@ mod wrapped_lp_token_merge;
 pub enum RegisterRequestType {
     ProxyFarm,
     ProxyPair,
+    ProxySwap,
 }
 
 #[elrond_wasm::contract]
@@ -25,8 +27,10 @@ pub trait ProxyDexImpl:
     proxy_common::ProxyCommonModule
     + proxy_pair::ProxyPairModule
     + proxy_farm::ProxyFarmModule
+    + proxy_swap::ProxySwapModule
     + token_merge::TokenMergeModule
     + token_send::TokenSendModule
+    + token_metadata::TokenMetadataModule
     + wrapped_farm_token_merge::WrappedFarmTokenMerge
     + wrapped_lp_token_merge::WrappedLpTokenMerge
     + events::EventsModule
@@ -104,6 +108,29 @@ pub trait ProxyDexImpl:
         )
     }
 
+    #[only_owner]
+    #[payable("EGLD")]
+    #[endpoint(registerProxySwap)]
+    fn register_proxy_swap(
+        &self,
+        #[payment_amount] register_cost: BigUint,
+        token_display_name: ManagedBuffer,
+        token_ticker: ManagedBuffer,
+        num_decimals: usize,
+    ) -> AsyncCall {
+        require!(
+            self.wrapped_swap_token_id().is_empty(),
+            "Token exists already"
+        );
+        self.register_meta_esdt(
+            register_cost,
+            token_display_name,
+            token_ticker,
+            num_decimals,
+            RegisterRequestType::ProxySwap,
+        )
+    }
+
     fn register_meta_esdt(
         &self,
         register_cost: BigUint,
@@ -153,6 +180,11 @@ pub trait ProxyDexImpl:
                             self.wrapped_farm_token_id().set(&token_id);
                         }
                     }
+                    RegisterRequestType::ProxySwap => {
+                        if self.wrapped_swap_token_id().is_empty() {
+                            self.wrapped_swap_token_id().set(&token_id);
+                        }
+                    }
                 }
             }
             ManagedAsyncCallResult::Err(message) => {
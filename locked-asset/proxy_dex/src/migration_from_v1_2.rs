@@ -9,6 +9,8 @@ use super::proxy_pair;
 use super::wrapped_farm_token_merge;
 use super::wrapped_lp_token_merge;
 use crate::proxy_farm;
+use farm::ProxyTrait as _;
+use farm_migration::ProxyTrait as _;
 
 mod farm_v1_2_contract_proxy {
     elrond_wasm::imports!();
@@ -31,6 +33,7 @@ pub trait MigrationModule:
     + proxy_pair::ProxyPairModule
     + token_merge::TokenMergeModule
     + token_send::TokenSendModule
+    + token_metadata::TokenMetadataModule
     + wrapped_farm_token_merge::WrappedFarmTokenMerge
     + wrapped_lp_token_merge::WrappedLpTokenMerge
     + events::EventsModule
@@ -70,6 +73,77 @@ pub trait MigrationModule:
             .execute_on_dest_context_custom_range(|_, after| (after - 2, after))
             .into_tuple();
 
+        self.rewrap_migrated_farm_position(
+            payment_token_id,
+            payment_token_nonce,
+            payment_amount,
+            wrapped_farm_token_attrs,
+            new_pos,
+            reward,
+        );
+    }
+
+    /// Moves the farm position behind a wrapped farm token to the migration target
+    /// of its farm, using the farm's `migratePosition` endpoint. The target farm
+    /// has to be intermediated as well, for the new position to be usable.
+    #[payable("*")]
+    #[endpoint(migrateWrappedFarmPosition)]
+    fn migrate_wrapped_farm_position(
+        &self,
+        #[payment_token] payment_token_id: TokenIdentifier,
+        #[payment_nonce] payment_token_nonce: u64,
+        #[payment_amount] payment_amount: BigUint,
+        farm_address: ManagedAddress,
+    ) {
+        self.require_is_intermediated_farm(&farm_address);
+        self.require_wrapped_farm_token_id_not_empty();
+        self.require_wrapped_lp_token_id_not_empty();
+
+        let migration_target: ManagedAddress = self
+            .farm_contract_proxy(farm_address.clone())
+            .migration_target()
+            .execute_on_dest_context();
+        self.require_is_intermediated_farm(&migration_target);
+
+        require!(
+            payment_token_id == self.wrapped_farm_token_id().get(),
+            "Should only be used with wrapped farm tokens"
+        );
+        require!(payment_amount != 0u64, "Payment amount cannot be zero");
+
+        let wrapped_farm_token_attrs =
+            self.get_wrapped_farm_token_attributes(&payment_token_id, payment_token_nonce);
+        let farm_token_id = wrapped_farm_token_attrs.farm_token_id.clone();
+        let farm_token_nonce = wrapped_farm_token_attrs.farm_token_nonce;
+
+        let (new_pos, reward) = self
+            .farm_contract_proxy(farm_address)
+            .migrate_position(OptionalArg::Some(self.blockchain().get_sc_address()))
+            .add_token_transfer(farm_token_id, farm_token_nonce, payment_amount.clone())
+            .execute_on_dest_context_custom_range(|_, after| (after - 2, after))
+            .into_tuple();
+
+        self.rewrap_migrated_farm_position(
+            payment_token_id,
+            payment_token_nonce,
+            payment_amount,
+            wrapped_farm_token_attrs,
+            new_pos,
+            reward,
+        );
+    }
+
+    /// Burns the migrated wrapped farm tokens and sends the caller new ones, wrapping
+    /// the new farm position, along with the rewards paid by the old farm.
+    fn rewrap_migrated_farm_position(
+        &self,
+        payment_token_id: TokenIdentifier,
+        payment_token_nonce: u64,
+        payment_amount: BigUint,
+        wrapped_farm_token_attrs: WrappedFarmTokenAttributes<Self::Api>,
+        new_pos: EsdtTokenPayment<Self::Api>,
+        reward: EsdtTokenPayment<Self::Api>,
+    ) {
         // Burn the old proxy farm position
         self.send()
             .esdt_local_burn(&payment_token_id, payment_token_nonce, &payment_amount);
@@ -87,11 +161,12 @@ pub trait MigrationModule:
                 &wrapped_farm_token_attrs.farming_token_amount,
             ),
         };
-        let new_nonce = self.nft_create_tokens(&wrapped_farm_token, &new_pos.amount, &new_attrs);
+        let new_nonce =
+            self.create_position_tokens(&payment_token_id, &new_pos.amount, &new_attrs, None);
 
         let mut payments = ManagedVec::new();
         payments.push(EsdtTokenPayment::new(
-            wrapped_farm_token,
+            payment_token_id,
             new_nonce,
             new_pos.amount,
         ));
@@ -2,7 +2,9 @@ elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
 use common_structs::Nonce;
-use common_structs::{WrappedFarmTokenAttributes, WrappedLpTokenAttributes};
+use common_structs::{
+    WrappedFarmTokenAttributes, WrappedLpTokenAttributes, WrappedSwapTokenAttributes,
+};
 
 #[elrond_wasm::module]
 pub trait ProxyCommonModule: token_send::TokenSendModule {
@@ -34,6 +36,20 @@ pub trait ProxyCommonModule: token_send::TokenSendModule {
         token_info.decode_attributes_or_exit()
     }
 
+    fn get_wrapped_swap_token_attributes(
+        &self,
+        token_id: &TokenIdentifier,
+        token_nonce: Nonce,
+    ) -> WrappedSwapTokenAttributes<Self::Api> {
+        let token_info = self.blockchain().get_esdt_token_data(
+            &self.blockchain().get_sc_address(),
+            token_id,
+            token_nonce,
+        );
+
+        token_info.decode_attributes_or_exit()
+    }
+
     fn burn_payment_tokens(
         &self,
         payments: ManagedVecRefIterator<Self::Api, EsdtTokenPayment<Self::Api>>,
@@ -66,6 +82,10 @@ pub trait ProxyCommonModule: token_send::TokenSendModule {
     #[storage_mapper("wrapped_farm_token_id")]
     fn wrapped_farm_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
 
+    #[view(getWrappedSwapTokenId)]
+    #[storage_mapper("wrapped_swap_token_id")]
+    fn wrapped_swap_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
     #[storage_mapper("locked_asset_factory_address")]
     fn locked_asset_factory_address(&self) -> SingleValueMapper<ManagedAddress>;
 
@@ -0,0 +1,256 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use common_structs::{Nonce, WrappedSwapTokenAttributes};
+use pair::ProxyTrait as _;
+
+use super::events;
+use super::proxy_common;
+use super::proxy_pair;
+
+type SwapOperationType<M> =
+    MultiArg4<ManagedAddress<M>, ManagedBuffer<M>, TokenIdentifier<M>, BigUint<M>>;
+
+pub const SWAP_TOKENS_FIXED_INPUT_FUNC_NAME: &[u8] = b"swapTokensFixedInput";
+pub const SWAP_TOKENS_FIXED_OUTPUT_FUNC_NAME: &[u8] = b"swapTokensFixedOutput";
+
+#[elrond_wasm::module]
+pub trait ProxySwapModule:
+    proxy_common::ProxyCommonModule
+    + proxy_pair::ProxyPairModule
+    + token_merge::TokenMergeModule
+    + token_send::TokenSendModule
+    + token_metadata::TokenMetadataModule
+    + events::EventsModule
+{
+    /// Swaps locked assets through intermediated pairs without unlocking them. The locked
+    /// assets are kept and the same amount of assets is minted, as `addLiquidityProxy` does.
+    /// The swap operations follow the router ones. Only the first one can be a fixed output swap,
+    /// its unused input being sent back as it was received.
+    ///
+    /// The output is kept as well and a Wrapped Swap Token is sent instead, which keeps the
+    /// locked assets nonce and so the original unlock schedule. It can be swapped again the
+    /// same way, swapping it back to the asset returning the locked assets.
+    #[payable("*")]
+    #[endpoint(swapLockedAssetProxy)]
+    fn swap_locked_asset_proxy(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] token_nonce: Nonce,
+        #[payment_amount] amount: BigUint,
+        swap_operations: MultiArgVec<SwapOperationType<Self::Api>>,
+    ) -> EsdtTokenPayment<Self::Api> {
+        require!(!self.wrapped_swap_token_id().is_empty(), "Empty token id");
+        require!(token_nonce != 0, "Can only be called with an SFT");
+        require!(amount != 0u32, "Payment amount cannot be zero");
+        require!(!swap_operations.is_empty(), "Empty swap operations");
+
+        let asset_token_id = self.asset_token_id().get();
+        let locked_asset_token_id = self.locked_asset_token_id().get();
+        let wrapped_swap_token_id = self.wrapped_swap_token_id().get();
+
+        let (input_token_id, locked_assets_provided, locked_assets_nonce) =
+            if token_id == locked_asset_token_id {
+                self.send().esdt_local_mint(&asset_token_id, 0, &amount);
+                (asset_token_id.clone(), amount.clone(), token_nonce)
+            } else if token_id == wrapped_swap_token_id {
+                let attributes = self.get_wrapped_swap_token_attributes(&token_id, token_nonce);
+                let locked_assets_provided = self.rule_of_three_non_zero_result(
+                    &amount,
+                    &attributes.token_total_amount,
+                    &attributes.locked_assets_invested,
+                );
+                (
+                    attributes.token_id,
+                    locked_assets_provided,
+                    attributes.locked_assets_nonce,
+                )
+            } else {
+                sc_panic!("Bad payment token");
+            };
+
+        let (output_tokens, unused_amount) = self.swap_position_tokens(
+            EsdtTokenPayment::new(input_token_id, 0, amount.clone()),
+            swap_operations,
+        );
+        let used_amount = &amount - &unused_amount;
+        let locked_assets_invested =
+            self.rule_of_three_non_zero_result(&used_amount, &amount, &locked_assets_provided);
+
+        let caller = self.blockchain().get_caller();
+        if unused_amount > 0u32 {
+            if token_id == locked_asset_token_id {
+                self.send()
+                    .esdt_local_burn(&asset_token_id, 0, &unused_amount);
+            }
+            self.transfer_execute_custom(
+                &caller,
+                &token_id,
+                token_nonce,
+                &unused_amount,
+                &OptionalArg::None,
+            );
+        }
+        if token_id == wrapped_swap_token_id {
+            self.send()
+                .esdt_local_burn(&token_id, token_nonce, &used_amount);
+        }
+
+        let output_position = if output_tokens.token_identifier == asset_token_id {
+            self.return_swapped_locked_assets(
+                &caller,
+                &output_tokens.amount,
+                &locked_assets_invested,
+                locked_assets_nonce,
+            )
+        } else {
+            self.create_wrapped_swap_tokens_and_send(
+                &caller,
+                &output_tokens,
+                &locked_assets_invested,
+                locked_assets_nonce,
+            )
+        };
+
+        self.emit_swap_locked_asset_proxy_event(
+            &caller,
+            &token_id,
+            token_nonce,
+            &used_amount,
+            &output_position.token_identifier,
+            output_position.token_nonce,
+            &output_position.amount,
+            locked_assets_nonce,
+            &locked_assets_invested,
+        );
+
+        output_position
+    }
+
+    /// Returns the output of the swaps and the unused input of a first fixed output swap.
+    fn swap_position_tokens(
+        &self,
+        input_tokens: EsdtTokenPayment<Self::Api>,
+        swap_operations: MultiArgVec<SwapOperationType<Self::Api>>,
+    ) -> (EsdtTokenPayment<Self::Api>, BigUint) {
+        let swap_fixed_input_endpoint = ManagedBuffer::from(SWAP_TOKENS_FIXED_INPUT_FUNC_NAME);
+        let swap_fixed_output_endpoint = ManagedBuffer::from(SWAP_TOKENS_FIXED_OUTPUT_FUNC_NAME);
+
+        let mut last_payment = input_tokens;
+        let mut unused_amount = BigUint::zero();
+        for (index, entry) in swap_operations.into_vec().into_iter().enumerate() {
+            let (pair_address, function, token_wanted, amount_wanted) = entry.into_tuple();
+            self.require_is_intermediated_pair(&pair_address);
+
+            if function == swap_fixed_input_endpoint {
+                last_payment = self
+                    .pair_contract_proxy(pair_address)
+                    .swap_tokens_fixed_input(
+                        last_payment.token_identifier,
+                        0,
+                        last_payment.amount,
+                        token_wanted,
+                        amount_wanted,
+                        OptionalArg::None,
+                    )
+                    .execute_on_dest_context_custom_range(|_, after| (after - 1, after));
+            } else if function == swap_fixed_output_endpoint {
+                // The leftovers of later swaps could not be sent back locked.
+                require!(index == 0, "Fixed output only for the first swap");
+
+                let (payment, residuum) = self
+                    .pair_contract_proxy(pair_address)
+                    .swap_tokens_fixed_output(
+                        last_payment.token_identifier,
+                        0,
+                        last_payment.amount,
+                        token_wanted,
+                        amount_wanted,
+                        OptionalArg::None,
+                    )
+                    .execute_on_dest_context_custom_range(|_, after| (after - 2, after))
+                    .into_tuple();
+                last_payment = payment;
+                unused_amount = residuum.amount;
+            } else {
+                sc_panic!("Invalid function to call");
+            }
+        }
+
+        (last_payment, unused_amount)
+    }
+
+    fn create_wrapped_swap_tokens_and_send(
+        &self,
+        caller: &ManagedAddress,
+        swapped_tokens: &EsdtTokenPayment<Self::Api>,
+        locked_assets_invested: &BigUint,
+        locked_assets_nonce: Nonce,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let wrapped_swap_token_id = self.wrapped_swap_token_id().get();
+        let attributes = WrappedSwapTokenAttributes {
+            token_id: swapped_tokens.token_identifier.clone(),
+            token_total_amount: swapped_tokens.amount.clone(),
+            locked_assets_invested: locked_assets_invested.clone(),
+            locked_assets_nonce,
+        };
+
+        let new_nonce = self.create_position_tokens(
+            &wrapped_swap_token_id,
+            &swapped_tokens.amount,
+            &attributes,
+            None,
+        );
+        self.transfer_execute_custom(
+            caller,
+            &wrapped_swap_token_id,
+            new_nonce,
+            &swapped_tokens.amount,
+            &OptionalArg::None,
+        );
+
+        self.create_payment(&wrapped_swap_token_id, new_nonce, &swapped_tokens.amount)
+    }
+
+    /// Same as `removeLiquidityProxy`, the locked assets are sent back up to the amount
+    /// received from the swaps, any surplus being sent as assets.
+    fn return_swapped_locked_assets(
+        &self,
+        caller: &ManagedAddress,
+        assets_received: &BigUint,
+        locked_assets_invested: &BigUint,
+        locked_assets_nonce: Nonce,
+    ) -> EsdtTokenPayment<Self::Api> {
+        let locked_asset_token_id = self.locked_asset_token_id().get();
+        let asset_token_id = self.asset_token_id().get();
+
+        let locked_assets_to_send =
+            core::cmp::min(assets_received.clone(), locked_assets_invested.clone());
+        self.transfer_execute_custom(
+            caller,
+            &locked_asset_token_id,
+            locked_assets_nonce,
+            &locked_assets_to_send,
+            &OptionalArg::None,
+        );
+
+        if assets_received > locked_assets_invested {
+            let difference = assets_received - locked_assets_invested;
+            self.send()
+                .direct(caller, &asset_token_id, 0, &difference, &[]);
+        } else if assets_received < locked_assets_invested {
+            let difference = locked_assets_invested - assets_received;
+            self.send()
+                .esdt_local_burn(&locked_asset_token_id, locked_assets_nonce, &difference);
+        }
+
+        self.send()
+            .esdt_local_burn(&asset_token_id, 0, &locked_assets_to_send);
+
+        self.create_payment(
+            &locked_asset_token_id,
+            locked_assets_nonce,
+            &locked_assets_to_send,
+        )
+    }
+}
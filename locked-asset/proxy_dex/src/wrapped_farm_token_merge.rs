@@ -20,6 +20,7 @@ use farm::farm_token_merge::ProxyTrait as _;
 pub trait WrappedFarmTokenMerge:
     token_merge::TokenMergeModule
     + token_send::TokenSendModule
+    + token_metadata::TokenMetadataModule
     + proxy_common::ProxyCommonModule
     + wrapped_lp_token_merge::WrappedLpTokenMerge
 {
@@ -81,10 +82,11 @@ pub trait WrappedFarmTokenMerge:
             farming_token_amount: farming_token_amount.amount,
         };
 
-        let new_nonce = self.nft_create_tokens(
+        let new_nonce = self.create_position_tokens(
             &wrapped_farm_token_id,
             &merged_farm_token_amount.amount,
             &new_attrs,
+            None,
         );
         self.transfer_execute_custom(
             caller,
@@ -301,10 +303,11 @@ pub trait WrappedFarmTokenMerge:
             .get_merged_wrapped_lp_token_attributes(&lp_token_amount, &merged_locked_token_amount);
 
         let wrapped_lp_token_id = tokens.get(0).attributes.farming_token_id;
-        let new_nonce = self.nft_create_tokens(
+        let new_nonce = self.create_position_tokens(
             &wrapped_lp_token_id,
             &merged_wrapped_lp_token_amount,
             &attrs,
+            None,
         );
 
         for wrapped_lp_token in wrapped_lp_tokens.iter() {
@@ -11,7 +11,10 @@ use proxy_pair::WrappedLpToken;
 
 #[elrond_wasm::module]
 pub trait WrappedLpTokenMerge:
-    token_merge::TokenMergeModule + token_send::TokenSendModule + proxy_common::ProxyCommonModule
+    token_merge::TokenMergeModule
+    + token_send::TokenSendModule
+    + token_metadata::TokenMetadataModule
+    + proxy_common::ProxyCommonModule
 {
     #[payable("*")]
     #[endpoint(mergeWrappedLpTokens)]
@@ -63,8 +66,12 @@ pub trait WrappedLpTokenMerge:
             .get_merged_wrapped_lp_token_attributes(&lp_token_amount, &merged_locked_token_amount);
         self.burn_payment_tokens(payments);
 
-        let new_nonce =
-            self.nft_create_tokens(&wrapped_lp_token_id, &merged_wrapped_lp_amount, &attrs);
+        let new_nonce = self.create_position_tokens(
+            &wrapped_lp_token_id,
+            &merged_wrapped_lp_amount,
+            &attrs,
+            None,
+        );
 
         self.transfer_execute_custom(
             caller,
@@ -0,0 +1,536 @@
+use common_structs::{
+    LockedAssetTokenAttributesEx, UnlockMilestoneEx, UnlockScheduleEx, WrappedSwapTokenAttributes,
+};
+use elrond_wasm::types::{Address, EsdtLocalRole, ManagedVec, MultiArg4, MultiArgVec, OptionalArg};
+use elrond_wasm_debug::tx_mock::TxContextStack;
+use elrond_wasm_debug::{
+    managed_address, managed_biguint, managed_buffer, managed_token_id, rust_biguint,
+    testing_framework::*, DebugApi,
+};
+
+use pair::config::{ConfigModule, State};
+use pair::Pair;
+use proxy_dex::proxy_common::ProxyCommonModule;
+use proxy_dex::proxy_swap::ProxySwapModule;
+use proxy_dex::ProxyDexImpl;
+
+const PAIR_WASM_PATH: &'static str = "../../dex/pair/output/pair.wasm";
+const PROXY_DEX_WASM_PATH: &'static str = "output/proxy_dex.wasm";
+
+const MEX_TOKEN_ID: &[u8] = b"MEX-abcdef";
+const WEGLD_TOKEN_ID: &[u8] = b"WEGLD-abcdef";
+const USDC_TOKEN_ID: &[u8] = b"USDC-abcdef";
+const FIRST_LP_TOKEN_ID: &[u8] = b"LPTOK-abcdef";
+const SECOND_LP_TOKEN_ID: &[u8] = b"LPTOK-ghijkl";
+const LOCKED_MEX_TOKEN_ID: &[u8] = b"LKMEX-abcdef";
+const WRAPPED_SWAP_TOKEN_ID: &[u8] = b"WSWAP-abcdef";
+
+const SWAP_TOKENS_FIXED_INPUT: &[u8] = b"swapTokensFixedInput";
+const SWAP_TOKENS_FIXED_OUTPUT: &[u8] = b"swapTokensFixedOutput";
+
+const LOCKED_MEX_NONCE: u64 = 1;
+const USER_LOCKED_MEX_AMOUNT: u64 = 100_000;
+const PAIR_RESERVE: u64 = 1_000_000;
+
+#[allow(dead_code)] // owner_address is unused, at least for now
+struct ProxySwapSetup<PairObjBuilder, ProxyObjBuilder>
+where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+    ProxyObjBuilder: 'static + Copy + Fn() -> proxy_dex::ContractObj<DebugApi>,
+{
+    pub blockchain_wrapper: BlockchainStateWrapper,
+    pub owner_address: Address,
+    pub user_address: Address,
+    pub first_pair_wrapper: ContractObjWrapper<pair::ContractObj<DebugApi>, PairObjBuilder>,
+    pub second_pair_wrapper: ContractObjWrapper<pair::ContractObj<DebugApi>, PairObjBuilder>,
+    pub proxy_wrapper: ContractObjWrapper<proxy_dex::ContractObj<DebugApi>, ProxyObjBuilder>,
+}
+
+fn setup_pair<PairObjBuilder>(
+    blockchain_wrapper: &mut BlockchainStateWrapper,
+    owner_addr: &Address,
+    pair_builder: PairObjBuilder,
+    first_token_id: &[u8],
+    second_token_id: &[u8],
+    lp_token_id: &[u8],
+) -> ContractObjWrapper<pair::ContractObj<DebugApi>, PairObjBuilder>
+where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let pair_wrapper = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(owner_addr),
+        pair_builder,
+        PAIR_WASM_PATH,
+    );
+
+    blockchain_wrapper
+        .execute_tx(owner_addr, &pair_wrapper, &rust_zero, |sc| {
+            sc.init(
+                managed_token_id!(first_token_id),
+                managed_token_id!(second_token_id),
+                managed_address!(owner_addr),
+                managed_address!(owner_addr),
+                0,
+                0,
+                OptionalArg::None,
+            );
+
+            sc.lp_token_identifier()
+                .set(&managed_token_id!(lp_token_id));
+            sc.state().set(&State::Active);
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    set_pair_reserves(
+        blockchain_wrapper,
+        &pair_wrapper,
+        first_token_id,
+        PAIR_RESERVE,
+        second_token_id,
+        PAIR_RESERVE,
+    );
+
+    pair_wrapper
+}
+
+fn set_pair_reserves<PairObjBuilder>(
+    blockchain_wrapper: &mut BlockchainStateWrapper,
+    pair_wrapper: &ContractObjWrapper<pair::ContractObj<DebugApi>, PairObjBuilder>,
+    first_token_id: &[u8],
+    first_token_reserve: u64,
+    second_token_id: &[u8],
+    second_token_reserve: u64,
+) where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+{
+    let owner = blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    blockchain_wrapper
+        .execute_tx(&owner, pair_wrapper, &rust_biguint!(0), |sc| {
+            sc.pair_reserve(&managed_token_id!(first_token_id))
+                .set(&managed_biguint!(first_token_reserve));
+            sc.pair_reserve(&managed_token_id!(second_token_id))
+                .set(&managed_biguint!(second_token_reserve));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    blockchain_wrapper.set_esdt_balance(
+        pair_wrapper.address_ref(),
+        first_token_id,
+        &rust_biguint!(first_token_reserve),
+    );
+    blockchain_wrapper.set_esdt_balance(
+        pair_wrapper.address_ref(),
+        second_token_id,
+        &rust_biguint!(second_token_reserve),
+    );
+}
+
+fn locked_mex_attributes() -> LockedAssetTokenAttributesEx<DebugApi> {
+    LockedAssetTokenAttributesEx {
+        unlock_schedule: UnlockScheduleEx {
+            unlock_milestones: ManagedVec::from(vec![UnlockMilestoneEx {
+                unlock_epoch: 10,
+                unlock_percent: 100_000,
+            }]),
+        },
+        is_merged: false,
+    }
+}
+
+fn setup_proxy_swap<PairObjBuilder, ProxyObjBuilder>(
+    pair_builder: PairObjBuilder,
+    proxy_builder: ProxyObjBuilder,
+) -> ProxySwapSetup<PairObjBuilder, ProxyObjBuilder>
+where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+    ProxyObjBuilder: 'static + Copy + Fn() -> proxy_dex::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let mut blockchain_wrapper = BlockchainStateWrapper::new();
+    let owner_addr = blockchain_wrapper.create_user_account(&rust_zero);
+
+    let first_pair_wrapper = setup_pair(
+        &mut blockchain_wrapper,
+        &owner_addr,
+        pair_builder,
+        WEGLD_TOKEN_ID,
+        MEX_TOKEN_ID,
+        FIRST_LP_TOKEN_ID,
+    );
+    let second_pair_wrapper = setup_pair(
+        &mut blockchain_wrapper,
+        &owner_addr,
+        pair_builder,
+        WEGLD_TOKEN_ID,
+        USDC_TOKEN_ID,
+        SECOND_LP_TOKEN_ID,
+    );
+
+    let proxy_wrapper = blockchain_wrapper.create_sc_account(
+        &rust_zero,
+        Some(&owner_addr),
+        proxy_builder,
+        PROXY_DEX_WASM_PATH,
+    );
+    let first_pair = first_pair_wrapper.address_ref().clone();
+    let second_pair = second_pair_wrapper.address_ref().clone();
+    blockchain_wrapper
+        .execute_tx(&owner_addr, &proxy_wrapper, &rust_zero, |sc| {
+            sc.init(
+                managed_token_id!(MEX_TOKEN_ID),
+                managed_token_id!(LOCKED_MEX_TOKEN_ID),
+                managed_address!(&owner_addr),
+            );
+
+            sc.wrapped_swap_token_id()
+                .set(&managed_token_id!(WRAPPED_SWAP_TOKEN_ID));
+            sc.intermediated_pairs()
+                .insert(managed_address!(&first_pair));
+            sc.intermediated_pairs()
+                .insert(managed_address!(&second_pair));
+
+            StateChange::Commit
+        })
+        .assert_ok();
+
+    blockchain_wrapper.set_esdt_local_roles(
+        proxy_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &[EsdtLocalRole::Mint, EsdtLocalRole::Burn][..],
+    );
+    blockchain_wrapper.set_esdt_local_roles(
+        proxy_wrapper.address_ref(),
+        LOCKED_MEX_TOKEN_ID,
+        &[EsdtLocalRole::NftBurn][..],
+    );
+    blockchain_wrapper.set_esdt_local_roles(
+        proxy_wrapper.address_ref(),
+        WRAPPED_SWAP_TOKEN_ID,
+        &[
+            EsdtLocalRole::NftCreate,
+            EsdtLocalRole::NftAddQuantity,
+            EsdtLocalRole::NftBurn,
+        ][..],
+    );
+
+    let user_addr = blockchain_wrapper.create_user_account(&rust_zero);
+    let _ = DebugApi::dummy();
+    blockchain_wrapper.set_nft_balance(
+        &user_addr,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        &rust_biguint!(USER_LOCKED_MEX_AMOUNT),
+        &locked_mex_attributes(),
+    );
+    let _ = TxContextStack::static_pop();
+
+    ProxySwapSetup {
+        blockchain_wrapper,
+        owner_address: owner_addr,
+        user_address: user_addr,
+        first_pair_wrapper,
+        second_pair_wrapper,
+        proxy_wrapper,
+    }
+}
+
+fn swap_locked_asset_proxy<PairObjBuilder, ProxyObjBuilder>(
+    setup: &mut ProxySwapSetup<PairObjBuilder, ProxyObjBuilder>,
+    payment_token_id: &[u8],
+    payment_nonce: u64,
+    payment_amount: u64,
+    swap_operations: &[(&Address, &[u8], &[u8], u64)],
+    expected_output: (&[u8], u64, u64),
+) where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+    ProxyObjBuilder: 'static + Copy + Fn() -> proxy_dex::ContractObj<DebugApi>,
+{
+    let user = setup.user_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &user,
+            &setup.proxy_wrapper,
+            payment_token_id,
+            payment_nonce,
+            &rust_biguint!(payment_amount),
+            |sc| {
+                let mut operations = Vec::new();
+                for (pair_address, function, token_wanted, amount_wanted) in swap_operations {
+                    operations.push(MultiArg4::from((
+                        managed_address!(*pair_address),
+                        managed_buffer!(*function),
+                        managed_token_id!(*token_wanted),
+                        managed_biguint!(*amount_wanted),
+                    )));
+                }
+
+                let output = sc.swap_locked_asset_proxy(
+                    managed_token_id!(payment_token_id),
+                    payment_nonce,
+                    managed_biguint!(payment_amount),
+                    MultiArgVec(operations),
+                );
+
+                let (token_id, token_nonce, amount) = expected_output;
+                assert_eq!(output.token_identifier, managed_token_id!(token_id));
+                assert_eq!(output.token_nonce, token_nonce);
+                assert_eq!(output.amount, managed_biguint!(amount));
+
+                StateChange::Commit
+            },
+        )
+        .assert_ok();
+}
+
+fn check_wrapped_swap_token_balance<PairObjBuilder, ProxyObjBuilder>(
+    setup: &mut ProxySwapSetup<PairObjBuilder, ProxyObjBuilder>,
+    token_nonce: u64,
+    swapped_token_id: &[u8],
+    swapped_amount: u64,
+    locked_assets_invested: u64,
+) where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+    ProxyObjBuilder: 'static + Copy + Fn() -> proxy_dex::ContractObj<DebugApi>,
+{
+    let _ = DebugApi::dummy();
+    setup.blockchain_wrapper.check_nft_balance(
+        &setup.user_address,
+        WRAPPED_SWAP_TOKEN_ID,
+        token_nonce,
+        &rust_biguint!(swapped_amount),
+        &WrappedSwapTokenAttributes::<DebugApi> {
+            token_id: managed_token_id!(swapped_token_id),
+            token_total_amount: managed_biguint!(swapped_amount),
+            locked_assets_invested: managed_biguint!(locked_assets_invested),
+            locked_assets_nonce: LOCKED_MEX_NONCE,
+        },
+    );
+    let _ = TxContextStack::static_pop();
+}
+
+fn check_user_locked_mex_balance<PairObjBuilder, ProxyObjBuilder>(
+    setup: &mut ProxySwapSetup<PairObjBuilder, ProxyObjBuilder>,
+    expected_amount: u64,
+) where
+    PairObjBuilder: 'static + Copy + Fn() -> pair::ContractObj<DebugApi>,
+    ProxyObjBuilder: 'static + Copy + Fn() -> proxy_dex::ContractObj<DebugApi>,
+{
+    let _ = DebugApi::dummy();
+    setup.blockchain_wrapper.check_nft_balance(
+        &setup.user_address,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        &rust_biguint!(expected_amount),
+        &locked_mex_attributes(),
+    );
+    let _ = TxContextStack::static_pop();
+}
+
+#[test]
+fn test_proxy_swap_setup() {
+    let _ = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+}
+
+#[test]
+fn test_swap_locked_asset_fixed_input() {
+    let mut setup = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+    let first_pair = setup.first_pair_wrapper.address_ref().clone();
+
+    // 10_000 * 1_000_000 / 1_010_000
+    let expected_wegld_out = 9_900;
+    swap_locked_asset_proxy(
+        &mut setup,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        10_000,
+        &[(&first_pair, SWAP_TOKENS_FIXED_INPUT, WEGLD_TOKEN_ID, 1)],
+        (WRAPPED_SWAP_TOKEN_ID, 1, expected_wegld_out),
+    );
+
+    check_wrapped_swap_token_balance(&mut setup, 1, WEGLD_TOKEN_ID, expected_wegld_out, 10_000);
+    check_user_locked_mex_balance(&mut setup, USER_LOCKED_MEX_AMOUNT - 10_000);
+    setup.blockchain_wrapper.check_esdt_balance(
+        setup.proxy_wrapper.address_ref(),
+        WEGLD_TOKEN_ID,
+        &rust_biguint!(expected_wegld_out),
+    );
+}
+
+#[test]
+fn test_swap_locked_asset_fixed_output_residuum() {
+    let mut setup = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+    let first_pair = setup.first_pair_wrapper.address_ref().clone();
+
+    // 1_000_000 * 5_000 / 995_000 + 1 MEX are used, the rest is sent back locked
+    let expected_mex_used = 5_026;
+    swap_locked_asset_proxy(
+        &mut setup,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        10_000,
+        &[(&first_pair, SWAP_TOKENS_FIXED_OUTPUT, WEGLD_TOKEN_ID, 5_000)],
+        (WRAPPED_SWAP_TOKEN_ID, 1, 5_000),
+    );
+
+    check_wrapped_swap_token_balance(&mut setup, 1, WEGLD_TOKEN_ID, 5_000, expected_mex_used);
+    check_user_locked_mex_balance(&mut setup, USER_LOCKED_MEX_AMOUNT - expected_mex_used);
+    setup.blockchain_wrapper.check_esdt_balance(
+        setup.proxy_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &rust_biguint!(0),
+    );
+}
+
+#[test]
+fn test_swap_locked_asset_multi_hop() {
+    let mut setup = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+    let first_pair = setup.first_pair_wrapper.address_ref().clone();
+    let second_pair = setup.second_pair_wrapper.address_ref().clone();
+
+    // 9_900 WEGLD from the first pair, then 9_900 * 1_000_000 / 1_009_900 USDC
+    let expected_usdc_out = 9_802;
+    swap_locked_asset_proxy(
+        &mut setup,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        10_000,
+        &[
+            (&first_pair, SWAP_TOKENS_FIXED_INPUT, WEGLD_TOKEN_ID, 1),
+            (&second_pair, SWAP_TOKENS_FIXED_INPUT, USDC_TOKEN_ID, 1),
+        ],
+        (WRAPPED_SWAP_TOKEN_ID, 1, expected_usdc_out),
+    );
+
+    check_wrapped_swap_token_balance(&mut setup, 1, USDC_TOKEN_ID, expected_usdc_out, 10_000);
+
+    // the unused input of a later fixed output swap could not be sent back locked
+    let user = setup.user_address.clone();
+    setup
+        .blockchain_wrapper
+        .execute_esdt_transfer(
+            &user,
+            &setup.proxy_wrapper,
+            LOCKED_MEX_TOKEN_ID,
+            LOCKED_MEX_NONCE,
+            &rust_biguint!(10_000),
+            |sc| {
+                let operations = vec![
+                    MultiArg4::from((
+                        managed_address!(&first_pair),
+                        managed_buffer!(SWAP_TOKENS_FIXED_INPUT),
+                        managed_token_id!(WEGLD_TOKEN_ID),
+                        managed_biguint!(1),
+                    )),
+                    MultiArg4::from((
+                        managed_address!(&second_pair),
+                        managed_buffer!(SWAP_TOKENS_FIXED_OUTPUT),
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(1_000),
+                    )),
+                ];
+                let _ = sc.swap_locked_asset_proxy(
+                    managed_token_id!(LOCKED_MEX_TOKEN_ID),
+                    LOCKED_MEX_NONCE,
+                    managed_biguint!(10_000),
+                    MultiArgVec(operations),
+                );
+
+                StateChange::Revert
+            },
+        )
+        .assert_user_error("Fixed output only for the first swap");
+}
+
+#[test]
+fn test_swap_back_to_asset_burns_missing_locked_assets() {
+    let mut setup = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+    let first_pair = setup.first_pair_wrapper.address_ref().clone();
+
+    swap_locked_asset_proxy(
+        &mut setup,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        10_000,
+        &[(&first_pair, SWAP_TOKENS_FIXED_INPUT, WEGLD_TOKEN_ID, 1)],
+        (WRAPPED_SWAP_TOKEN_ID, 1, 9_900),
+    );
+
+    // 9_900 * 1_010_000 / 1_000_000 MEX, one less than the locked assets invested,
+    // which is burned instead of being sent back
+    let expected_mex_back = 9_999;
+    swap_locked_asset_proxy(
+        &mut setup,
+        WRAPPED_SWAP_TOKEN_ID,
+        1,
+        9_900,
+        &[(&first_pair, SWAP_TOKENS_FIXED_INPUT, MEX_TOKEN_ID, 1)],
+        (LOCKED_MEX_TOKEN_ID, LOCKED_MEX_NONCE, expected_mex_back),
+    );
+
+    check_user_locked_mex_balance(
+        &mut setup,
+        USER_LOCKED_MEX_AMOUNT - 10_000 + expected_mex_back,
+    );
+    let user = setup.user_address.clone();
+    setup
+        .blockchain_wrapper
+        .check_esdt_balance(&user, MEX_TOKEN_ID, &rust_biguint!(0));
+    setup.blockchain_wrapper.check_esdt_balance(
+        setup.proxy_wrapper.address_ref(),
+        MEX_TOKEN_ID,
+        &rust_biguint!(0),
+    );
+}
+
+#[test]
+fn test_swap_back_to_asset_sends_surplus() {
+    let mut setup = setup_proxy_swap(pair::contract_obj, proxy_dex::contract_obj);
+    let first_pair = setup.first_pair_wrapper.address_ref().clone();
+
+    swap_locked_asset_proxy(
+        &mut setup,
+        LOCKED_MEX_TOKEN_ID,
+        LOCKED_MEX_NONCE,
+        10_000,
+        &[(&first_pair, SWAP_TOKENS_FIXED_INPUT, WEGLD_TOKEN_ID, 1)],
+        (WRAPPED_SWAP_TOKEN_ID, 1, 9_900),
+    );
+
+    // WEGLD gets more expensive in the meantime
+    let first_pair_wrapper = &setup.first_pair_wrapper;
+    set_pair_reserves(
+        &mut setup.blockchain_wrapper,
+        first_pair_wrapper,
+        WEGLD_TOKEN_ID,
+        900_000,
+        MEX_TOKEN_ID,
+        1_200_000,
+    );
+
+    // 9_900 * 1_200_000 / 909_900 MEX, the locked assets invested are sent back
+    // and the surplus is sent unlocked
+    let expected_mex_back = 13_056;
+    swap_locked_asset_proxy(
+        &mut setup,
+        WRAPPED_SWAP_TOKEN_ID,
+        1,
+        9_900,
+        &[(&first_pair, SWAP_TOKENS_FIXED_INPUT, MEX_TOKEN_ID, 1)],
+        (LOCKED_MEX_TOKEN_ID, LOCKED_MEX_NONCE, 10_000),
+    );
+
+    check_user_locked_mex_balance(&mut setup, USER_LOCKED_MEX_AMOUNT);
+    let user = setup.user_address.clone();
+    setup.blockchain_wrapper.check_esdt_balance(
+        &user,
+        MEX_TOKEN_ID,
+        &rust_biguint!(expected_mex_back - 10_000),
+    );
+}
@@ -20,17 +20,25 @@ elrond_wasm_node::wasm_endpoints! {
         getIntermediatedPairs
         getLastErrorMessage
         getLockedAssetTokenId
+        getPositionTokenBaseUri
+        getPositionTokenDisplayName
+        getPositionTokenName
         getTransferExecGasLimit
         getWrappedFarmTokenId
         getWrappedLpTokenId
+        getWrappedSwapTokenId
         mergeWrappedFarmTokens
         mergeWrappedLpTokens
         migrateV1_2Position
+        migrateWrappedFarmPosition
         registerProxyFarm
         registerProxyPair
+        registerProxySwap
         removeIntermediatedFarm
         removeIntermediatedPair
         removeLiquidityProxy
         setLocalRoles
+        setPositionTokenMetadata
+        swapLockedAssetProxy
     )
 }